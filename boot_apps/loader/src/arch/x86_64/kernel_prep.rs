@@ -5,6 +5,7 @@
 //**************************************************************************************************
 
 use crate::arch::stall;
+use crate::error::Error;
 use core::mem;
 use elf;
 use kernel_interface::init;
@@ -33,10 +34,10 @@ unsafe fn kernel_jump() -> ! {
     stall();
 }
 
-pub fn check_headers(_: &elf::IdentityHeader, header: &elf::Header) {
-    assert_eq!(
-        header.machine,
-        elf::Machine::X86_64,
-        "Kernel is not x86_64."
-    );
+pub fn check_headers(_: &elf::IdentityHeader, header: &elf::Header) -> Result<(), Error> {
+    if header.machine != elf::Machine::X86_64 {
+        return Err(Error::WrongMachine(header.machine));
+    }
+
+    Ok(())
 }
@@ -8,3 +8,13 @@ pub mod kernel_prep;
 pub mod paging;
 
 pub use x86::stall;
+
+pub fn random_u64() -> Option<u64> {
+    unsafe {
+        if x86::random::is_rdrand_supported() {
+            x86::random::rdrand_u64()
+        } else {
+            None
+        }
+    }
+}
@@ -4,16 +4,25 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+pub use x86::paging::size_64::MapError;
+
 use core::convert::TryFrom;
 use core::mem;
 use uefi::memory;
 use uefi::memory::{MemoryPages, MemoryType};
 use x86::control_registers::size_64::cr3;
 use x86::control_registers::size_64::cr3::FlagsValue;
-use x86::paging::size_64::{MapType, Mapper, MapperInterface, Pml4Table};
+use x86::cpuid;
+use x86::msr::ia32_efer;
+use x86::paging;
+use x86::paging::size_64::{MapType, Mapper, MapperInterface, Pml4Table, Protection, RootTable};
 use x86::PhysicalAddress52;
 
-pub fn map(physical_memory: &mut [u8], virtual_memory: memory::Segment, page_count: usize) {
+pub fn map(
+    physical_memory: &mut [u8],
+    virtual_memory: memory::Segment,
+    page_count: usize,
+) -> Result<(), MapError> {
     unsafe {
         let allocator = &mut BootServicesInterface;
 
@@ -25,15 +34,71 @@ pub fn map(physical_memory: &mut [u8], virtual_memory: memory::Segment, page_cou
 
         let count = u64::try_from(page_count).unwrap();
 
-        mapper
-            .map_level_4(
-                table,
-                virtual_memory.start(),
-                physical_memory.as_ptr(),
-                MapType::Page4Kib,
-                count,
-            )
-            .expect("Failed to map memory.");
+        mapper.map_level_4(
+            table,
+            virtual_memory.start(),
+            physical_memory.as_ptr(),
+            MapType::Page4Kib,
+            count,
+        )
+    }
+}
+
+// Execute disable bits are reserved until they are enabled. Returns if they can be used.
+
+pub fn enable_no_execute() -> bool {
+    unsafe {
+        if !cpuid::leaf_80000001::read().execute_disable() {
+            return false;
+        }
+
+        let mut efer = ia32_efer::read();
+        efer.set_no_execute_enabled(true);
+        ia32_efer::write(efer);
+
+        true
+    }
+}
+
+// Changes the protection of mapped memory. Without execute disable bits every page is executable.
+
+pub fn protect(
+    virtual_memory: memory::Segment,
+    writable: bool,
+    executable: bool,
+) -> Result<(), MapError> {
+    unsafe {
+        let allocator = &mut BootServicesInterface;
+
+        let mut mapper = Mapper::new(allocator);
+
+        let table = cr3::read::<FlagsValue>()
+            .physical_address()
+            .as_mut_ptr::<Pml4Table>();
+
+        let protection = Protection {
+            writable,
+            executable: executable || !ia32_efer::read().no_execute_enabled(),
+            user_accessible: false,
+        };
+
+        let count = (virtual_memory.len() as u64) / paging::PAGE_4_KIB_SIZE_IN_BYTES;
+
+        mapper.protect(
+            RootTable::Pml4(table),
+            virtual_memory.start(),
+            MapType::Page4Kib,
+            count,
+            protection,
+        )?;
+
+        for i in 0..count {
+            paging::invalidate_page(
+                virtual_memory.start() as u64 + i * paging::PAGE_4_KIB_SIZE_IN_BYTES,
+            );
+        }
+
+        Ok(())
     }
 }
 
@@ -0,0 +1,342 @@
+//**************************************************************************************************
+// boot_config.rs                                                                                  *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str;
+use uefi::io::storage::Volume;
+use uefi::{system, Error};
+
+// The boot configuration lists the entries shown in the boot menu. Global settings come first and
+// every entry starts with its name in brackets. Lines starting with '#' are comments.
+//
+//     timeout = 5
+//     default = verdure
+//     fallback = safe
+//
+//     [verdure]
+//     title = Verdure OS
+//     kernel = boot\system\kernel
+//     initial = boot\initial
+//     command_line = log=debug
+//     resolution = 1280x720
+//
+//     [safe]
+//     title = Verdure OS (Safe Mode)
+//     command_line = nokaslr debug=on
+//     resolution = max
+//
+//     [shell]
+//     title = UEFI Shell
+//     application = EFI\tools\shell.efi
+//     command_line = -nostartup
+//
+// The timeout is in seconds up to an hour and the menu is skipped when it is 0. The default entry is the first
+// one unless set and the fallback entry is booted when the file of the selected one is missing.
+// Entries with an application start that EFI application from the boot volume instead of the
+// kernel and pass it the command line. The menu is shown again when the application exits.
+
+pub const CONFIG_PATH: &str = "boot\\boot.cfg";
+
+pub const DEFAULT_KERNEL_PATH: &str = "boot\\system\\kernel";
+
+pub const DEFAULT_RESOLUTION: Resolution = Resolution::Size {
+    width: 1280,
+    height: 720,
+};
+
+pub const DEFAULT_TIMEOUT: u32 = 5;
+
+// Longer timeouts are shortened to this.
+
+pub const MAX_TIMEOUT: u32 = 3600;
+
+// Time to read configuration errors before the screen is cleared.
+
+const ERROR_DELAY_MICROSECONDS: usize = 3_000_000;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub timeout: u32,
+    pub default: usize,
+    pub fallback: Option<usize>,
+    pub entries: Vec<Entry>,
+}
+
+impl Config {
+    // Reads the configuration from the boot volume. The built-in configuration is used if there
+    // is no file or it is invalid so the system can still boot.
+
+    pub fn load(volume: &Volume) -> Self {
+        let mut buffer = Vec::new();
+
+        let node = match volume.open_node(CONFIG_PATH, true, false) {
+            Ok(node) => node,
+            Err(Error::PathNonExistent(_)) => return Self::built_in(),
+            Err(error) => return Self::report_error(format_args!("{}", error)),
+        };
+
+        if let Err(error) = node.read_to_end(&mut buffer) {
+            return Self::report_error(format_args!("{}", error));
+        }
+
+        let text = match str::from_utf8(&buffer) {
+            Ok(text) => text,
+            Err(_) => return Self::report_error(format_args!("The file is not UTF-8.")),
+        };
+
+        match Self::parse(text) {
+            Ok(config) => config,
+            Err(error) => Self::report_error(format_args!("{}", error)),
+        }
+    }
+
+    // A single entry for the kernel at its default path that boots without a menu.
+
+    pub fn built_in() -> Self {
+        Self {
+            timeout: 0,
+            default: 0,
+            fallback: None,
+            entries: vec![Entry::new(String::from("default"))],
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let mut timeout = DEFAULT_TIMEOUT;
+        let mut default_name = None;
+        let mut fallback_name = None;
+        let mut entries = Vec::<Entry>::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let error = |kind| ConfigError { line_number, kind };
+
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                let name = name.trim();
+
+                if name.is_empty() {
+                    return Err(error(ConfigErrorKind::InvalidLine));
+                }
+
+                if entries.iter().any(|entry| entry.name == name) {
+                    return Err(error(ConfigErrorKind::DuplicateEntry(String::from(name))));
+                }
+
+                entries.push(Entry::new(String::from(name)));
+                continue;
+            }
+
+            let (key, value) = match line.find('=') {
+                Some(index) => (line[..index].trim(), line[index + 1..].trim()),
+                None => return Err(error(ConfigErrorKind::InvalidLine)),
+            };
+
+            let invalid_value = || error(ConfigErrorKind::InvalidValue(String::from(key)));
+
+            match (entries.last_mut(), key) {
+                (None, "timeout") => {
+                    timeout = value
+                        .parse::<u32>()
+                        .map_err(|_| invalid_value())?
+                        .min(MAX_TIMEOUT)
+                }
+                (None, "default") => default_name = Some(value),
+                (None, "fallback") => fallback_name = Some(value),
+                (Some(entry), "title") => entry.title = String::from(value),
+                (Some(entry), "kernel") => entry.kernel = parse_path(value),
+                (Some(entry), "application") => {
+                    entry.kind = EntryKind::Application(parse_path(value))
+                }
+                (Some(entry), "initial") => entry.initial = Some(parse_path(value)),
+                (Some(entry), "command_line") => entry.command_line = Some(String::from(value)),
+                (Some(entry), "resolution") => {
+                    entry.resolution = Resolution::parse(value).ok_or_else(invalid_value)?
+                }
+                _ => return Err(error(ConfigErrorKind::UnknownKey(String::from(key)))),
+            }
+        }
+
+        if entries.is_empty() {
+            return Err(ConfigError {
+                line_number: 0,
+                kind: ConfigErrorKind::NoEntries,
+            });
+        }
+
+        let find_entry = |name: &str| {
+            entries
+                .iter()
+                .position(|entry| entry.name == name)
+                .ok_or_else(|| ConfigError {
+                    line_number: 0,
+                    kind: ConfigErrorKind::UnknownEntry(String::from(name)),
+                })
+        };
+
+        let default = default_name.map_or(Ok(0), find_entry)?;
+        let fallback = fallback_name.map(find_entry).transpose()?;
+
+        Ok(Self {
+            timeout,
+            default,
+            fallback,
+            entries,
+        })
+    }
+
+    pub fn default_entry(&self) -> &Entry {
+        &self.entries[self.default]
+    }
+
+    pub fn fallback_entry(&self) -> Option<&Entry> {
+        self.fallback.map(|index| &self.entries[index])
+    }
+
+    fn report_error(args: fmt::Arguments) -> Self {
+        con_out_println!("Failed to load \"{}\". {}", CONFIG_PATH, args);
+        con_out_println!("Using the built-in boot entry.");
+
+        let _ = system::stall(ERROR_DELAY_MICROSECONDS);
+
+        Self::built_in()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub name: String,
+    pub title: String,
+    pub kind: EntryKind,
+    pub kernel: String,
+    pub initial: Option<String>,
+    // Options given to the loader itself still take priority over these.
+    pub command_line: Option<String>,
+    pub resolution: Resolution,
+}
+
+impl Entry {
+    pub fn new(name: String) -> Self {
+        Self {
+            title: name.clone(),
+            name,
+            kind: EntryKind::Kernel,
+            kernel: String::from(DEFAULT_KERNEL_PATH),
+            initial: None,
+            command_line: None,
+            resolution: DEFAULT_RESOLUTION,
+        }
+    }
+
+    // The file on the boot volume that this entry starts.
+
+    pub fn path(&self) -> &str {
+        match &self.kind {
+            EntryKind::Kernel => &self.kernel,
+            EntryKind::Application(path) => path,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EntryKind {
+    Kernel,
+    // Another EFI application like a shell, a firmware updater or an older loader.
+    Application(String),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Resolution {
+    // The closest mode with a framebuffer is used if there is no exact match.
+    Size { width: u32, height: u32 },
+    Maximum,
+}
+
+impl Resolution {
+    // Accepts "<width>x<height>" or "max".
+
+    pub fn parse(text: &str) -> Option<Self> {
+        if text.eq_ignore_ascii_case("max") {
+            return Some(Resolution::Maximum);
+        }
+
+        let index = text.find(|c| c == 'x' || c == 'X')?;
+        let width = text[..index].trim().parse().ok()?;
+        let height = text[index + 1..].trim().parse().ok()?;
+
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        Some(Resolution::Size { width, height })
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resolution::Size { width, height } => write!(f, "{}x{}", width, height),
+            Resolution::Maximum => write!(f, "maximum"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigError {
+    // 0 if the error is not specific to a line.
+    pub line_number: usize,
+    pub kind: ConfigErrorKind,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line_number != 0 {
+            write!(f, "Line {}: ", self.line_number)?;
+        }
+
+        match &self.kind {
+            ConfigErrorKind::InvalidLine => write!(f, "The line is not a setting or entry."),
+            ConfigErrorKind::UnknownKey(key) => write!(f, "The setting \"{}\" is unknown.", key),
+            ConfigErrorKind::InvalidValue(key) => {
+                write!(f, "The value of \"{}\" is invalid.", key)
+            }
+            ConfigErrorKind::DuplicateEntry(name) => {
+                write!(f, "The entry \"{}\" is declared more than once.", name)
+            }
+            ConfigErrorKind::UnknownEntry(name) => write!(f, "There is no entry \"{}\".", name),
+            ConfigErrorKind::NoEntries => write!(f, "There are no entries."),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConfigErrorKind {
+    InvalidLine,
+    UnknownKey(String),
+    InvalidValue(String),
+    DuplicateEntry(String),
+    UnknownEntry(String),
+    NoEntries,
+}
+
+// UEFI paths use backslashes but forward slashes are accepted for convenience.
+
+fn parse_path(value: &str) -> String {
+    value
+        .trim_start_matches(|c| c == '\\' || c == '/')
+        .replace('/', "\\")
+}
@@ -0,0 +1,53 @@
+//**************************************************************************************************
+// chain_load.rs                                                                                   *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::boot_config::Entry;
+use crate::error::Error;
+use alloc::format;
+use alloc::string::String;
+use uefi::image::{self, Image};
+use uefi::system;
+
+// Starts an EFI application from the boot volume and waits for it to exit. Returns an error if
+// the application could not be started or exited with an error.
+
+pub fn run(entry: &Entry, path: &str) -> Result<(), Error> {
+    let device_path = image::file_device_path(&format!("\\{}", path))
+        .map_err(|error| Error::Firmware("build the application path", error))?;
+
+    let mut application = Image::load(&device_path).map_err(|error| match error {
+        uefi::Error::PathNonExistent(_) => Error::FileNotFound(String::from(path)),
+        error => Error::ApplicationUnloadable(String::from(path), error),
+    })?;
+
+    // Applications expect their own path first like the shell passes it.
+
+    let load_options = match &entry.command_line {
+        Some(command_line) => format!("\\{} {}", path, command_line),
+        None => format!("\\{}", path),
+    };
+
+    application
+        .set_load_options(&load_options)
+        .map_err(|_| Error::InvalidCommandLine("It must be UCS-2."))?;
+
+    // The firmware's watchdog would reset the system while a shell or updater is still in use.
+
+    let _ = system::set_watchdog_timer(0);
+
+    con_out_println!("Starting \"{}\".", device_path);
+
+    let result = application
+        .start()
+        .map_err(|error| Error::ApplicationFailed(String::from(path), error));
+
+    // The menu is shown again after the application exits, so the firmware's default watchdog is
+    // restored to reset the system if booting hangs from here.
+
+    let _ = system::set_watchdog_timer(300);
+
+    result
+}
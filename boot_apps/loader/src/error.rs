@@ -0,0 +1,139 @@
+//**************************************************************************************************
+// error.rs                                                                                        *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::arch::paging::MapError;
+use alloc::string::String;
+use core::fmt;
+use kernel_interface::init;
+
+// Everything that can stop the loader from starting the kernel. These are shown on the error
+// screen instead of panicking so the user can retry or change firmware settings.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Firmware(&'static str, uefi::Error),
+    NoFramebuffer,
+    FileNotFound(String),
+    FileUnreadable(String, uefi::Error),
+    InvalidCommandLine(&'static str),
+    InvalidElf(elf::Error),
+    WrongEndian,
+    WrongMachine(elf::Machine),
+    NotExecutable,
+    KernelTooLarge,
+    TooManyKernelSegments,
+    WritableExecutableKernelPage(u64),
+    AllocationFailed(&'static str, uefi::Error),
+    MappingFailed(&'static str, MapError),
+    ApplicationUnloadable(String, uefi::Error),
+    ApplicationFailed(String, uefi::Error),
+}
+
+impl Error {
+    // A suggestion for the user on how the problem might be fixed.
+
+    pub fn advice(&self) -> &'static str {
+        match self {
+            Error::Firmware(_, _) => {
+                "The firmware reported an error. Restarting the system may fix the problem."
+            }
+            Error::NoFramebuffer => {
+                "The loader requires a graphics mode with a linear framebuffer. Try a different \
+                 resolution in the boot configuration."
+            }
+            Error::FileNotFound(_) => {
+                "Check that the path in the boot configuration is correct and that the file was \
+                 copied to the boot volume."
+            }
+            Error::FileUnreadable(_, _) => {
+                "The boot volume may be damaged or was removed. Check the boot device."
+            }
+            Error::InvalidCommandLine(_) => {
+                "Check the command line in the boot configuration or in boot\\system\\cmdline."
+            }
+            Error::InvalidElf(_)
+            | Error::WrongEndian
+            | Error::WrongMachine(_)
+            | Error::NotExecutable
+            | Error::KernelTooLarge
+            | Error::TooManyKernelSegments
+            | Error::WritableExecutableKernelPage(_) => {
+                "The kernel file is damaged or was built for a different system. Rebuild and copy \
+                 it to the boot volume again."
+            }
+            Error::AllocationFailed(_, _) | Error::MappingFailed(_, _) => {
+                "There is not enough free memory to start the system."
+            }
+            Error::ApplicationUnloadable(_, uefi::Error::SecurityViolation) => {
+                "The application is not signed with a key that Secure Boot accepts. Sign it or \
+                 disable Secure Boot in the firmware setup."
+            }
+            Error::ApplicationUnloadable(_, _) => {
+                "The application may be damaged or built for a different system. Copy it to the \
+                 boot volume again."
+            }
+            Error::ApplicationFailed(_, _) => {
+                "The application reported an error. Check the command line in the boot \
+                 configuration."
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Firmware(operation, error) => write!(f, "Failed to {}. {}", operation, error),
+            Error::NoFramebuffer => write!(f, "Graphics output has no linear framebuffer."),
+            Error::FileNotFound(path) => write!(f, "The file \"{}\" does not exist.", path),
+            Error::FileUnreadable(path, error) => {
+                write!(f, "Failed to read the file \"{}\". {}", path, error)
+            }
+            Error::InvalidCommandLine(reason) => {
+                write!(f, "The kernel command line is invalid. {}", reason)
+            }
+            Error::InvalidElf(error) => write!(f, "The kernel is not a valid ELF file. {}", error),
+            Error::WrongEndian => write!(f, "The kernel is the wrong endian."),
+            Error::WrongMachine(machine) => write!(
+                f,
+                "The kernel is built for the wrong machine ({:?}).",
+                machine
+            ),
+            Error::NotExecutable => write!(f, "The kernel is not an executable."),
+            Error::KernelTooLarge => write!(f, "The kernel does not fit in the address space."),
+            Error::TooManyKernelSegments => write!(
+                f,
+                "The kernel has more than {} loadable segments.",
+                init::KERNEL_SEGMENTS_CAPACITY
+            ),
+            Error::WritableExecutableKernelPage(address) => write!(
+                f,
+                "The kernel has writable and executable segments sharing the page at {:#X}.",
+                address
+            ),
+            Error::AllocationFailed(purpose, error) => write!(
+                f,
+                "Failed to allocate memory for the {}. {}",
+                purpose, error
+            ),
+            Error::MappingFailed(purpose, error) => {
+                write!(f, "Failed to map the {}. {}", purpose, error)
+            }
+            Error::ApplicationUnloadable(path, error) => {
+                write!(f, "Failed to load the application \"{}\". {}", path, error)
+            }
+            Error::ApplicationFailed(path, error) => {
+                write!(f, "The application \"{}\" failed. {}", path, error)
+            }
+        }
+    }
+}
+
+impl From<elf::Error> for Error {
+    fn from(error: elf::Error) -> Self {
+        Error::InvalidElf(error)
+    }
+}
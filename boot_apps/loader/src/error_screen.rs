@@ -0,0 +1,364 @@
+//**************************************************************************************************
+// error_screen.rs                                                                                 *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::arch;
+use crate::error::Error;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use psf::Font;
+use uefi::graphics::{BltPixel, Output, OutputBuffer};
+use uefi::io::console::{BackColor, FrontColor, InputDevice, Key, OutputDevice};
+use uefi::runtime::{ResetType, RuntimeServices};
+use uefi::system;
+
+const POLL_MICROSECONDS: usize = 10_000;
+
+// Text output is assumed to have the 80x25 mode every console must support.
+
+const TEXT_COLUMNS: usize = 80;
+
+const MARGIN_COLUMNS: usize = 2;
+
+const BACKGROUND: BltPixel = pixel(128, 0, 0);
+
+const FOREGROUND: BltPixel = pixel(255, 255, 255);
+
+const DIM_FOREGROUND: BltPixel = pixel(255, 170, 170);
+
+// Glyphs are scaled up on large screens so the text stays readable.
+
+const SCALED_PIXELS_PER_COLUMN: usize = 120;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Style {
+    Normal,
+    Dim,
+}
+
+// Shows the error over the whole screen until the user chooses what to do. Returns if the boot
+// should be retried. The system is reset for the other choices so this does not return then.
+
+pub fn show(error: &Error, title: Option<&str>) {
+    let mut screen = Screen::open();
+
+    let mut input = match InputDevice::con_in() {
+        Ok(input) => input,
+        Err(_) => {
+            screen.draw(
+                error,
+                title,
+                Some("No keyboard was found. The system is halted."),
+            );
+            unsafe { arch::stall() }
+        }
+    };
+
+    let _ = input.reset();
+
+    screen.draw(error, title, None);
+
+    loop {
+        let key = match input.read_key() {
+            Ok(key) => key,
+            Err(_) => unsafe { arch::stall() },
+        };
+
+        match key {
+            Some(Key::Char('r')) | Some(Key::Char('R')) => break,
+            Some(Key::Char('b')) | Some(Key::Char('B')) => reset(),
+            Some(Key::Char('f')) | Some(Key::Char('F')) => {
+                let status = match RuntimeServices::current()
+                    .and_then(|services| services.boot_to_firmware_setup())
+                {
+                    Ok(()) => reset(),
+                    Err(uefi::Error::NotSupported) => {
+                        String::from("The firmware can't be asked to open its setup.")
+                    }
+                    Err(error) => format!("Failed to request the firmware setup. {}", error),
+                };
+
+                screen.draw(error, title, Some(&status));
+            }
+            _ => {}
+        }
+
+        let _ = system::stall(POLL_MICROSECONDS);
+    }
+
+    screen.close();
+}
+
+fn reset() -> ! {
+    if let Ok(services) = RuntimeServices::current() {
+        services.reset(ResetType::Cold);
+    }
+
+    unsafe { arch::stall() }
+}
+
+// Graphics output is preferred so the error is visible after the console was left in a graphics
+// mode. Text output is used if there is no graphics output.
+
+enum Screen {
+    Graphics(GraphicsScreen),
+    Text(OutputDevice),
+    None,
+}
+
+impl Screen {
+    fn open() -> Self {
+        if let Some(screen) = GraphicsScreen::open() {
+            return Screen::Graphics(screen);
+        }
+
+        match OutputDevice::con_out() {
+            Ok(output) => Screen::Text(output),
+            Err(_) => Screen::None,
+        }
+    }
+
+    fn columns(&self) -> usize {
+        match self {
+            Screen::Graphics(screen) => screen.columns(),
+            _ => TEXT_COLUMNS,
+        }
+    }
+
+    fn draw(&mut self, error: &Error, title: Option<&str>, status: Option<&str>) {
+        self.clear();
+
+        let width = self.columns().saturating_sub(MARGIN_COLUMNS * 2).max(1);
+
+        let mut lines = vec![
+            (
+                String::from("The system could not be started."),
+                Style::Normal,
+            ),
+            (String::new(), Style::Normal),
+        ];
+
+        if let Some(title) = title {
+            lines.push((format!("Boot entry: {}", title), Style::Dim));
+            lines.push((String::new(), Style::Normal));
+        }
+
+        for line in wrap(&format!("{}", error), width) {
+            lines.push((line, Style::Normal));
+        }
+
+        lines.push((String::new(), Style::Normal));
+
+        for line in wrap(error.advice(), width) {
+            lines.push((line, Style::Dim));
+        }
+
+        lines.push((String::new(), Style::Normal));
+        lines.push((String::from("R  Retry"), Style::Normal));
+        lines.push((String::from("B  Reboot"), Style::Normal));
+        lines.push((String::from("F  Reboot to firmware setup"), Style::Normal));
+
+        if let Some(status) = status {
+            lines.push((String::new(), Style::Normal));
+
+            for line in wrap(status, width) {
+                lines.push((line, Style::Dim));
+            }
+        }
+
+        for (row, (line, style)) in lines.iter().enumerate() {
+            self.write_line(row + 1, line, *style);
+        }
+    }
+
+    fn clear(&mut self) {
+        match self {
+            Screen::Graphics(screen) => screen.clear(),
+            Screen::Text(output) => {
+                let _ = output.set_colors(BackColor::Red, FrontColor::White);
+                let _ = output.clear();
+                let _ = output.set_cursor_visible(false);
+            }
+            Screen::None => {}
+        }
+    }
+
+    fn write_line(&mut self, row: usize, text: &str, style: Style) {
+        match self {
+            Screen::Graphics(screen) => screen.write_line(row, text, style),
+            Screen::Text(output) => {
+                let front_color = match style {
+                    Style::Normal => FrontColor::White,
+                    Style::Dim => FrontColor::LightGray,
+                };
+
+                let _ = output.set_cursor_position(MARGIN_COLUMNS, row);
+                let _ = output.set_colors(BackColor::Red, front_color);
+                let _ = write!(output, "{}", text);
+            }
+            Screen::None => {}
+        }
+    }
+
+    // Leaves the screen in the colours the rest of the loader expects.
+
+    fn close(&mut self) {
+        match self {
+            Screen::Graphics(_) | Screen::Text(_) => {
+                if let Ok(mut output) = OutputDevice::con_out() {
+                    let _ = output.set_colors(BackColor::Black, FrontColor::LightGray);
+                    let _ = output.clear();
+                    let _ = output.set_cursor_visible(true);
+                }
+            }
+            Screen::None => {}
+        }
+    }
+}
+
+struct GraphicsScreen {
+    output: Output,
+    font: Font<'static>,
+    width: usize,
+    height: usize,
+    scale: usize,
+}
+
+impl GraphicsScreen {
+    fn open() -> Option<Self> {
+        let output = OutputBuffer::locate().ok()?.open(0).ok()?;
+        let font = Font::new(psf::BUILTIN_FONT).ok()?;
+
+        let width = output.width() as usize;
+        let height = output.height() as usize;
+
+        let scale = (width / (SCALED_PIXELS_PER_COLUMN * font.width())).max(1);
+
+        Some(Self {
+            output,
+            font,
+            width,
+            height,
+            scale,
+        })
+    }
+
+    fn columns(&self) -> usize {
+        self.width / (self.font.width() * self.scale)
+    }
+
+    fn clear(&mut self) {
+        let _ = self.output.fill(BACKGROUND, 0, 0, self.width, self.height);
+    }
+
+    // The whole row is drawn at once since every Blt call can be slow on some firmware.
+
+    fn write_line(&mut self, row: usize, text: &str, style: Style) {
+        let foreground = match style {
+            Style::Normal => FOREGROUND,
+            Style::Dim => DIM_FOREGROUND,
+        };
+
+        let glyph_width = self.font.width() * self.scale;
+        let glyph_height = self.font.height() * self.scale;
+
+        let x = MARGIN_COLUMNS * glyph_width;
+        let y = row * glyph_height;
+
+        if y + glyph_height > self.height || x >= self.width {
+            return;
+        }
+
+        let columns = text
+            .chars()
+            .count()
+            .min(self.columns().saturating_sub(MARGIN_COLUMNS));
+
+        if columns == 0 {
+            return;
+        }
+
+        let line_width = columns * glyph_width;
+        let mut buffer = vec![BACKGROUND; line_width * glyph_height];
+
+        let bytes_per_row = self.font.bytes_per_row();
+
+        for (column, character) in text.chars().take(columns).enumerate() {
+            let glyph = match self.font.glyph(character).or_else(|| self.font.glyph('?')) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+
+            for y in 0..glyph_height {
+                let glyph_row = y / self.scale;
+                let bits = &glyph[glyph_row * bytes_per_row..(glyph_row + 1) * bytes_per_row];
+
+                for x in 0..glyph_width {
+                    let glyph_column = x / self.scale;
+
+                    if bits[glyph_column / 8] & (0x80 >> (glyph_column % 8)) != 0 {
+                        buffer[y * line_width + column * glyph_width + x] = foreground;
+                    }
+                }
+            }
+        }
+
+        let _ = self.output.draw(&buffer, x, y, line_width, glyph_height);
+    }
+}
+
+// Splits text into lines of at most the width in characters. Words longer than a line are split.
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_len = 0;
+
+    for word in text.split_whitespace() {
+        let mut word = word;
+
+        if line_len > 0 && line_len + 1 + word.chars().count() > width {
+            lines.push(line);
+            line = String::new();
+            line_len = 0;
+        }
+
+        while word.chars().count() > width {
+            let split = word
+                .char_indices()
+                .nth(width)
+                .map_or(word.len(), |(index, _)| index);
+
+            lines.push(String::from(&word[..split]));
+            word = &word[split..];
+        }
+
+        if line_len > 0 {
+            line.push(' ');
+            line_len += 1;
+        }
+
+        line.push_str(word);
+        line_len += word.chars().count();
+    }
+
+    if line_len > 0 {
+        lines.push(line);
+    }
+
+    lines
+}
+
+const fn pixel(red: u8, green: u8, blue: u8) -> BltPixel {
+    BltPixel {
+        blue,
+        green,
+        red,
+        reserved: 0,
+    }
+}
@@ -5,36 +5,62 @@
 //**************************************************************************************************
 
 use crate::arch;
+use crate::boot_config::Entry;
+use crate::error::Error;
+use alloc::string::String;
 use alloc::vec::Vec;
 use core::convert::{TryFrom, TryInto};
 use core::mem;
+use core::str;
 use kernel_interface::init;
+use memory::Address64;
 use uefi::configuration::Table;
+use uefi::image;
 use uefi::io::storage::Volume;
 use uefi::io::Endian;
-use uefi::memory::{MemoryMap, MemoryMapKey, MemoryPages, Segment};
+use uefi::memory::{MemoryDescriptor, MemoryMap, MemoryMapKey, MemoryPages, Segment, PAGE_SIZE};
+use uefi::random;
+use uefi::runtime::RuntimeServices;
 use uefi::system;
 
-pub fn run_and_jump() -> ! {
-    let mut args = init::Args::default();
+const COMMAND_LINE_PATH: &str = "boot\\system\\cmdline";
 
-    con_out_println!("Starting kernel prep.");
+// Returns only if the kernel could not be started. Memory allocated for the kernel is freed again
+// so the boot can be retried.
 
-    let mut volume = Volume::containing_current_image().expect("Failed to obtain storage volume");
+pub fn run_and_jump(
+    volume: &mut Volume,
+    entry: &Entry,
+    framebuffer: init::FramebufferInfo,
+) -> Error {
+    let mut args = init::Args {
+        framebuffer,
+        ..init::Args::default()
+    };
 
-    let entry_address = load_kernel(&mut volume);
+    // Pages handed to the kernel are only given up once boot services are exited.
 
-    create_kernel_stack();
+    let mut allocations = Vec::new();
 
-    //load_initial(&mut volume, &mut args);
+    con_out_println!("Starting kernel prep.");
 
-    obtain_configuration_tables(&mut args);
+    let entry_address = match prepare(volume, entry, &mut args, &mut allocations) {
+        Ok(entry_address) => entry_address,
+        Err(error) => return error,
+    };
 
     con_out_println!("Obtaining the memory map and then jumping to kernel.");
 
-    let key = obtain_memory_map(&mut args);
+    let key = match obtain_memory_map(&mut args) {
+        Ok(key) => key,
+        Err(error) => return error,
+    };
 
-    system::exit(key).expect("Failed to exit boot services.");
+    if let Err(error) = system::exit(key) {
+        return Error::Firmware("exit boot services", error);
+    }
+
+    mem::forget(allocations);
 
     unsafe {
         arch::kernel_prep::enter_kernel(entry_address, args);
@@ -43,55 +69,142 @@ pub fn run_and_jump() -> ! {
     panic!("Kernel returned from entry.");
 }
 
-fn load_kernel(volume: &mut Volume) -> usize {
-    // This code is fine for now but in the future this (and a lot of other code) should
-    // avoid panicking and instead provide a more helpful error screen.
+fn prepare(
+    volume: &mut Volume,
+    entry: &Entry,
+    args: &mut init::Args,
+    allocations: &mut Vec<MemoryPages>,
+) -> Result<usize, Error> {
+    obtain_command_line(volume, entry, args)?;
+
+    let entry_address = load_kernel(volume, &entry.kernel, args, allocations)?;
+
+    create_kernel_stack(allocations)?;
+
+    if let Some(path) = &entry.initial {
+        load_initial(volume, path, args, allocations)?;
+    }
+
+    obtain_configuration_tables(args);
+
+    obtain_runtime_services(args);
+
+    Ok(entry_address)
+}
+
+fn obtain_command_line(
+    volume: &mut Volume,
+    entry: &Entry,
+    args: &mut init::Args,
+) -> Result<(), Error> {
+    // Options given to the loader by the boot manager or the shell take priority over the boot
+    // entry which takes priority over the file on the boot volume.
+
+    let load_options =
+        image::load_options().map_err(|error| Error::Firmware("read load options", error))?;
+    let mut command_line = String::from(strip_image_path(&load_options).trim());
+
+    if command_line.is_empty() {
+        command_line = entry.command_line.clone().unwrap_or_default();
+    }
+
+    if command_line.is_empty() {
+        let mut buffer = Vec::new();
+
+        match read_file(volume, COMMAND_LINE_PATH, &mut buffer) {
+            Ok(()) | Err(Error::FileNotFound(_)) => {}
+            Err(error) => return Err(error),
+        }
 
+        let text = str::from_utf8(&buffer)
+            .map_err(|_| Error::InvalidCommandLine("The command line file is not UTF-8."))?;
+
+        // Line breaks are allowed in the file to keep long command lines readable.
+
+        command_line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+
+    args.command_line
+        .set(&command_line)
+        .map_err(|_| Error::InvalidCommandLine("The command line is too long."))?;
+
+    con_out_println!("Kernel command line is \"{}\".", command_line);
+
+    Ok(())
+}
+
+fn read_file(volume: &mut Volume, path: &str, buffer: &mut Vec<u8>) -> Result<(), Error> {
+    let node = volume
+        .open_node(path, true, false)
+        .map_err(|error| match error {
+            uefi::Error::PathNonExistent(_) => Error::FileNotFound(String::from(path)),
+            _ => Error::FileUnreadable(String::from(path), error),
+        })?;
+
+    node.read_to_end(buffer)
+        .map_err(|error| Error::FileUnreadable(String::from(path), error))
+}
+
+// The shell passes the path of the image as the first option like a C program's argv.
+
+fn strip_image_path(load_options: &str) -> &str {
+    let trimmed = load_options.trim_start();
+
+    let first_len = trimmed
+        .find(char::is_whitespace)
+        .unwrap_or_else(|| trimmed.len());
+
+    if trimmed[..first_len].to_ascii_lowercase().ends_with(".efi") {
+        &trimmed[first_len..]
+    } else {
+        trimmed
+    }
+}
+
+fn load_kernel(
+    volume: &mut Volume,
+    path: &str,
+    args: &mut init::Args,
+    allocations: &mut Vec<MemoryPages>,
+) -> Result<usize, Error> {
     let mut kernel_buffer = Vec::new();
 
-    volume
-        .open_node("boot\\system\\kernel", true, false)
-        .expect("Failed to open kernel.")
-        .read_to_end(&mut kernel_buffer)
-        .expect("Failed to read kernel.");
+    read_file(volume, path, &mut kernel_buffer)?;
 
     con_out_println!("Read kernel from disk.");
 
     let kernel_file = elf::File::new(kernel_buffer.as_ref());
 
-    let identity_header = kernel_file
-        .read_identity_header()
-        .expect("Failed to read kernel identity header.");
+    let identity_header = kernel_file.read_identity_header()?;
 
-    assert!(identity_header.is_valid(), "Kernel binary is not valid.");
+    if !identity_header.is_valid() {
+        return Err(Error::InvalidElf(elf::Error::InvalidMagic));
+    }
 
-    assert_eq!(
-        Endian::CURRENT,
-        identity_header
-            .data
-            .try_into()
-            .expect("Kernel endian is unknown."),
-        "Kernel is the wrong endian."
-    );
+    let endian: Endian = identity_header
+        .data
+        .try_into()
+        .map_err(|_| Error::InvalidElf(elf::Error::UnknownData))?;
 
-    let header = kernel_file
-        .read_header()
-        .expect("Failed to read kernel header.");
+    if endian != Endian::CURRENT {
+        return Err(Error::WrongEndian);
+    }
 
-    assert_eq!(
-        header.object_type,
-        elf::ObjectType::EXECUTABLE,
-        "Kernel is not an executable."
-    );
+    let header = kernel_file.read_header()?;
+
+    // Only a position independent kernel can be moved away from the address it was linked at.
+
+    let is_position_independent = match header.object_type {
+        elf::ObjectType::EXECUTABLE => false,
+        elf::ObjectType::DYNAMIC => true,
+        _ => return Err(Error::NotExecutable),
+    };
 
-    arch::kernel_prep::check_headers(&identity_header, &header);
+    arch::kernel_prep::check_headers(&identity_header, &header)?;
 
     con_out_println!("Kernel is valid.");
-    con_out_println!("Kernel entry at {:#X}.", header.entry);
 
-    let load_memory_segment = kernel_file
-        .load_memory_segment()
-        .expect("Failed to get kernel load memory segment.");
+    let load_memory_segment = kernel_file.load_memory_segment()?;
 
     con_out_println!(
         "Kernel requires {} byte(s) of memory.",
@@ -100,7 +213,7 @@ fn load_kernel(volume: &mut Volume) -> usize {
 
     let mut pages =
         MemoryPages::with_byte_len(load_memory_segment.len(), init::KERNEL_UEFI_MEMORY_TYPE)
-            .expect("Failed to allocate pages for kernel.");
+            .map_err(|error| Error::AllocationFailed("kernel", error))?;
 
     let page_count = pages.len();
 
@@ -108,29 +221,248 @@ fn load_kernel(volume: &mut Volume) -> usize {
 
     let pages_slice = pages.as_mut_slice();
 
-    kernel_file
-        .load_to(pages_slice)
-        .expect("Failed to load kernel to paged memory.");
+    kernel_file.load_to(pages_slice)?;
 
     con_out_println!("Loaded kernel at {:#X}.", pages_slice.as_ptr() as usize);
 
-    arch::paging::map(pages_slice, load_memory_segment, page_count);
+    let slide = if is_position_independent {
+        choose_kernel_slide(args)
+    } else {
+        con_out_println!("Kernel is not position independent and will not be moved.");
+        0
+    };
+
+    kernel_file.relocate(pages_slice, slide)?;
+
+    let virtual_start = (load_memory_segment.start() as u64)
+        .checked_add(slide)
+        .filter(|start| {
+            start
+                .checked_add(load_memory_segment.len() as u64)
+                .is_some()
+        })
+        .ok_or(Error::KernelTooLarge)?;
+
+    arch::paging::map(
+        pages_slice,
+        Segment::with_len(virtual_start as usize, load_memory_segment.len()),
+        page_count,
+    )
+    .map_err(|error| Error::MappingFailed("kernel", error))?;
+
+    con_out_println!("Mapped kernel to {:#X}.", virtual_start);
+
+    protect_kernel(&kernel_file, slide, args)?;
+
+    allocations.push(pages);
 
-    con_out_println!("Mapped kernel to {:#X}.", load_memory_segment.start());
+    args.kernel_slide = slide;
 
-    mem::forget(pages);
+    load_kernel_symbols(&kernel_file, args, allocations)?;
 
-    usize::try_from(header.entry).expect("Kernel entry address is too large.")
+    let entry_address = header.entry.wrapping_add(slide);
+
+    con_out_println!("Kernel entry at {:#X}.", entry_address);
+
+    usize::try_from(entry_address).map_err(|_| Error::KernelTooLarge)
 }
 
-fn create_kernel_stack() {
+// Each segment gets the permissions from its flags so code can't be written and data can't be
+// executed. A page shared by two segments gets the permissions of both unless that would make it
+// writable and executable. The segments are passed on so the kernel can protect its own mapping
+// of itself the same way.
+
+fn protect_kernel(kernel_file: &elf::File, slide: u64, args: &mut init::Args) -> Result<(), Error> {
+    if arch::paging::enable_no_execute() {
+        con_out_println!("Execute disable is enabled.");
+    } else {
+        con_out_println!("Execute disable is not supported. Kernel data will be executable.");
+    }
+
+    // The last page of the previous segment with its writable and executable permissions.
+
+    let mut previous_last_page: Option<(usize, bool, bool)> = None;
+
+    for segment in kernel_file.load_segments()? {
+        let segment = segment?;
+
+        let start = segment.virtual_address.wrapping_add(slide) as usize;
+        let end = start + segment.memory_size as usize;
+
+        let first_page = start & !(PAGE_SIZE - 1);
+        let last_page = (end - 1) & !(PAGE_SIZE - 1);
+
+        let writable = segment.is_writable();
+        let executable = segment.is_executable();
+
+        arch::paging::protect(
+            Segment::with_end(first_page, last_page + PAGE_SIZE),
+            writable,
+            executable,
+        )
+        .map_err(|error| Error::MappingFailed("kernel", error))?;
+
+        let mut first_page_permissions = (writable, executable);
+
+        if let Some((page, previous_writable, previous_executable)) = previous_last_page {
+            if page == first_page {
+                con_out_println!("Kernel segments share the page at {:#X}.", page);
+
+                first_page_permissions = (
+                    writable || previous_writable,
+                    executable || previous_executable,
+                );
+
+                if first_page_permissions == (true, true) {
+                    return Err(Error::WritableExecutableKernelPage(page as u64));
+                }
+
+                arch::paging::protect(
+                    Segment::with_len(page, PAGE_SIZE),
+                    first_page_permissions.0,
+                    first_page_permissions.1,
+                )
+                .map_err(|error| Error::MappingFailed("kernel", error))?;
+            }
+        }
+
+        let (last_page_writable, last_page_executable) = if first_page == last_page {
+            first_page_permissions
+        } else {
+            (writable, executable)
+        };
+
+        previous_last_page = Some((last_page, last_page_writable, last_page_executable));
+
+        args.kernel_segments
+            .push(init::KernelSegment {
+                start: Address64::new(start as u64),
+                len: segment.memory_size as usize,
+                writable,
+                executable,
+            })
+            .map_err(|_| Error::TooManyKernelSegments)?;
+
+        con_out_println!(
+            "Protected kernel segment at {:#X} as {}{}{}.",
+            start,
+            if segment.is_readable() { "r" } else { "-" },
+            if writable { "w" } else { "-" },
+            if executable { "x" } else { "-" },
+        );
+    }
+
+    Ok(())
+}
+
+// The slide is a random multiple of the alignment so the kernel can't be found at a known
+// address. "nokaslr" on the command line keeps the kernel at its linked address which makes
+// debugging easier.
+
+fn choose_kernel_slide(args: &init::Args) -> u64 {
+    if args
+        .command_line
+        .as_str()
+        .split_whitespace()
+        .any(|option| option == "nokaslr")
+    {
+        con_out_println!("KASLR is disabled by the kernel command line.");
+        return 0;
+    }
+
+    let random = match random_u64() {
+        Some(random) => random,
+        None => {
+            con_out_println!("No source of random numbers was found. KASLR is disabled.");
+            return 0;
+        }
+    };
+
+    let slot_count = init::KERNEL_SLIDE_LIMIT / init::KERNEL_SLIDE_ALIGNMENT;
+    let slide = (random % slot_count) * init::KERNEL_SLIDE_ALIGNMENT;
+
+    con_out_println!("Kernel slide is {:#X}.", slide);
+
+    slide
+}
+
+// The UEFI RNG protocol is preferred since firmware may mix several sources. The processor is
+// used if the firmware doesn't provide it.
+
+fn random_u64() -> Option<u64> {
+    let mut bytes = [0; 8];
+
+    if random::fill(&mut bytes).is_ok() {
+        return Some(u64::from_ne_bytes(bytes));
+    }
+
+    arch::random_u64()
+}
+
+fn load_kernel_symbols(
+    kernel_file: &elf::File,
+    args: &mut init::Args,
+    allocations: &mut Vec<MemoryPages>,
+) -> Result<(), Error> {
+    // The kernel uses its symbols to print readable backtraces. They are optional so a stripped
+    // kernel can still boot.
+
+    let symbol_table_header =
+        match kernel_file.find_section_header(elf::SectionSegmentType::SYMBOL_TABLE)? {
+            Some(header) => header,
+            None => {
+                con_out_println!("Kernel has no symbol table.");
+                return Ok(());
+            }
+        };
+
+    let (symbol_table, string_table) = kernel_file.read_symbol_table(&symbol_table_header)?;
+
+    let symbol_table = symbol_table.source();
+    let string_table = string_table.source();
+
+    // Both tables are kept in one allocation of their own memory type so the kernel does not
+    // reclaim them or map them as part of its image.
+
+    let mut pages = MemoryPages::with_byte_len(
+        symbol_table.len() + string_table.len(),
+        init::KERNEL_SYMBOLS_UEFI_MEMORY_TYPE,
+    )
+    .map_err(|error| Error::AllocationFailed("kernel symbols", error))?;
+
+    let pages_slice = pages.as_mut_slice();
+
+    let (symbol_table_copy, remaining) = pages_slice.split_at_mut(symbol_table.len());
+    let string_table_copy = &mut remaining[..string_table.len()];
+
+    symbol_table_copy.copy_from_slice(symbol_table);
+    string_table_copy.copy_from_slice(string_table);
+
+    args.symbols = init::SymbolInfo {
+        symbol_table: Address64::new(symbol_table_copy.as_ptr() as u64),
+        symbol_table_len: symbol_table.len(),
+        string_table: Address64::new(string_table_copy.as_ptr() as u64),
+        string_table_len: string_table.len(),
+    };
+
+    allocations.push(pages);
+
+    con_out_println!(
+        "Loaded {} byte(s) of kernel symbols.",
+        symbol_table.len() + string_table.len()
+    );
+
+    Ok(())
+}
+
+fn create_kernel_stack(allocations: &mut Vec<MemoryPages>) -> Result<(), Error> {
     // Allocate memory for the kernel stack.
 
     let mut pages = MemoryPages::with_len(
         init::STACK_PAGES as usize,
         init::KERNEL_STACK_UEFI_MEMORY_TYPE,
     )
-    .expect("Failed to allocate pages for kernel stack.");
+    .map_err(|error| Error::AllocationFailed("kernel stack", error))?;
 
     let pages_slice = pages.as_mut_slice();
 
@@ -140,26 +472,60 @@ fn create_kernel_stack() {
         pages_slice,
         Segment::with_len(init::BP_STACK_VIRTUAL_BOTTOM as usize, pages_slice.len()),
         init::STACK_PAGES as usize,
-    );
+    )
+    .map_err(|error| Error::MappingFailed("kernel stack", error))?;
 
-    mem::forget(pages);
+    allocations.push(pages);
 
     con_out_println!(
         "Mapped kernel stack bottom to {:#X}.",
         init::BP_STACK_VIRTUAL_BOTTOM
     );
+
+    Ok(())
 }
 
-fn load_initial(volume: &mut Volume, args: &mut init::Args) {
+fn load_initial(
+    volume: &mut Volume,
+    path: &str,
+    args: &mut init::Args,
+    allocations: &mut Vec<MemoryPages>,
+) -> Result<(), Error> {
     let mut initial_buffer = Vec::new();
 
-    volume
-        .open_node("boot\\initial", true, false)
-        .expect("Failed to open initial.")
-        .read_to_end(&mut initial_buffer)
-        .expect("Failed to read initial.");
+    read_file(volume, path, &mut initial_buffer)?;
 
     con_out_println!("Read initial from disk.");
+
+    if initial_buffer.is_empty() {
+        con_out_println!("Initial is empty and will not be passed to the kernel.");
+        return Ok(());
+    }
+
+    // The image gets its own memory type so the kernel knows not to reuse it while it is needed.
+
+    let mut pages =
+        MemoryPages::with_byte_len(initial_buffer.len(), init::INITIAL_IMAGE_UEFI_MEMORY_TYPE)
+            .map_err(|error| Error::AllocationFailed("initial", error))?;
+
+    let pages_slice = pages.as_mut_slice();
+
+    pages_slice[..initial_buffer.len()].copy_from_slice(&initial_buffer);
+
+    args.initial_image = init::InitialImageInfo {
+        address: Address64::new(pages_slice.as_ptr() as u64),
+        len: initial_buffer.len(),
+    };
+
+    con_out_println!(
+        "Loaded {} byte(s) of initial at {:#X}.",
+        initial_buffer.len(),
+        pages_slice.as_ptr() as usize
+    );
+
+    allocations.push(pages);
+
+    Ok(())
 }
 
 fn obtain_configuration_tables(args: &mut init::Args) {
@@ -196,8 +562,26 @@ fn obtain_configuration_tables(args: &mut init::Args) {
                 }
                 Table::Sal(_) => {}
                 Table::Mps(_) => {}
-                Table::Smbios(_) => {}
-                Table::Smbios3(_) => {}
+                Table::Smbios(entry_point_ptr) => {
+                    // Prefer the SMBIOS 3 entry point if available.
+
+                    if args.system_info.smbios.is_null() {
+                        args.system_info.smbios = Address64::new(entry_point_ptr as u64);
+                    }
+
+                    con_out_println!(
+                        "Found SMBIOS entry point at {:#X}.",
+                        entry_point_ptr as usize
+                    );
+                }
+                Table::Smbios3(entry_point_ptr) => {
+                    args.system_info.smbios = Address64::new(entry_point_ptr as u64);
+
+                    con_out_println!(
+                        "Found SMBIOS 3 entry point at {:#X}.",
+                        entry_point_ptr as usize
+                    );
+                }
                 Table::Unknown(_) => {}
             }
         }
@@ -206,17 +590,42 @@ fn obtain_configuration_tables(args: &mut init::Args) {
     con_out_println!("Obtained configuration tables.");
 }
 
-fn obtain_memory_map(args: &mut init::Args) -> MemoryMapKey {
-    let mut uefi_map = MemoryMap::get().expect("Failed to get memory map.");
+// The kernel moves the runtime services to its own address space so it can still use them after
+// boot services are exited. The regions they need are found with the memory map.
+
+fn obtain_runtime_services(args: &mut init::Args) {
+    match RuntimeServices::current() {
+        Ok(services) => {
+            args.system_info.uefi_runtime = Address64::new(services.as_ptr() as u64);
+
+            con_out_println!(
+                "Found runtime services at {:#X}.",
+                services.as_ptr() as usize
+            );
+
+            if let Ok(boot_current) = services.boot_current() {
+                con_out_println!("Booted from load option Boot{:04X}.", boot_current);
+            }
+        }
+        Err(error) => con_out_println!("Runtime services are not available. {}", error),
+    }
+}
+
+fn obtain_memory_map(args: &mut init::Args) -> Result<MemoryMapKey, Error> {
+    let get_map = || MemoryMap::get().map_err(|error| Error::Firmware("get the memory map", error));
+
+    let mut uefi_map = get_map()?;
     let mut kernel_map = Vec::<init::MemorySection>::new();
+    let mut runtime_map = Vec::<MemoryDescriptor>::new();
 
-    // Loop until the vector for the kernel's memory map is large enough to contain the
-    // UEFI memory map. Allocating more memory for the vector invalidates the previous memory
+    // Loop until the vectors for the kernel's memory maps are large enough to contain the
+    // UEFI memory map. Allocating more memory for the vectors invalidates the previous memory
     // map requiring it and the exit key to be acquired again.
 
-    while kernel_map.capacity() < uefi_map.len() {
-        kernel_map.reserve(uefi_map.len() - kernel_map.capacity());
-        uefi_map = MemoryMap::get().expect("Failed to get memory map.");
+    while kernel_map.capacity() < uefi_map.len() || runtime_map.capacity() < uefi_map.len() {
+        kernel_map.reserve(uefi_map.len());
+        runtime_map.reserve(uefi_map.len());
+        uefi_map = get_map()?;
     }
 
     for uefi_entry in uefi_map.iter() {
@@ -226,13 +635,18 @@ fn obtain_memory_map(args: &mut init::Args) -> MemoryMapKey {
             len: segment.len(),
             memory_type: uefi_entry.region_type().into(),
         });
+
+        if uefi_entry.is_runtime() {
+            runtime_map.push(uefi_entry.descriptor());
+        }
     }
 
     args.memory_map = init::MemoryMap::from_vec(kernel_map);
+    args.system_info.uefi_runtime_map = init::UefiRuntimeMap::from_vec(runtime_map);
 
     let key = uefi_map.key();
 
     mem::forget(uefi_map);
 
-    key
+    Ok(key)
 }
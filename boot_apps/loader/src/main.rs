@@ -14,13 +14,24 @@ extern crate uefi;
 extern crate alloc;
 
 mod arch;
+mod boot_config;
+mod chain_load;
+mod error;
+mod error_screen;
 mod kernel_prep;
+mod menu;
 
+use ::memory::Address64;
+use boot_config::{Config, Entry, EntryKind, Resolution};
 use core::alloc::Layout;
 use core::fmt::Write;
 use core::panic::PanicInfo;
+use error::Error;
+use kernel_interface::init;
+use uefi::ffi::graphics_output::PixelFormat;
 use uefi::graphics;
 use uefi::io::console;
+use uefi::io::storage::Volume;
 use uefi::memory;
 use uefi::system;
 use uefi::{Handle, Status, SystemTable};
@@ -34,19 +45,94 @@ pub unsafe extern "C" fn efi_main(image_handle: Handle, system_table: *mut Syste
     main();
 }
 
+// Every failure to boot is shown on the error screen. Retrying starts over from the menu so a
+// different entry can be chosen.
+
 fn main() -> ! {
-    initialize_graphics_and_console();
-    kernel_prep::run_and_jump();
+    loop {
+        let mut volume = match Volume::containing_current_image() {
+            Ok(volume) => volume,
+            Err(error) => {
+                error_screen::show(&Error::Firmware("open the boot volume", error), None);
+                continue;
+            }
+        };
+
+        let config = Config::load(&volume);
+
+        // The menu is drawn in the mode of the default entry. Otherwise it would be drawn in the
+        // firmware's mode, or the mode of an entry that failed to boot, and then cleared when the
+        // mode changes.
+
+        if config.timeout != 0 {
+            if let Err(error) = set_resolution(config.default_entry().resolution) {
+                con_out_println!("{}", error);
+            }
+        }
+
+        let entry = select_entry(&volume, &config);
+
+        if let Err(error) = boot(&mut volume, entry) {
+            con_out_println!("Failed to boot \"{}\". {}", entry.title, error);
+
+            error_screen::show(&error, Some(&entry.title));
+        }
+    }
 }
 
-fn initialize_graphics_and_console() {
-    let buffer =
-        graphics::OutputBuffer::locate().expect("Failed to locate graphics output buffer.");
+// Kernels never return. Applications return when they exit and the menu is shown again.
 
-    let mut output = buffer.open(0).expect("Failed to open graphics output.");
-    output
-        .set_closest_resolution(1280, 720, true)
-        .expect("Failed to set graphics output resolution.");
+fn boot(volume: &mut Volume, entry: &Entry) -> Result<(), Error> {
+    if let EntryKind::Application(path) = &entry.kind {
+        con_out_println!("Booting \"{}\".", entry.title);
+        return chain_load::run(entry, path);
+    }
+
+    let framebuffer = initialize_graphics_and_console(entry.resolution)?;
+
+    con_out_println!("Booting \"{}\".", entry.title);
+
+    Err(kernel_prep::run_and_jump(volume, entry, framebuffer))
+}
+
+// Boots the fallback entry instead if the file of the chosen one is missing.
+
+fn select_entry<'a>(volume: &Volume, config: &'a Config) -> &'a Entry {
+    let entry = menu::select(config);
+
+    if volume.open_node(entry.path(), true, false).is_ok() {
+        return entry;
+    }
+
+    match config.fallback_entry() {
+        Some(fallback) => {
+            con_out_println!(
+                "Failed to open \"{}\". Using the fallback entry \"{}\".",
+                entry.path(),
+                fallback.title
+            );
+            fallback
+        }
+        None => entry,
+    }
+}
+
+fn set_resolution(resolution: Resolution) -> Result<graphics::Output, Error> {
+    let mut output = graphics::OutputBuffer::locate()
+        .and_then(|buffer| buffer.open(0))
+        .map_err(|error| Error::Firmware("open graphics output", error))?;
+
+    match resolution {
+        Resolution::Size { width, height } => output.set_closest_resolution(width, height, true),
+        Resolution::Maximum => output.maximize(true),
+    }
+    .map_err(|error| Error::Firmware("set the graphics output resolution", error))?;
+
+    Ok(output)
+}
+
+fn initialize_graphics_and_console(resolution: Resolution) -> Result<init::FramebufferInfo, Error> {
+    let output = set_resolution(resolution)?;
 
     con_out_println!("Verdure OS UEFI Boot Loader");
     con_out_println!("Copyright (c) 2018-2021 The Verdure Project");
@@ -55,17 +141,39 @@ fn initialize_graphics_and_console() {
         con_out_println!("This is a debug build.");
     }
 
-    match output.framebuffer_address() {
-        Some(address) => {
-            con_out_println!(
-                "Graphics output initialized at address {:#X} with {}x{} resolution.",
-                address,
-                output.width(),
-                output.height()
-            );
-        }
-        None => panic!("Graphics output could not be initialized with a linear framebuffer."),
+    if let Ok(path) = uefi::image::device_path() {
+        con_out_println!("Loaded from {}.", path);
     }
+
+    let address = output.framebuffer_address().ok_or(Error::NoFramebuffer)?;
+
+    con_out_println!(
+        "Graphics output initialized at address {:#X} with {}x{} resolution.",
+        address,
+        output.width(),
+        output.height()
+    );
+
+    let pixel_bit_mask = output.pixel_bit_mask();
+
+    let pixel_format = match output.pixel_format() {
+        PixelFormat::RedGreenBlueReserved8BitPerColor => init::PixelFormat::RGB,
+        PixelFormat::BlueGreenRedReserved8BitPerColor => init::PixelFormat::BGR,
+        PixelFormat::BitMask => init::PixelFormat::BIT_MASK,
+        PixelFormat::BltOnly => unreachable!(),
+    };
+
+    Ok(init::FramebufferInfo {
+        address: Address64::new(address),
+        size: output.framebuffer_size(),
+        width: output.width(),
+        height: output.height(),
+        stride: output.pixels_per_scan_line(),
+        pixel_format,
+        red_mask: pixel_bit_mask.red_mask,
+        green_mask: pixel_bit_mask.green_mask,
+        blue_mask: pixel_bit_mask.blue_mask,
+    })
 }
 
 #[alloc_error_handler]
@@ -0,0 +1,172 @@
+//**************************************************************************************************
+// menu.rs                                                                                         *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::boot_config::{Config, Entry};
+use core::fmt::Write;
+use uefi::io::console::{BackColor, FrontColor, InputDevice, Key, OutputDevice, ScanCode};
+use uefi::system;
+
+// Keys are polled instead of waiting on events so the countdown can be updated between them.
+
+const POLL_MICROSECONDS: usize = 10_000;
+
+const POLLS_PER_SECOND: u32 = 1_000_000 / POLL_MICROSECONDS as u32;
+
+// Rows of the screen above the first entry.
+
+const HEADER_ROWS: usize = 3;
+
+// Shows the entries until one is chosen or the timeout runs out. Any key stops the countdown.
+// The default entry is returned without a menu if the timeout is 0 or there is no keyboard.
+
+pub fn select(config: &Config) -> &Entry {
+    if config.timeout == 0 {
+        return config.default_entry();
+    }
+
+    let mut input = match InputDevice::con_in() {
+        Ok(input) => input,
+        Err(_) => return config.default_entry(),
+    };
+
+    let _ = input.reset();
+
+    let mut menu = Menu {
+        config,
+        output: OutputDevice::con_out().expect("Failed to get con out output device."),
+        selected: config.default,
+    };
+
+    let mut remaining_polls = Some(config.timeout.saturating_mul(POLLS_PER_SECOND));
+
+    menu.draw();
+    menu.draw_countdown(remaining_polls);
+
+    // Reading stops if the keyboard fails and the current entry is booted.
+
+    while let Ok(key) = input.read_key() {
+        if let Some(key) = key {
+            if remaining_polls.take().is_some() {
+                menu.draw_countdown(None);
+            }
+
+            match key {
+                Key::Char('\r') | Key::Char('\n') => break,
+                Key::Special(ScanCode::UP) => menu.move_selection(-1),
+                Key::Special(ScanCode::DOWN) => menu.move_selection(1),
+                Key::Special(ScanCode::HOME) => menu.set_selection(0),
+                Key::Special(ScanCode::END) => menu.set_selection(config.entries.len() - 1),
+                // Entries can also be booted directly by number.
+                Key::Char(character) => {
+                    if let Some(index) = character
+                        .to_digit(10)
+                        .and_then(|digit| (digit as usize).checked_sub(1))
+                        .filter(|&index| index < config.entries.len())
+                    {
+                        menu.set_selection(index);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(polls) = remaining_polls {
+            if polls == 0 {
+                break;
+            }
+
+            if polls % POLLS_PER_SECOND == 0 {
+                menu.draw_countdown(remaining_polls);
+            }
+
+            remaining_polls = Some(polls - 1);
+        }
+
+        let _ = system::stall(POLL_MICROSECONDS);
+    }
+
+    let _ = menu.output.clear();
+
+    &config.entries[menu.selected]
+}
+
+struct Menu<'a> {
+    config: &'a Config,
+    output: OutputDevice,
+    selected: usize,
+}
+
+impl Menu<'_> {
+    fn move_selection(&mut self, offset: isize) {
+        let len = self.config.entries.len() as isize;
+        let selected = (self.selected as isize + offset).rem_euclid(len);
+
+        self.set_selection(selected as usize);
+    }
+
+    fn set_selection(&mut self, selected: usize) {
+        let previous = self.selected;
+        self.selected = selected;
+
+        self.draw_entry(previous);
+        self.draw_entry(selected);
+    }
+
+    fn draw(&mut self) {
+        let _ = self.output.clear();
+        let _ = self.output.set_cursor_visible(false);
+
+        let _ = writeln!(self.output, "Verdure OS UEFI Boot Loader\r");
+        let _ = writeln!(
+            self.output,
+            "Use the arrow keys or a number to choose an entry and Enter to boot it.\r"
+        );
+
+        for index in 0..self.config.entries.len() {
+            self.draw_entry(index);
+        }
+    }
+
+    fn draw_entry(&mut self, index: usize) {
+        let entry = &self.config.entries[index];
+
+        let (back_color, front_color) = if index == self.selected {
+            (BackColor::LightGray, FrontColor::Black)
+        } else {
+            (BackColor::Black, FrontColor::LightGray)
+        };
+
+        let _ = self.output.set_cursor_position(0, HEADER_ROWS + index);
+        let _ = self.output.set_colors(back_color, front_color);
+        let _ = write!(self.output, " {:>2}. {} ", index + 1, entry.title);
+        let _ = self
+            .output
+            .set_colors(BackColor::Black, FrontColor::LightGray);
+    }
+
+    // Erases the countdown line when there is no countdown.
+
+    fn draw_countdown(&mut self, remaining_polls: Option<u32>) {
+        let row = HEADER_ROWS + self.config.entries.len() + 1;
+
+        let _ = self.output.set_cursor_position(0, row);
+
+        match remaining_polls {
+            Some(polls) => {
+                let seconds = (polls + POLLS_PER_SECOND - 1) / POLLS_PER_SECOND;
+                let _ = write!(
+                    self.output,
+                    "Booting \"{}\" in {} second(s).   ",
+                    self.config.entries[self.selected].title, seconds
+                );
+            }
+            None => {
+                let _ = write!(self.output, "{:1$}", "", 79);
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//**************************************************************************************************
+// backtrace.rs                                                                                    *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use super::vmm;
+use crate::symbols;
+use core::fmt;
+
+const MAX_FRAMES: usize = 32;
+
+// Walks the chain of saved frame pointers. The kernel is built with frame pointers so every frame
+// starts with the caller's frame pointer followed by the return address.
+
+#[derive(Copy, Clone, Debug)]
+pub struct Backtrace {
+    instruction_pointer: Option<u64>,
+    frame_pointer: u64,
+}
+
+impl Backtrace {
+    // Must be inlined so the frame pointer belongs to the caller and stays valid while the
+    // backtrace is printed.
+
+    #[inline(always)]
+    pub fn current() -> Self {
+        let frame_pointer: u64;
+        unsafe {
+            asm!("mov {}, rbp", out(reg) frame_pointer, options(nomem, nostack));
+        }
+        Self {
+            instruction_pointer: None,
+            frame_pointer,
+        }
+    }
+
+    pub fn with_registers(instruction_pointer: u64, frame_pointer: u64) -> Self {
+        Self {
+            instruction_pointer: Some(instruction_pointer),
+            frame_pointer,
+        }
+    }
+}
+
+impl fmt::Display for Backtrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut index = 0;
+
+        if let Some(instruction_pointer) = self.instruction_pointer {
+            write_frame(f, index, instruction_pointer, instruction_pointer)?;
+            index += 1;
+        }
+
+        let mut frame_pointer = self.frame_pointer;
+
+        while index < MAX_FRAMES && is_valid_frame_pointer(frame_pointer) {
+            let frame = frame_pointer as *const u64;
+            let (next_frame_pointer, return_address) = unsafe { (*frame, *frame.add(1)) };
+
+            if return_address == 0 {
+                break;
+            }
+
+            // Return addresses point after the call so the call itself is resolved.
+
+            write_frame(f, index, return_address, return_address - 1)?;
+
+            frame_pointer = next_frame_pointer;
+            index += 1;
+        }
+
+        if index == MAX_FRAMES {
+            write!(f, "\n  ...")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_frame(
+    f: &mut fmt::Formatter<'_>,
+    index: usize,
+    address: u64,
+    lookup_address: u64,
+) -> fmt::Result {
+    if index != 0 {
+        writeln!(f)?;
+    }
+
+    match symbols::resolve(lookup_address as usize) {
+        Some(location) => write!(f, "  #{:<2} {:#018X} {}", index, address, location),
+        None => write!(f, "  #{:<2} {:#018X} <unknown>", index, address),
+    }
+}
+
+fn is_valid_frame_pointer(frame_pointer: u64) -> bool {
+    // Kernel stacks, including the BP and IST stacks, are all above the start of the kernel stack
+    // area. Anything else is a frame from the boot loader or a corrupted value.
+
+    frame_pointer % 8 == 0
+        && frame_pointer >= vmm::KERNEL_STACKS_VIRTUAL_START
+        && frame_pointer <= u64::MAX - 16
+}
@@ -4,13 +4,19 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+use crate::console;
 use crate::spinlock::{Spinlock, SpinlockGuard};
 use core::fmt::{Arguments, Error, Write};
-use kernel_interface::init::Args;
-use uart_8250_family::{SerialPort, Settings};
+use core::sync::atomic::{AtomicU16, Ordering};
+use kernel_interface::init::DebugConfig;
+use uart_8250_family::{Port, SerialPort, Settings};
 
 static WRITER: Spinlock<Writer> = Spinlock::new(Writer::new());
 
+// Base address of the configured serial port for the emergency path or 0 if there is none.
+
+static EMERGENCY_PORT: AtomicU16 = AtomicU16::new(0);
+
 #[derive(Clone, Debug)]
 pub struct Writer(Option<SerialPort>);
 
@@ -19,15 +25,16 @@ impl Writer {
         Self(None)
     }
 
-    pub unsafe fn config(&mut self, args: &Args) {
-        if args.debug_config.enabled {
-            let mut serial_port = SerialPort::new(args.debug_config.port_number);
+    pub unsafe fn config(&mut self, config: &DebugConfig) {
+        if config.enabled && !config.is_gdb_dedicated() {
+            let mut serial_port = SerialPort::new(config.port_number);
 
             let mut settings = Settings::default();
-            settings.set_baud_divisor(args.debug_config.baud_divisor);
+            settings.set_baud_divisor(config.baud_divisor);
 
             if serial_port.configure(settings).is_ok() {
                 self.0 = Some(serial_port);
+                EMERGENCY_PORT.store(config.port_number.base_address(), Ordering::Release);
                 return;
             }
         }
@@ -37,6 +44,7 @@ impl Writer {
 
     pub fn disable(&mut self) {
         self.0 = None;
+        EMERGENCY_PORT.store(0, Ordering::Release);
     }
 
     pub fn is_available(&self) -> bool {
@@ -64,13 +72,48 @@ pub fn writer() -> SpinlockGuard<'static, Writer> {
 
 pub fn _print(args: Arguments) {
     let _ = writer().write_fmt(args);
+    console::_print(args);
 }
 
+// Writes to the serial port only for output that has a separate form for the console.
+
+pub fn _print_serial(args: Arguments) {
+    let _ = writer().write_fmt(args);
+}
+
+// Writes to the serial port without taking the writer's lock. This is used by the panic handler
+// and exception handlers which may have interrupted code that holds the lock. Output from other
+// CPUs can be interleaved so they should be halted first.
+
+pub fn _emergency_print(args: Arguments) {
+    console::_emergency_print(args);
+
+    let base_address = EMERGENCY_PORT.load(Ordering::Acquire);
+
+    if base_address == 0 {
+        return;
+    }
+
+    let mut serial_port = unsafe { SerialPort::new(Port::new(base_address)) };
+    let _ = serial_port.write_fmt(args);
+}
+
+#[allow(unused_macros)]
 macro_rules! print {
     ($($arg:tt)*) => ($crate::arch::debug::_print(format_args!($($arg)*)));
 }
 
+#[allow(unused_macros)]
 macro_rules! println {
     () => (print!("\n"));
     ($($arg:tt)*) => (print!("{}\n", format_args!($($arg)*)))
 }
+
+macro_rules! emergency_print {
+    ($($arg:tt)*) => ($crate::arch::debug::_emergency_print(format_args!($($arg)*)));
+}
+
+macro_rules! emergency_println {
+    () => (emergency_print!("\n"));
+    ($($arg:tt)*) => (emergency_print!("{}\n", format_args!($($arg)*)))
+}
@@ -6,15 +6,21 @@
 
 use crate::drivers::timers::{Device, DeviceCalibration};
 use crate::spinlock::Spinlock;
-use units::Nanoseconds;
+use core::sync::atomic::{AtomicU64, Ordering};
+use units::{Nanoseconds, Time};
 use x86;
 use x86::cpuid;
 
 static STATE: Spinlock<State> = Spinlock::new(State {
-    time_per: None,
     calibration_start: None,
 });
 
+// Nanoseconds per count as a 32.32 fixed point number or 0 before calibration. A whole number of
+// nanoseconds would round to 0 on any TSC faster than 1 GHz. It is read without a lock so the
+// kernel log can convert timestamps.
+
+static SCALE: AtomicU64 = AtomicU64::new(0);
+
 pub unsafe fn create_device() -> Option<Device> {
     let (_, _, features) = cpuid::leaf_1::read();
 
@@ -68,12 +74,24 @@ pub fn finish_calibration(time_passed: Nanoseconds<u64>) {
 
     let difference = current_count - previous_count;
 
-    state.time_per = Some(time_passed / difference);
+    let scale = (u128::from(time_passed.into_inner()) << 32) / u128::from(difference.max(1));
+
+    SCALE.store(scale as u64, Ordering::Release);
     state.calibration_start = None;
 }
 
+// Converts a count difference to nanoseconds. Returns None if the TSC was not calibrated yet.
+
+pub fn count_to_nanoseconds(count: u64) -> Option<Nanoseconds<u64>> {
+    match SCALE.load(Ordering::Acquire) {
+        0 => None,
+        scale => Some(Nanoseconds::new(
+            ((u128::from(count) * u128::from(scale)) >> 32) as u64,
+        )),
+    }
+}
+
 struct State {
-    time_per: Option<Nanoseconds<u64>>,
     calibration_start: Option<u64>,
 }
 
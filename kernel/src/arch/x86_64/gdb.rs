@@ -0,0 +1,588 @@
+//**************************************************************************************************
+// gdb.rs                                                                                          *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use super::gdt;
+use super::idt::context::{Context, Register};
+use super::vmm;
+use crate::spinlock::Spinlock;
+use core::convert::TryFrom;
+use core::ptr;
+use io::{Read, Write};
+use kernel_interface::init::DebugConfig;
+use uart_8250_family::{SerialPort, Settings};
+use x86::control_registers::size_64::cr0;
+
+// Remote serial protocol stub for debugging the kernel with GDB. It is entered from the
+// breakpoint and debug exception handlers and talks to GDB until it is told to continue, step or
+// detach.
+
+//TODO Support breaking in with Ctrl-C once serial interrupts are handled.
+
+const BUFFER_SIZE: usize = 4096;
+
+const MAX_BREAKPOINTS: usize = 32;
+
+const BREAKPOINT_INSTRUCTION: u8 = 0xCC;
+
+// GDB's x86-64 register set starts with 16 general purpose registers and RIP which are 64 bit,
+// followed by EFLAGS and 6 segment registers which are 32 bit.
+
+const REGISTER_COUNT: usize = 18;
+
+const SEGMENT_REGISTER_COUNT: usize = 6;
+
+const WIDE_REGISTER_COUNT: usize = 17;
+
+static STATE: Spinlock<Option<State>> = Spinlock::new(None);
+
+pub unsafe fn init(config: &DebugConfig) {
+    if !config.gdb_enabled {
+        return;
+    }
+
+    let mut serial_port = SerialPort::new(config.gdb_port_number);
+
+    let mut settings = Settings::default();
+    settings.set_baud_divisor(config.baud_divisor);
+
+    if let Err(error) = serial_port.configure(settings) {
+        error!("Failed to configure the GDB serial port. {}", error);
+        return;
+    }
+
+    *STATE.lock() = Some(State {
+        serial_port,
+        packet: [0; BUFFER_SIZE],
+        response: Response::new(),
+        breakpoints: Breakpoints::new(),
+    });
+
+    info!("GDB stub initialized. Waiting for GDB to connect.");
+
+    breakpoint();
+}
+
+pub fn breakpoint() {
+    unsafe {
+        asm!("int3");
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Trap {
+    Breakpoint,
+    Debug,
+}
+
+// Returns false if the stub is not enabled so the exception can be handled normally.
+
+pub fn handle_trap(context: &mut Context, trap: Trap) -> bool {
+    let mut state_lock = STATE.lock();
+
+    let state = match state_lock.as_mut() {
+        Some(state) => state,
+        None => return false,
+    };
+
+    match trap {
+        Trap::Breakpoint => {
+            // GDB expects the instruction pointer to be at breakpoints it inserted instead of
+            // after the int3 instruction.
+
+            let address = context.register(Register::Rip) - 1;
+
+            if state.breakpoints.find(address).is_some() {
+                context.set_register(Register::Rip, address);
+            }
+        }
+        Trap::Debug => context.set_single_step(false),
+    }
+
+    state.run(context);
+
+    true
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Action {
+    Reply,
+    Continue,
+    Step,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Breakpoint {
+    address: u64,
+    original: u8,
+}
+
+struct Breakpoints([Option<Breakpoint>; MAX_BREAKPOINTS]);
+
+impl Breakpoints {
+    const fn new() -> Self {
+        Self([None; MAX_BREAKPOINTS])
+    }
+
+    // Only software breakpoints are supported. Returns None for other types so an empty reply
+    // tells GDB they are not supported.
+
+    fn change(&mut self, insert: bool, arguments: &[u8]) -> Option<Option<()>> {
+        let mut fields = arguments.split(|&byte| byte == b',');
+
+        if fields.next()? != b"0" {
+            return None;
+        }
+
+        let address = match fields.next().and_then(parse_hex) {
+            Some(address) => address,
+            None => return Some(None),
+        };
+
+        Some(if insert {
+            self.insert(address)
+        } else {
+            self.remove(address)
+        })
+    }
+
+    fn insert(&mut self, address: u64) -> Option<()> {
+        if self.find(address).is_some() {
+            return Some(());
+        }
+
+        let slot = self.0.iter_mut().find(|slot| slot.is_none())?;
+
+        let original = read_byte(address)?;
+        write_byte(address, BREAKPOINT_INSTRUCTION)?;
+
+        *slot = Some(Breakpoint { address, original });
+
+        Some(())
+    }
+
+    fn remove(&mut self, address: u64) -> Option<()> {
+        let index = self.find(address)?;
+        let breakpoint = self.0[index].take()?;
+
+        write_byte(breakpoint.address, breakpoint.original)
+    }
+
+    fn remove_all(&mut self) {
+        for slot in self.0.iter_mut() {
+            if let Some(breakpoint) = slot.take() {
+                write_byte(breakpoint.address, breakpoint.original);
+            }
+        }
+    }
+
+    fn find(&self, address: u64) -> Option<usize> {
+        self.0
+            .iter()
+            .position(|slot| slot.map_or(false, |breakpoint| breakpoint.address == address))
+    }
+}
+
+struct State {
+    serial_port: SerialPort,
+    packet: [u8; BUFFER_SIZE],
+    response: Response,
+    breakpoints: Breakpoints,
+}
+
+impl State {
+    fn run(&mut self, context: &mut Context) {
+        self.response.clear();
+        self.response.push_str("S05");
+        self.send_response();
+
+        loop {
+            let len = self.receive_packet();
+
+            self.response.clear();
+
+            let action = self.process(len, context);
+
+            match action {
+                Action::Reply => self.send_response(),
+                Action::Continue => return,
+                Action::Step => {
+                    context.set_single_step(true);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn process(&mut self, len: usize, context: &mut Context) -> Action {
+        let packet = &self.packet[..len];
+
+        let (command, arguments) = match packet.split_first() {
+            Some((&command, arguments)) => (command, arguments),
+            None => return Action::Reply,
+        };
+
+        match command {
+            b'?' => self.response.push_str("S05"),
+            b'g' => read_registers(&mut self.response, context),
+            b'G' => match write_registers(arguments, context) {
+                Some(()) => self.response.push_str("OK"),
+                None => self.response.push_str("E00"),
+            },
+            b'p' => match parse_hex(arguments) {
+                Some(number) if read_register(&mut self.response, context, number) => {}
+                _ => self.response.push_str("E00"),
+            },
+            b'P' => match write_register(arguments, context) {
+                Some(()) => self.response.push_str("OK"),
+                None => self.response.push_str("E00"),
+            },
+            b'm' => {
+                let response = &mut self.response;
+                if read_memory(arguments, response).is_none() {
+                    response.clear();
+                    response.push_str("E14");
+                }
+            }
+            b'M' => match write_memory(arguments) {
+                Some(()) => self.response.push_str("OK"),
+                None => self.response.push_str("E14"),
+            },
+            b'c' | b's' => {
+                if let Some(address) = parse_hex(arguments) {
+                    context.set_register(Register::Rip, address);
+                }
+
+                return if command == b'c' {
+                    Action::Continue
+                } else {
+                    Action::Step
+                };
+            }
+            b'Z' | b'z' => {
+                if let Some(result) = self.breakpoints.change(command == b'Z', arguments) {
+                    match result {
+                        Some(()) => self.response.push_str("OK"),
+                        None => self.response.push_str("E0E"),
+                    }
+                }
+            }
+            b'D' | b'k' => {
+                // The kernel cannot be killed so both detach and kill remove all breakpoints and
+                // let the kernel continue.
+
+                self.breakpoints.remove_all();
+
+                if command == b'D' {
+                    self.response.push_str("OK");
+                    self.send_response();
+                }
+
+                return Action::Continue;
+            }
+            b'H' => self.response.push_str("OK"),
+            b'q' => {
+                if arguments.starts_with(b"Supported") {
+                    self.response.push_str("PacketSize=1000");
+                } else if arguments.starts_with(b"Attached") {
+                    self.response.push_str("1");
+                }
+            }
+            _ => {}
+        }
+
+        Action::Reply
+    }
+
+    // Packets are framed as $<data>#<checksum> where the checksum is the sum of the data bytes
+    // modulo 256. Each packet is acknowledged with + or rejected with - to request it again.
+
+    fn receive_packet(&mut self) -> usize {
+        loop {
+            while self.read_serial() != b'$' {}
+
+            let mut len = 0;
+            let mut checksum = 0u8;
+
+            loop {
+                let byte = self.read_serial();
+
+                if byte == b'#' {
+                    break;
+                }
+
+                if len < BUFFER_SIZE {
+                    self.packet[len] = byte;
+                    len += 1;
+                }
+
+                checksum = checksum.wrapping_add(byte);
+            }
+
+            let expected = [self.read_serial(), self.read_serial()];
+
+            if parse_hex(&expected) == Some(checksum as u64) && len < BUFFER_SIZE {
+                self.write_serial(b"+");
+                return len;
+            }
+
+            self.write_serial(b"-");
+        }
+    }
+
+    fn send_response(&mut self) {
+        let checksum = self
+            .response
+            .as_slice()
+            .iter()
+            .fold(0u8, |checksum, &byte| checksum.wrapping_add(byte));
+
+        let trailer = [b'#', hex_digit(checksum >> 4), hex_digit(checksum & 0xF)];
+
+        loop {
+            self.write_serial(b"$");
+
+            let _ = self
+                .serial_port
+                .write(&mut self.response.buffer[..self.response.len]);
+
+            self.write_serial(&trailer);
+
+            if self.read_serial() != b'-' {
+                return;
+            }
+        }
+    }
+
+    fn read_serial(&mut self) -> u8 {
+        let mut buffer = [0];
+
+        // Line errors only affect the byte that was received so it is read again.
+
+        while self.serial_port.read_exact(&mut buffer).is_err() {}
+
+        buffer[0]
+    }
+
+    fn write_serial(&mut self, bytes: &[u8]) {
+        let mut buffer = [0; 3];
+        let buffer = &mut buffer[..bytes.len()];
+        buffer.copy_from_slice(bytes);
+        let _ = self.serial_port.write(buffer);
+    }
+}
+
+struct Response {
+    buffer: [u8; BUFFER_SIZE],
+    len: usize,
+}
+
+impl Response {
+    const fn new() -> Self {
+        Self {
+            buffer: [0; BUFFER_SIZE],
+            len: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+
+    fn remaining(&self) -> usize {
+        BUFFER_SIZE - self.len
+    }
+
+    fn push_str(&mut self, value: &str) {
+        for &byte in value.as_bytes() {
+            self.push_byte(byte);
+        }
+    }
+
+    fn push_byte(&mut self, byte: u8) {
+        if self.len < BUFFER_SIZE {
+            self.buffer[self.len] = byte;
+            self.len += 1;
+        }
+    }
+
+    fn push_hex_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.push_byte(hex_digit(byte >> 4));
+            self.push_byte(hex_digit(byte & 0xF));
+        }
+    }
+}
+
+fn read_registers(response: &mut Response, context: &Context) {
+    for number in 0..REGISTER_COUNT + SEGMENT_REGISTER_COUNT {
+        read_register(response, context, number as u64);
+    }
+}
+
+fn read_register(response: &mut Response, context: &Context, number: u64) -> bool {
+    let number = match u8::try_from(number) {
+        Ok(number) => number,
+        Err(_) => return false,
+    };
+
+    if let Ok(register) = Register::try_from(number) {
+        let value = context.register(register).to_le_bytes();
+        response.push_hex_bytes(&value[..register_size(number as usize)]);
+        return true;
+    }
+
+    // The kernel loads the same data selector into every data segment register.
+
+    let selector = match number as usize - REGISTER_COUNT {
+        0 => context.stack_frame().code_segment() as u32,
+        1 => context.stack_frame().stack_segment() as u32,
+        2..=5 => u32::from(gdt::kernel_data_selector()),
+        _ => return false,
+    };
+
+    response.push_hex_bytes(&selector.to_le_bytes());
+
+    true
+}
+
+fn write_registers(arguments: &[u8], context: &mut Context) -> Option<()> {
+    // Segment registers are ignored since they cannot be changed from here.
+
+    let mut offset = 0;
+
+    for number in 0..REGISTER_COUNT {
+        let end = offset + register_size(number) * 2;
+        let register = Register::try_from(number as u8).ok()?;
+
+        context.set_register(register, parse_hex_le(arguments.get(offset..end)?)?);
+        offset = end;
+    }
+
+    Some(())
+}
+
+fn register_size(number: usize) -> usize {
+    if number < WIDE_REGISTER_COUNT {
+        8
+    } else {
+        4
+    }
+}
+
+fn write_register(arguments: &[u8], context: &mut Context) -> Option<()> {
+    let separator = arguments.iter().position(|&byte| byte == b'=')?;
+
+    let number = u8::try_from(parse_hex(&arguments[..separator])?).ok()?;
+    let register = Register::try_from(number).ok()?;
+
+    context.set_register(register, parse_hex_le(&arguments[separator + 1..])?);
+
+    Some(())
+}
+
+fn read_memory(arguments: &[u8], response: &mut Response) -> Option<()> {
+    let (address, len) = parse_address_len(arguments)?;
+
+    if len > (response.remaining() / 2) as u64 {
+        return None;
+    }
+
+    for offset in 0..len {
+        response.push_hex_bytes(&[read_byte(address.checked_add(offset)?)?]);
+    }
+
+    Some(())
+}
+
+fn write_memory(arguments: &[u8]) -> Option<()> {
+    let separator = arguments.iter().position(|&byte| byte == b':')?;
+
+    let (address, len) = parse_address_len(&arguments[..separator])?;
+    let data = &arguments[separator + 1..];
+
+    if data.len() as u64 != len * 2 {
+        return None;
+    }
+
+    for (offset, byte) in data.chunks(2).enumerate() {
+        write_byte(address.checked_add(offset as u64)?, parse_hex(byte)? as u8)?;
+    }
+
+    Some(())
+}
+
+fn read_byte(address: u64) -> Option<u8> {
+    if !vmm::is_mapped(address) {
+        return None;
+    }
+
+    unsafe { Some(ptr::read_volatile(address as *const u8)) }
+}
+
+fn write_byte(address: u64, value: u8) -> Option<()> {
+    if !vmm::is_mapped(address) {
+        return None;
+    }
+
+    // Write protection is disabled for the write so breakpoints can be placed in read only code.
+
+    unsafe {
+        let original = cr0::read();
+
+        let mut writable = original;
+        writable.set_write_protect(false);
+        cr0::write(writable);
+
+        ptr::write_volatile(address as *mut u8, value);
+
+        cr0::write(original);
+    }
+
+    Some(())
+}
+
+fn parse_address_len(arguments: &[u8]) -> Option<(u64, u64)> {
+    let separator = arguments.iter().position(|&byte| byte == b',')?;
+
+    Some((
+        parse_hex(&arguments[..separator])?,
+        parse_hex(&arguments[separator + 1..])?,
+    ))
+}
+
+fn parse_hex(digits: &[u8]) -> Option<u64> {
+    if digits.is_empty() || digits.len() > 16 {
+        return None;
+    }
+
+    digits.iter().try_fold(0u64, |value, &digit| {
+        Some((value << 4) | (char::from(digit).to_digit(16)? as u64))
+    })
+}
+
+// Register values are sent in target byte order.
+
+fn parse_hex_le(digits: &[u8]) -> Option<u64> {
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+
+    let mut bytes = [0; 8];
+
+    for (index, pair) in digits.chunks(2).enumerate() {
+        *bytes.get_mut(index)? = parse_hex(pair)? as u8;
+    }
+
+    Some(u64::from_le_bytes(bytes))
+}
+
+fn hex_digit(value: u8) -> u8 {
+    b"0123456789abcdef"[(value & 0xF) as usize]
+}
@@ -10,12 +10,12 @@ use x86::segmentation::{
     load_data_selectors, segment,
     size_64::{load_cs, load_gdt},
 };
-use x86::tasks::size_64::tss_ldt;
+use x86::tasks::{load_task_register, size_64::tss_ldt};
 use x86::{ProtectionRing, Selector};
 
 // GDT mixes 8 byte (1 entry) and 16 byte (2 entries) descriptors so values are stored in
 // an 8 byte buffer.
-static mut ENTRIES: [u64; 5] = [0; 5];
+static mut ENTRIES: [u64; 7] = [0; 7];
 
 pub unsafe fn install() {
     // ENTRIES[0] is the null segment and is left at 0;
@@ -57,12 +57,17 @@ pub unsafe fn install() {
     tss.set_privilege_level(ProtectionRing::Level0);
     tss.set_descriptor_type(tss_ldt::DescriptorType::TssAvailable);
     tss.set_base_address(tss::offset());
+    tss.set_limit(tss::limit());
+    let tss_value = u128::from(tss);
+    ENTRIES[5] = tss_value as u64;
+    ENTRIES[6] = (tss_value >> 64) as u64;
 
     load_gdt(&ENTRIES[..].try_into().unwrap());
     load_cs(kernel_code_selector());
     load_data_selectors(kernel_data_selector());
+    load_task_register(tss_selector());
 
-    println!("GDT installed.");
+    info!("GDT installed.");
 }
 
 pub fn kernel_code_selector() -> Selector {
@@ -74,9 +79,13 @@ pub fn kernel_data_selector() -> Selector {
 }
 
 pub fn user_code_selector() -> Selector {
-    Selector::with_values(3, false, ProtectionRing::Level0)
+    Selector::with_values(3, false, ProtectionRing::Level3)
 }
 
 pub fn user_data_selector() -> Selector {
-    Selector::with_values(4, false, ProtectionRing::Level0)
+    Selector::with_values(4, false, ProtectionRing::Level3)
+}
+
+pub fn tss_selector() -> Selector {
+    Selector::with_values(5, false, ProtectionRing::Level0)
 }
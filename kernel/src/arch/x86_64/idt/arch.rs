@@ -4,74 +4,268 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
-use super::stack_frame::StackFrame;
+use super::context::{Context, ControlRegisters};
+use crate::arch::backtrace::Backtrace;
+use crate::arch::gdb::{self, Trap};
+use crate::arch::user;
+use crate::{stacks, tasks};
+use alloc::format;
+use core::fmt;
+use x86::control_registers::size_64::cr2;
+use x86::interrupts::{DescriptorTable, PageFaultErrorCode, SelectorErrorCode};
 
-pub(super) extern "x86-interrupt" fn divide_error_exception(stack_frame: &StackFrame) {}
+pub(super) fn divide_error_exception(context: &mut Context) {
+    fault(
+        context,
+        format_args!("A divide error exception was thrown."),
+    );
+}
+
+pub(super) fn debug_exception(context: &mut Context) {
+    if gdb::handle_trap(context, Trap::Debug) {
+        return;
+    }
+
+    emergency_println!(
+        "A debug exception was thrown at {:#X}.",
+        context.stack_frame().instruction_pointer()
+    );
+}
+
+pub(super) fn nmi(context: &mut Context) {
+    // Another CPU panicked and is halting the others so its output isn't interleaved.
+
+    if crate::is_panicking() {
+        unsafe { x86::stall() }
+    }
+
+    emergency_println!(
+        "A non-maskable interrupt was received at {:#X}.",
+        context.stack_frame().instruction_pointer()
+    );
+}
+
+pub(super) fn breakpoint(context: &mut Context) {
+    if gdb::handle_trap(context, Trap::Breakpoint) {
+        return;
+    }
+
+    // The saved instruction pointer is after the int3 instruction.
+
+    emergency_println!(
+        "A breakpoint was hit at {:#X}.",
+        context.stack_frame().instruction_pointer() - 1
+    );
+}
+
+pub(super) fn overflow_exception(context: &mut Context) {
+    fault(context, format_args!("An overflow exception was thrown."));
+}
 
-pub(super) extern "x86-interrupt" fn debug_exception(stack_frame: &StackFrame) {}
+pub(super) fn bound_range_exceeded_exception(context: &mut Context) {
+    fault(
+        context,
+        format_args!("A bound range exceeded exception was thrown."),
+    );
+}
 
-pub(super) extern "x86-interrupt" fn nmi(stack_frame: &StackFrame) {}
+pub(super) fn invalid_opcode_exception(context: &mut Context) {
+    fault(
+        context,
+        format_args!("An invalid opcode exception was thrown."),
+    );
+}
 
-pub(super) extern "x86-interrupt" fn breakpoint(stack_frame: &StackFrame) {}
+pub(super) fn device_not_available_exception(context: &mut Context) {
+    fault(
+        context,
+        format_args!("A device not available exception was thrown."),
+    );
+}
 
-pub(super) extern "x86-interrupt" fn overflow_exception(stack_frame: &StackFrame) {}
+pub(super) fn double_fault_exception(context: &mut Context) {
+    // This runs on its own IST stack so a fault caused by an overflowed kernel stack can still be
+    // reported. CR2 still holds the address of the original page fault in that case.
+
+    check_stack_overflow(cr2::read());
+
+    fault(
+        context,
+        format_args!("A double fault exception was thrown."),
+    );
+}
+
+pub(super) fn coprocessor_segment_exception(context: &mut Context) {
+    fault(
+        context,
+        format_args!("A coprocessor segment overrun exception was thrown."),
+    );
+}
 
-pub(super) extern "x86-interrupt" fn bound_range_exceeded_exception(stack_frame: &StackFrame) {}
+pub(super) fn invalid_tss_exception(context: &mut Context) {
+    selector_fault(context, "An invalid TSS exception");
+}
 
-pub(super) extern "x86-interrupt" fn invalid_opcode_exception(stack_frame: &StackFrame) {}
+pub(super) fn segment_not_present_exception(context: &mut Context) {
+    selector_fault(context, "A segment not present exception");
+}
 
-pub(super) extern "x86-interrupt" fn device_not_available_exception(stack_frame: &StackFrame) {}
+pub(super) fn stack_fault_exception(context: &mut Context) {
+    selector_fault(context, "A stack fault exception");
+}
 
-pub(super) extern "x86-interrupt" fn double_fault_exception(
-    stack_frame: &StackFrame,
-    error_code: u64,
-) {
-    println!("A double fault exception was thrown.");
+pub(super) fn general_protection_exception(context: &mut Context) {
+    selector_fault(context, "A general protection exception");
 }
 
-pub(super) extern "x86-interrupt" fn coprocessor_segment_exception(stack_frame: &StackFrame) {}
+pub(super) fn page_fault_exception(context: &mut Context) {
+    let address = cr2::read();
+
+    check_stack_overflow(address);
+
+    let error_code = PageFaultErrorCode::from(context.error_code());
+
+    let access = if error_code.is_instruction_fetch() {
+        "an instruction fetch"
+    } else if error_code.is_write() {
+        "a write"
+    } else {
+        "a read"
+    };
 
-pub(super) extern "x86-interrupt" fn invalid_tss_exception(
-    stack_frame: &StackFrame,
-    error_code: u64,
-) {
+    let cause = if error_code.is_reserved_bit_violation() {
+        "a reserved bit set in a paging structure"
+    } else if error_code.is_protection_key_violation() {
+        "a protection key violation"
+    } else if error_code.is_protection_violation() {
+        "a protection violation"
+    } else {
+        "a non-present page"
+    };
+
+    fault(
+        context,
+        format_args!(
+            "A page fault exception was thrown by {} of {:#X} due to {}.",
+            access, address, cause
+        ),
+    );
+}
+
+pub(super) fn x87_fpu_floating_point_error(context: &mut Context) {
+    fault(
+        context,
+        format_args!("An x87 FPU floating point error was thrown."),
+    );
 }
 
-pub(super) extern "x86-interrupt" fn segment_not_present_exception(
-    stack_frame: &StackFrame,
-    error_code: u64,
-) {
+pub(super) fn alignment_check_exception(context: &mut Context) {
+    fault(
+        context,
+        format_args!("An alignment check exception was thrown."),
+    );
 }
 
-pub(super) extern "x86-interrupt" fn stack_fault_exception(
-    stack_frame: &StackFrame,
-    error_code: u64,
-) {
+pub(super) fn machine_check_exception(context: &mut Context) {
+    fault(
+        context,
+        format_args!("A machine check exception was thrown."),
+    );
 }
 
-pub(super) extern "x86-interrupt" fn general_protection_exception(
-    stack_frame: &StackFrame,
-    error_code: u64,
-) {
+pub(super) fn simd_floating_point_exception(context: &mut Context) {
+    fault(
+        context,
+        format_args!("A SIMD floating point exception was thrown."),
+    );
 }
 
-pub(super) extern "x86-interrupt" fn page_fault_exception(
-    stack_frame: &StackFrame,
-    error_code: u64,
-) {
-    println!("A page fault exception was thrown.");
+pub(super) fn virtualization_exception(context: &mut Context) {
+    fault(
+        context,
+        format_args!("A virtualization exception was thrown."),
+    );
 }
 
-pub(super) extern "x86-interrupt" fn x87_fpu_floating_point_error(stack_frame: &StackFrame) {}
+fn selector_fault(context: &mut Context, exception: &str) {
+    let error_code = SelectorErrorCode::from(context.error_code());
+
+    // An error code of 0 means the exception was not caused by loading a selector.
+
+    if error_code.is_null() {
+        return fault(context, format_args!("{} was thrown.", exception));
+    }
 
-pub(super) extern "x86-interrupt" fn alignment_check_exception(
-    stack_frame: &StackFrame,
-    error_code: u64,
-) {
+    let table = match error_code.table() {
+        DescriptorTable::Gdt => "GDT",
+        DescriptorTable::Idt => "IDT",
+        DescriptorTable::Ldt => "LDT",
+    };
+
+    let origin = if error_code.is_external() {
+        " It was caused by an event external to the program."
+    } else {
+        ""
+    };
+
+    fault(
+        context,
+        format_args!(
+            "{} was thrown for entry {} of the {}.{}",
+            exception,
+            error_code.index(),
+            table,
+            origin
+        ),
+    );
 }
 
-pub(super) extern "x86-interrupt" fn machine_check_exception(stack_frame: &StackFrame) {}
+fn fault(context: &mut Context, description: fmt::Arguments) {
+    let control_registers = ControlRegisters::read();
+
+    // A fault in user mode only ends the task that caused it. The registers are logged a line
+    // per record since all of them don't fit in one.
+
+    if context.is_user_mode() {
+        error!("{} It was thrown in user mode.", description);
+
+        for line in format!("{}\n{}", context, control_registers).lines() {
+            error!("{}", line);
+        }
+
+        if unsafe { tasks::terminate_current() } {
+            user::exit(context, tasks::idle);
+            return;
+        }
+    }
 
-pub(super) extern "x86-interrupt" fn simd_floating_point_exception(stack_frame: &StackFrame) {}
+    let mode = if context.is_user_mode() {
+        "user"
+    } else {
+        "kernel"
+    };
 
-pub(super) extern "x86-interrupt" fn virtualization_exception(stack_frame: &StackFrame) {}
+    let backtrace = Backtrace::with_registers(
+        context.stack_frame().instruction_pointer(),
+        context.frame_pointer(),
+    );
+
+    crate::panic_with_backtrace(format_args!(
+        "{} It was thrown in {} mode.\n{}\n{}\nCall chain:\n{}",
+        description, mode, context, control_registers, backtrace
+    ));
+}
+
+fn check_stack_overflow(address: u64) {
+    // A fault inside a guard page means a kernel stack grew past its bottom.
+
+    if let Some(stack) = stacks::find_guard_hit(address as usize) {
+        panic!(
+            "Stack overflow in task {} at {:#X}. The stack spans {:#X} to {:#X}.",
+            stack.task_id(),
+            address,
+            stack.segment().start(),
+            stack.top()
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//**************************************************************************************************
+// context.rs                                                                                      *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use super::stack_frame::StackFrame;
+use core::fmt;
+use enums::numeric_enum;
+use memory::{GetBit, SetBitAssign};
+use x86::control_registers::size_64::{cr0, cr2, cr3, cr4};
+
+// Everything saved on the stack by the exception entry stubs in entry.rs. Field order is the
+// reverse of the push order for the same reason as in StackFrame.
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Context {
+    r15: u64,
+    r14: u64,
+    r13: u64,
+    r12: u64,
+    r11: u64,
+    r10: u64,
+    r9: u64,
+    r8: u64,
+    rbp: u64,
+    rdi: u64,
+    rsi: u64,
+    rdx: u64,
+    rcx: u64,
+    rbx: u64,
+    rax: u64,
+    vector: u64,
+    error_code: u64,
+    stack_frame: StackFrame,
+}
+
+impl Context {
+    pub fn vector(&self) -> u8 {
+        self.vector as u8
+    }
+
+    pub fn error_code(&self) -> u64 {
+        self.error_code
+    }
+
+    pub fn frame_pointer(&self) -> u64 {
+        self.rbp
+    }
+
+    pub fn stack_frame(&self) -> &StackFrame {
+        &self.stack_frame
+    }
+
+    pub fn stack_frame_mut(&mut self) -> &mut StackFrame {
+        &mut self.stack_frame
+    }
+
+    pub fn register(&self, register: Register) -> u64 {
+        match register {
+            Register::Rax => self.rax,
+            Register::Rbx => self.rbx,
+            Register::Rcx => self.rcx,
+            Register::Rdx => self.rdx,
+            Register::Rsi => self.rsi,
+            Register::Rdi => self.rdi,
+            Register::Rbp => self.rbp,
+            Register::Rsp => self.stack_frame.stack_pointer(),
+            Register::R8 => self.r8,
+            Register::R9 => self.r9,
+            Register::R10 => self.r10,
+            Register::R11 => self.r11,
+            Register::R12 => self.r12,
+            Register::R13 => self.r13,
+            Register::R14 => self.r14,
+            Register::R15 => self.r15,
+            Register::Rip => self.stack_frame.instruction_pointer(),
+            Register::Rflags => self.stack_frame.flags(),
+        }
+    }
+
+    pub fn set_register(&mut self, register: Register, value: u64) {
+        match register {
+            Register::Rax => self.rax = value,
+            Register::Rbx => self.rbx = value,
+            Register::Rcx => self.rcx = value,
+            Register::Rdx => self.rdx = value,
+            Register::Rsi => self.rsi = value,
+            Register::Rdi => self.rdi = value,
+            Register::Rbp => self.rbp = value,
+            Register::Rsp => self.stack_frame.set_stack_pointer(value),
+            Register::R8 => self.r8 = value,
+            Register::R9 => self.r9 = value,
+            Register::R10 => self.r10 = value,
+            Register::R11 => self.r11 = value,
+            Register::R12 => self.r12 = value,
+            Register::R13 => self.r13 = value,
+            Register::R14 => self.r14 = value,
+            Register::R15 => self.r15 = value,
+            Register::Rip => self.stack_frame.set_instruction_pointer(value),
+            Register::Rflags => self.stack_frame.set_flags(value),
+        }
+    }
+
+    // The trap flag raises a debug exception after the next instruction once the handler returns.
+
+    pub fn single_step(&self) -> bool {
+        self.stack_frame.flags().get_bit(8)
+    }
+
+    pub fn set_single_step(&mut self, value: bool) {
+        let mut flags = self.stack_frame.flags();
+        flags.set_bit_assign(8, value);
+        self.stack_frame.set_flags(flags);
+    }
+
+    pub fn is_user_mode(&self) -> bool {
+        self.stack_frame.code_segment() & 0x3 != 0
+    }
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frame = &self.stack_frame;
+
+        writeln!(
+            f,
+            "RAX={:#018X} RBX={:#018X} RCX={:#018X} RDX={:#018X}",
+            self.rax, self.rbx, self.rcx, self.rdx
+        )?;
+        writeln!(
+            f,
+            "RSI={:#018X} RDI={:#018X} RBP={:#018X} RSP={:#018X}",
+            self.rsi,
+            self.rdi,
+            self.rbp,
+            frame.stack_pointer()
+        )?;
+        writeln!(
+            f,
+            "R8 ={:#018X} R9 ={:#018X} R10={:#018X} R11={:#018X}",
+            self.r8, self.r9, self.r10, self.r11
+        )?;
+        writeln!(
+            f,
+            "R12={:#018X} R13={:#018X} R14={:#018X} R15={:#018X}",
+            self.r12, self.r13, self.r14, self.r15
+        )?;
+        write!(
+            f,
+            "RIP={:#018X} RFL={:#018X} CS ={:#06X} SS ={:#06X} ERR={:#X}",
+            frame.instruction_pointer(),
+            frame.flags(),
+            frame.code_segment(),
+            frame.stack_segment(),
+            self.error_code
+        )
+    }
+}
+
+// Registers that can be read and written through a Context. They are numbered the same as GDB's
+// x86-64 registers.
+
+numeric_enum!(
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub enum Register {
+        Rax = 0,
+        Rbx = 1,
+        Rcx = 2,
+        Rdx = 3,
+        Rsi = 4,
+        Rdi = 5,
+        Rbp = 6,
+        Rsp = 7,
+        R8 = 8,
+        R9 = 9,
+        R10 = 10,
+        R11 = 11,
+        R12 = 12,
+        R13 = 13,
+        R14 = 14,
+        R15 = 15,
+        Rip = 16,
+        Rflags = 17,
+    }
+
+    impl TryFrom<u8>;
+);
+
+#[derive(Debug, Clone, Copy)]
+pub struct ControlRegisters {
+    cr0: u64,
+    cr2: u64,
+    cr3: u64,
+    cr4: u64,
+}
+
+impl ControlRegisters {
+    pub fn read() -> Self {
+        Self {
+            cr0: cr0::read().into(),
+            cr2: cr2::read(),
+            cr3: cr3::read(),
+            cr4: cr4::read().into(),
+        }
+    }
+
+    pub fn cr2(&self) -> u64 {
+        self.cr2
+    }
+}
+
+impl fmt::Display for ControlRegisters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CR0={:#018X} CR2={:#018X} CR3={:#018X} CR4={:#018X}",
+            self.cr0, self.cr2, self.cr3, self.cr4
+        )
+    }
+}
@@ -0,0 +1,120 @@
+//**************************************************************************************************
+// entry.rs                                                                                        *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+// Exception entry stubs. Each one pushes a dummy error code if the processor does not push one
+// followed by its vector so every exception has the same stack layout. The common path then saves
+// the general purpose registers to form a Context and passes it to handle_exception. The processor
+// aligns the stack to 16 bytes before pushing the stack frame and 22 quadwords are pushed in total
+// so the stack is still aligned at the call.
+
+global_asm!(
+    r#"
+.intel_syntax noprefix
+
+.macro exception_stub vector, has_error_code
+exception_stub_\vector:
+.if \has_error_code == 0
+    push 0
+.endif
+    push \vector
+    jmp exception_common
+.endm
+
+.section .text
+exception_common:
+    push rax
+    push rbx
+    push rcx
+    push rdx
+    push rsi
+    push rdi
+    push rbp
+    push r8
+    push r9
+    push r10
+    push r11
+    push r12
+    push r13
+    push r14
+    push r15
+    mov rdi, rsp
+    cld
+    call handle_exception
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    pop r11
+    pop r10
+    pop r9
+    pop r8
+    pop rbp
+    pop rdi
+    pop rsi
+    pop rdx
+    pop rcx
+    pop rbx
+    pop rax
+    add rsp, 16
+    iretq
+
+exception_stub 0, 0
+exception_stub 1, 0
+exception_stub 2, 0
+exception_stub 3, 0
+exception_stub 4, 0
+exception_stub 5, 0
+exception_stub 6, 0
+exception_stub 7, 0
+exception_stub 8, 1
+exception_stub 9, 0
+exception_stub 10, 1
+exception_stub 11, 1
+exception_stub 12, 1
+exception_stub 13, 1
+exception_stub 14, 1
+exception_stub 15, 0
+exception_stub 16, 0
+exception_stub 17, 1
+exception_stub 18, 0
+exception_stub 19, 0
+exception_stub 20, 0
+
+.section .rodata
+.balign 8
+.global EXCEPTION_STUBS
+EXCEPTION_STUBS:
+    .quad exception_stub_0
+    .quad exception_stub_1
+    .quad exception_stub_2
+    .quad exception_stub_3
+    .quad exception_stub_4
+    .quad exception_stub_5
+    .quad exception_stub_6
+    .quad exception_stub_7
+    .quad exception_stub_8
+    .quad exception_stub_9
+    .quad exception_stub_10
+    .quad exception_stub_11
+    .quad exception_stub_12
+    .quad exception_stub_13
+    .quad exception_stub_14
+    .quad exception_stub_15
+    .quad exception_stub_16
+    .quad exception_stub_17
+    .quad exception_stub_18
+    .quad exception_stub_19
+    .quad exception_stub_20
+
+.att_syntax prefix
+"#
+);
+
+pub(super) const STUB_COUNT: usize = 21;
+
+extern "C" {
+    pub(super) static EXCEPTION_STUBS: [u64; STUB_COUNT];
+}
@@ -5,43 +5,42 @@
 //**************************************************************************************************
 
 pub mod arch;
+pub mod context;
+mod entry;
 mod stack_frame;
 
-use super::gdt;
+use self::context::Context;
+use super::{gdt, tss};
 use core::convert::TryInto;
-use x86::interrupts::size_64::{interrupt_trap_gate, load_idt};
+use x86::interrupts::size_64::{
+    interrupt_trap_gate::{self, IstIndex},
+    load_idt,
+};
 use x86::ProtectionRing;
 
-static mut ENTRIES: [interrupt_trap_gate::Descriptor; 21] =
-    [interrupt_trap_gate::Descriptor::new(); 21];
+static mut ENTRIES: [interrupt_trap_gate::Descriptor; entry::STUB_COUNT] =
+    [interrupt_trap_gate::Descriptor::new(); entry::STUB_COUNT];
 
 pub unsafe fn install() {
-    create_arch_entry(0, arch::divide_error_exception as u64);
-    create_arch_entry(1, arch::debug_exception as u64);
-    create_arch_entry(2, arch::nmi as u64);
-    create_arch_entry(3, arch::breakpoint as u64);
-    create_arch_entry(4, arch::overflow_exception as u64);
-    create_arch_entry(5, arch::bound_range_exceeded_exception as u64);
-    create_arch_entry(6, arch::invalid_opcode_exception as u64);
-    create_arch_entry(7, arch::device_not_available_exception as u64);
-    create_arch_entry(8, arch::double_fault_exception as u64);
-    create_arch_entry(9, arch::coprocessor_segment_exception as u64);
-    create_arch_entry(10, arch::invalid_tss_exception as u64);
-    create_arch_entry(11, arch::segment_not_present_exception as u64);
-    create_arch_entry(12, arch::stack_fault_exception as u64);
-    create_arch_entry(13, arch::general_protection_exception as u64);
-    create_arch_entry(14, arch::page_fault_exception as u64);
-    create_arch_entry(16, arch::x87_fpu_floating_point_error as u64);
-    create_arch_entry(17, arch::alignment_check_exception as u64);
-    create_arch_entry(18, arch::machine_check_exception as u64);
-    create_arch_entry(19, arch::simd_floating_point_exception as u64);
-    create_arch_entry(20, arch::virtualization_exception as u64);
-
     // 15 and 21-31 are reserved by Intel. 32 - 255 are user defined.
 
+    for (number, &stub) in entry::EXCEPTION_STUBS.iter().enumerate() {
+        if number != 15 {
+            create_arch_entry(number, stub);
+        }
+    }
+
+    // These can be raised while the current stack is unusable, for example after a kernel stack
+    // overflow, so they always switch to their own stack.
+
+    set_entry_ist(1, tss::DEBUG_IST);
+    set_entry_ist(2, tss::NMI_IST);
+    set_entry_ist(8, tss::DOUBLE_FAULT_IST);
+    set_entry_ist(18, tss::MACHINE_CHECK_IST);
+
     load_idt(&ENTRIES[..].try_into().expect("IDT too large."));
 
-    println!("IDT installed.");
+    info!("IDT installed.");
 }
 
 unsafe fn create_arch_entry(number: usize, offset: u64) {
@@ -51,3 +50,37 @@ unsafe fn create_arch_entry(number: usize, offset: u64) {
     ENTRIES[number].set_segment_selector(gdt::kernel_code_selector());
     ENTRIES[number].set_offset(offset);
 }
+
+unsafe fn set_entry_ist(number: usize, ist: IstIndex) {
+    ENTRIES[number].set_ist(Some(ist));
+}
+
+// Called by the entry stubs with the context of the interrupted code. Any changes to the context
+// are restored when the handler returns.
+
+#[no_mangle]
+extern "C" fn handle_exception(context: &mut Context) {
+    match context.vector() {
+        0 => arch::divide_error_exception(context),
+        1 => arch::debug_exception(context),
+        2 => arch::nmi(context),
+        3 => arch::breakpoint(context),
+        4 => arch::overflow_exception(context),
+        5 => arch::bound_range_exceeded_exception(context),
+        6 => arch::invalid_opcode_exception(context),
+        7 => arch::device_not_available_exception(context),
+        8 => arch::double_fault_exception(context),
+        9 => arch::coprocessor_segment_exception(context),
+        10 => arch::invalid_tss_exception(context),
+        11 => arch::segment_not_present_exception(context),
+        12 => arch::stack_fault_exception(context),
+        13 => arch::general_protection_exception(context),
+        14 => arch::page_fault_exception(context),
+        16 => arch::x87_fpu_floating_point_error(context),
+        17 => arch::alignment_check_exception(context),
+        18 => arch::machine_check_exception(context),
+        19 => arch::simd_floating_point_exception(context),
+        20 => arch::virtualization_exception(context),
+        vector => panic!("Exception stub for reserved vector {} was called.", vector),
+    }
+}
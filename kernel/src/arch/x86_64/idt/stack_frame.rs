@@ -4,17 +4,58 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
-// Representation of the handler's stack (figure 6-8 in Intel manual) for use
-// with the x86-interrupt calling convention. Field order is reversed compared
-// to the figure since the stack goes from a higher to lowers address unlike
-// structs which are lower to higher.
+// Representation of the stack pushed by the processor on an interrupt (figure 6-8 in Intel
+// manual). Field order is reversed compared to the figure since the stack goes from a higher to
+// lowers address unlike structs which are lower to higher.
 
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
-pub(super) struct StackFrame {
+pub struct StackFrame {
     instruction_pointer: u64,
     code_segment: u64,
     flags: u64,
     stack_pointer: u64,
     stack_segment: u64,
 }
+
+impl StackFrame {
+    pub fn instruction_pointer(&self) -> u64 {
+        self.instruction_pointer
+    }
+
+    pub fn set_instruction_pointer(&mut self, value: u64) {
+        self.instruction_pointer = value;
+    }
+
+    pub fn code_segment(&self) -> u64 {
+        self.code_segment
+    }
+
+    pub fn set_code_segment(&mut self, value: u64) {
+        self.code_segment = value;
+    }
+
+    pub fn flags(&self) -> u64 {
+        self.flags
+    }
+
+    pub fn set_flags(&mut self, value: u64) {
+        self.flags = value;
+    }
+
+    pub fn stack_pointer(&self) -> u64 {
+        self.stack_pointer
+    }
+
+    pub fn set_stack_pointer(&mut self, value: u64) {
+        self.stack_pointer = value;
+    }
+
+    pub fn stack_segment(&self) -> u64 {
+        self.stack_segment
+    }
+
+    pub fn set_stack_segment(&mut self, value: u64) {
+        self.stack_segment = value;
+    }
+}
@@ -11,6 +11,7 @@ use acpi::madt::MadtEntry;
 use acpi::RootEntry;
 use core::lazy::OnceCell;
 use kernel_interface::init::Args;
+use x86::apic::local::{CommonRegisters, DeliveryMode, DestinationShorthand};
 use x86::msr::ia32_apic_base;
 use x86::{apic, cpuid};
 
@@ -89,6 +90,38 @@ pub fn registers() -> SpinlockGuard<'static, Registers> {
     REGISTERS.lock()
 }
 
+// Sends an NMI to every other CPU. Returns false if the local APIC is not available or its
+// registers are in use by this CPU.
+
+pub fn send_nmi_to_others() -> bool {
+    let mut registers = match REGISTERS.try_lock() {
+        Some(registers) => registers,
+        None => return false,
+    };
+
+    unsafe {
+        match &mut *registers {
+            Registers::NotAvailable => return false,
+            Registers::Apic(registers) => {
+                let mut ipi = apic::local::Ipi::new();
+                ipi.set_delivery_mode(DeliveryMode::NMI);
+                ipi.set_level_assert(true);
+                ipi.set_destination_shorthand(DestinationShorthand::ALL_EXCLUDING_SELF);
+                registers.write_icr(ipi);
+            }
+            Registers::X2Apic(registers) => {
+                let mut ipi = apic::local::X2Ipi::new();
+                ipi.set_delivery_mode(DeliveryMode::NMI);
+                ipi.set_level_assert(true);
+                ipi.set_destination_shorthand(DestinationShorthand::ALL_EXCLUDING_SELF);
+                registers.write_icr(ipi);
+            }
+        }
+    }
+
+    true
+}
+
 pub enum Registers {
     NotAvailable,
     Apic(apic::local::Registers),
@@ -4,26 +4,73 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+use core::sync::atomic::{AtomicU64, Ordering};
 use kernel_interface::init::Args;
+use units::Nanoseconds;
 pub use x86::interrupts;
 pub use x86::stall;
+use x86::cpuid;
 
-use crate::{heap, pmm, tm};
+use crate::{
+    command_line, console, heap, initial_image, machine, pmm, stacks, symbols, tm, uefi_runtime,
+};
 
 #[macro_use]
 pub mod debug;
+pub mod backtrace;
 pub mod drivers;
+pub mod gdb;
 pub mod gdt;
 pub mod idt;
 pub mod local_apic;
 pub mod sync;
 pub mod tss;
+pub mod user;
 pub mod vmm;
 
 pub const PAGE_SIZE: usize = 4096;
 
+// Identifies the current CPU by its initial local APIC ID.
+
+pub fn cpu_id() -> u32 {
+    unsafe {
+        let (_, additional_information, _) = cpuid::leaf_1::read();
+        additional_information.initial_apic_id() as u32
+    }
+}
+
+// TSC count when the bootstrap processor entered the kernel. Timestamps count from here. The TSCs
+// of all CPUs are assumed to be synchronized which holds for processors with an invariant TSC.
+
+static BOOT_TIMESTAMP: AtomicU64 = AtomicU64::new(0);
+
+// Counts since boot. Use timestamp_to_time to convert them once the TSC is calibrated.
+
+pub fn timestamp() -> u64 {
+    let count = unsafe { x86::tsc::read() };
+    count.saturating_sub(BOOT_TIMESTAMP.load(Ordering::Relaxed))
+}
+
+pub fn timestamp_to_time(timestamp: u64) -> Option<Nanoseconds<u64>> {
+    drivers::timers::tsc::count_to_nanoseconds(timestamp)
+}
+
+// Sleeps until the next interrupt is received.
+
+pub fn wait_for_interrupt() {
+    unsafe { x86::halt() }
+}
+
+// Stops other CPUs with an NMI. Their NMI handlers halt once they see the kernel is panicking.
+
+pub fn halt_other_cpus() {
+    local_apic::send_nmi_to_others();
+}
+
 #[no_mangle]
 pub unsafe extern "sysv64" fn entry(args_ptr: *const Args) {
+    BOOT_TIMESTAMP.store(x86::tsc::read(), Ordering::Relaxed);
+
     if args_ptr.is_null() {
         return;
     }
@@ -34,18 +81,31 @@ pub unsafe extern "sysv64" fn entry(args_ptr: *const Args) {
         return;
     }
 
-    debug::writer().config(args);
+    debug::writer().config(&args.debug_config);
 
-    println!("Entered Verdure OS x86-64 kernel.");
+    info!("Entered Verdure OS x86-64 kernel.");
+
+    // Parse the command line once output is available so invalid options are reported. Its serial
+    // options replace the ones from the boot loader.
+    command_line::init(args);
+
+    let debug_config = command_line::debug_config(&args.debug_config);
+    debug::writer().config(&debug_config);
 
     interrupts::disable();
 
+    tss::init();
+
     gdt::install();
 
     idt::install();
 
     pmm::init_stage_one(args);
 
+    // Move the UEFI runtime services to the addresses the VMM maps them to while firmware is
+    // still identity mapped.
+    uefi_runtime::init(args);
+
     // Initialize virtual memory manager.
     vmm::init(args);
 
@@ -55,8 +115,26 @@ pub unsafe extern "sysv64" fn entry(args_ptr: *const Args) {
     let virtual_args_ptr = vmm::convert_physical_ptr(args_ptr);
     args = &*virtual_args_ptr;
 
+    // Show output on the screen now that the framebuffer is mapped.
+    console::init(args);
+
     pmm::init_stage_two();
 
+    // Record the BP stack and prepare the area for task kernel stacks.
+    stacks::init();
+
+    // Locate the kernel symbols passed by the boot loader for backtraces.
+    symbols::init(args);
+
+    // Keep the initial image from the boot loader for the first user task.
+    initial_image::init(args);
+
+    // Log what the machine is from the SMBIOS tables.
+    machine::init(args);
+
+    // Wait for GDB to attach if the remote stub is enabled.
+    gdb::init(&debug_config);
+
     // Try to enable local APIC for the timers and starting APs.
     local_apic::init(args);
 
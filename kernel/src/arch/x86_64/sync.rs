@@ -15,8 +15,7 @@ pub struct LockState {
 
 pub fn start_lock() -> LockState {
     let state = LockState {
-        //TODO Save interrupt state.
-        enable_interrupts: true,
+        enable_interrupts: interrupts::are_enabled(),
     };
     unsafe {
         interrupts::disable();
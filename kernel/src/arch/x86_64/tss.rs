@@ -1,13 +1,57 @@
 //**************************************************************************************************
 // tss.rs                                                                                          *
-// Copyright (c) 2019-2020 Aurora Berta-Oldham                                                     *
+// Copyright (c) 2019-2021 The Verdure Project                                                     *
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+use super::PAGE_SIZE;
+use core::mem;
+use x86::interrupts::size_64::interrupt_trap_gate::IstIndex;
 use x86::tasks::size_64::Tss;
 
+pub const DOUBLE_FAULT_IST: IstIndex = IstIndex::One;
+pub const NMI_IST: IstIndex = IstIndex::Two;
+pub const MACHINE_CHECK_IST: IstIndex = IstIndex::Three;
+pub const DEBUG_IST: IstIndex = IstIndex::Four;
+
+const IST_STACK_PAGES: usize = 4;
+
+// IST stacks are part of the kernel image so they can be used before the VMM is initialized.
+
+#[repr(C, align(4096))]
+struct IstStack([u8; IST_STACK_PAGES * PAGE_SIZE]);
+
+static mut DOUBLE_FAULT_STACK: IstStack = IstStack([0; IST_STACK_PAGES * PAGE_SIZE]);
+static mut NMI_STACK: IstStack = IstStack([0; IST_STACK_PAGES * PAGE_SIZE]);
+static mut MACHINE_CHECK_STACK: IstStack = IstStack([0; IST_STACK_PAGES * PAGE_SIZE]);
+static mut DEBUG_STACK: IstStack = IstStack([0; IST_STACK_PAGES * PAGE_SIZE]);
+
 static mut TSS: Tss = Tss::new();
 
+pub unsafe fn init() {
+    TSS.set_ist(DOUBLE_FAULT_IST, stack_top(&DOUBLE_FAULT_STACK));
+    TSS.set_ist(NMI_IST, stack_top(&NMI_STACK));
+    TSS.set_ist(MACHINE_CHECK_IST, stack_top(&MACHINE_CHECK_STACK));
+    TSS.set_ist(DEBUG_IST, stack_top(&DEBUG_STACK));
+
+    info!("TSS initialized.");
+}
+
+// Interrupts from user mode switch to this stack. It must be the kernel stack of the task that
+// runs next.
+
+pub unsafe fn set_kernel_stack(top: u64) {
+    TSS.set_rsp_0(top);
+}
+
 pub fn offset() -> u64 {
     unsafe { (&TSS as *const Tss) as u64 }
 }
+
+pub fn limit() -> u32 {
+    (mem::size_of::<Tss>() - 1) as u32
+}
+
+fn stack_top(stack: &IstStack) -> u64 {
+    (stack as *const IstStack as u64) + mem::size_of::<IstStack>() as u64
+}
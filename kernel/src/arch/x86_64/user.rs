@@ -0,0 +1,72 @@
+//**************************************************************************************************
+// user.rs                                                                                         *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use super::idt::context::Context;
+use super::{gdt, tss};
+use kernel_interface::init::BP_STACK_VIRTUAL_TOP;
+
+// User mode starts with only interrupts enabled. Bit 1 is reserved and always set.
+
+const USER_FLAGS: u64 = 0x202;
+
+// Kernel mode is resumed with the same flags after a task is terminated.
+
+const KERNEL_FLAGS: u64 = 0x202;
+
+// Switches to user mode at the entry point with the stack pointer. The address space of the task
+// must be active. Interrupts and exceptions from user mode continue on the kernel stack. General
+// purpose registers are cleared so no kernel values are visible to the task.
+
+pub unsafe fn enter(entry: u64, stack_pointer: u64, kernel_stack_top: u64) -> ! {
+    tss::set_kernel_stack(kernel_stack_top);
+
+    asm!(
+        "mov ds, ax",
+        "mov es, ax",
+        "push rax",
+        "push rsi",
+        "push rdx",
+        "push rcx",
+        "push rdi",
+        "xor eax, eax",
+        "xor ebx, ebx",
+        "xor ecx, ecx",
+        "xor edx, edx",
+        "xor esi, esi",
+        "xor edi, edi",
+        "xor ebp, ebp",
+        "xor r8d, r8d",
+        "xor r9d, r9d",
+        "xor r10d, r10d",
+        "xor r11d, r11d",
+        "xor r12d, r12d",
+        "xor r13d, r13d",
+        "xor r14d, r14d",
+        "xor r15d, r15d",
+        "iretq",
+        in("rax") u64::from(gdt::user_data_selector()),
+        in("rcx") u64::from(gdt::user_code_selector()),
+        in("rdx") USER_FLAGS,
+        in("rsi") stack_pointer,
+        in("rdi") entry,
+        options(noreturn)
+    );
+}
+
+// Rewrites the interrupted user frame so the exception handler returns to the function in kernel
+// mode on the bootstrap processor's stack. The stack pointer is offset like a call pushed a return
+// address. The kernel stack of the terminated task stays in use until the handler returns, so the
+// function is where it can be freed.
+
+pub fn exit(context: &mut Context, function: extern "C" fn() -> !) {
+    let stack_frame = context.stack_frame_mut();
+
+    stack_frame.set_instruction_pointer(function as usize as u64);
+    stack_frame.set_code_segment(u64::from(gdt::kernel_code_selector()));
+    stack_frame.set_flags(KERNEL_FLAGS);
+    stack_frame.set_stack_pointer(BP_STACK_VIRTUAL_TOP - 8);
+    stack_frame.set_stack_segment(u64::from(gdt::kernel_data_selector()));
+}
@@ -7,13 +7,19 @@
 use crate::frame::Frame;
 use crate::pmm;
 use crate::spinlock::Spinlock;
+use alloc::vec::Vec;
 use core::convert::TryInto;
 use core::ptr;
-use kernel_interface::init::{Args, MemoryType};
+use core::slice;
+use kernel_interface::init::{Args, MemoryType, UefiMemoryDescriptor};
 use units;
 use x86::control_registers::size_64::{cr3, cr4};
+use x86::msr::{ia32_efer, ia32_pat};
 use x86::paging::size_64 as paging;
-use x86::paging::size_64::{MapType, MapValue, MapperInterface, RootTable};
+use x86::paging::size_64::{
+    DirectoryPtrTable, DirectoryPtrValue, DirectoryTable, DirectoryValue, MapType, MapValue,
+    MapperInterface, Pml4Table, Pml4Value, Pml5Table, Pml5Value, RootTable, Table, TableValue,
+};
 use x86::{cpuid, PhysicalAddress52, VirtualAddress48, VirtualAddress57};
 
 use core::fmt::Debug;
@@ -21,10 +27,40 @@ pub use kernel_interface::init::{
     BP_STACK_VIRTUAL_BOTTOM, BP_STACK_VIRTUAL_TOP, KERNEL_VIRTUAL_START,
 };
 use memory::Address64;
+use uefi::memory::{MemoryAttributes, MemoryType as UefiMemoryType};
 use units::{Bytes, Information, Pebibytes, Tebibytes};
 
 pub const PHYSICAL_MAP_VIRTUAL_START: u64 = 0xffff800000000000;
 
+// Kernel stacks are placed in their own area below the BP stack so guard pages between them are
+// never mapped by anything else.
+
+pub const KERNEL_STACKS_VIRTUAL_START: u64 = 0xffffff0000000000;
+
+pub const KERNEL_STACKS_VIRTUAL_END: u64 = BP_STACK_VIRTUAL_BOTTOM;
+
+// UEFI runtime regions are mapped at their physical address offset from this area. Firmware
+// sometimes relies on regions keeping their distance from each other so they are not packed.
+
+pub const UEFI_RUNTIME_VIRTUAL_START: u64 = 0xfffffe0000000000;
+
+pub const UEFI_RUNTIME_VIRTUAL_END: u64 = KERNEL_STACKS_VIRTUAL_START;
+
+// User address spaces only use the lower half of a level 4 address space even when level 5
+// paging is active. The first page is never mapped so null pointers always fault.
+
+pub const USER_VIRTUAL_START: u64 = 0x1000;
+
+pub const USER_VIRTUAL_END: u64 = 0x0000_8000_0000_0000;
+
+pub use x86::paging::size_64::{CacheType, MapError, Protection};
+
+// Root table entries from this index up map the kernel half of every address space.
+
+const KERNEL_ROOT_INDEX: usize = 256;
+
+const ROOT_ENTRIES: usize = 512;
+
 static STATE: Spinlock<Option<State>> = Spinlock::new(None);
 
 pub unsafe fn init(args: &Args) {
@@ -33,7 +69,34 @@ pub unsafe fn init(args: &Args) {
     assert!(state.is_none(), "VMM has already been initialized.");
 
     let linear_address_57 = cr4::read().la57();
-    let pages_1gib = cpuid::leaf_80000001::read().pages_1gib();
+    let extended_features = cpuid::leaf_80000001::read();
+    let pages_1gib = extended_features.pages_1gib();
+
+    // Execute disable bits may only be used once they are enabled. Without them every page that
+    // can be read can also be executed.
+
+    let no_execute = extended_features.execute_disable();
+
+    if no_execute {
+        let mut efer = ia32_efer::read();
+        efer.set_no_execute_enabled(true);
+        ia32_efer::write(efer);
+
+        info!("Execute disable is enabled.");
+    } else {
+        info!("Execute disable is not supported.");
+    }
+
+    // Write combining is only available once IA32_PAT is set. Without PAT the page attribute bit
+    // is ignored and write combining falls back to write back.
+
+    let (_, _, features) = cpuid::leaf_1::read();
+
+    if features.pat() {
+        ia32_pat::write(ia32_pat::VALUE);
+    } else {
+        warn!("PAT is not supported. Write combining will not be used.");
+    }
 
     let mut allocator = IdentityMapperInterface;
 
@@ -41,7 +104,7 @@ pub unsafe fn init(args: &Args) {
     let final_root_table;
     let root_table_address = allocator.alloc_table();
 
-    println!(
+    debug!(
         "Created kernel root page table at {:#X}.",
         root_table_address
     );
@@ -50,10 +113,10 @@ pub unsafe fn init(args: &Args) {
 
     let map_type = {
         if pages_1gib {
-            println!("1 Gib is the max supported page size.");
+            debug!("1 Gib is the max supported page size.");
             MapType::Page1Gib
         } else {
-            println!("2 Mib is the max supported page size.");
+            debug!("2 Mib is the max supported page size.");
             MapType::Page2Mib
         }
     };
@@ -68,7 +131,7 @@ pub unsafe fn init(args: &Args) {
     let physical_map_page_count;
 
     if linear_address_57 {
-        println!("Level 5 paging is active.");
+        info!("Level 5 paging is active.");
 
         let root_table_ptr = root_table_address.as_mut_ptr();
 
@@ -78,7 +141,7 @@ pub unsafe fn init(args: &Args) {
         physical_map_size = Pebibytes::new(32).convert();
         physical_map_page_count = physical_map_size.into_inner() / page_size;
     } else {
-        println!("Level 4 paging is active.");
+        info!("Level 4 paging is active.");
 
         let root_table_ptr = root_table_address.as_mut_ptr();
 
@@ -101,15 +164,29 @@ pub unsafe fn init(args: &Args) {
         )
         .expect("Failed to create physical memory mapping.");
 
-    println!(
+    mapper
+        .protect(
+            root_table,
+            PHYSICAL_MAP_VIRTUAL_START,
+            map_type,
+            physical_map_page_count,
+            kernel_protection(true, false, no_execute),
+        )
+        .expect("Failed to protect physical memory mapping.");
+
+    debug!(
         "Created physical memory mapping using {} large pages.",
         physical_map_page_count
     );
 
     // Map kernel sections of memory. The boot loader may use non-contiguous memory sections
-    // as long as the binary data is stored in order of the virtual mapping.
+    // as long as the binary data is stored in order of the virtual mapping. The kernel runs at
+    // the address the boot loader moved it to.
+
+    let kernel_start = KERNEL_VIRTUAL_START + args.kernel_slide;
+    let mut kernel_virtual = kernel_start;
 
-    let mut kernel_virtual = KERNEL_VIRTUAL_START;
+    info!("Kernel slide is {:#X}.", args.kernel_slide);
 
     for entry in args
         .memory_map
@@ -130,7 +207,7 @@ pub unsafe fn init(args: &Args) {
             )
             .expect("Failed to map kernel.");
 
-        println!(
+        debug!(
             "Created kernel mapping for section at {:#X} using {} pages.",
             segment.start(),
             page_count,
@@ -139,7 +216,89 @@ pub unsafe fn init(args: &Args) {
         kernel_virtual += segment.len() as u64;
     }
 
-    println!("Created all kernel mappings.");
+    debug!("Created all kernel mappings.");
+
+    // Protect the kernel with the permissions the boot loader gave each segment. Pages outside of
+    // every segment are only readable. A page shared by two segments gets the permissions of both
+    // unless that would make it writable and executable. The boot loader refuses those already.
+
+    let page_size = MapType::Page4Kib.size_in_bytes();
+
+    mapper
+        .protect(
+            root_table,
+            kernel_start,
+            MapType::Page4Kib,
+            (kernel_virtual - kernel_start) / page_size,
+            kernel_protection(false, false, no_execute),
+        )
+        .expect("Failed to protect kernel.");
+
+    let mut previous_last_page: Option<(u64, Protection)> = None;
+
+    for segment in args.kernel_segments.as_slice() {
+        if segment.len == 0 {
+            continue;
+        }
+
+        let start = u64::from(segment.start);
+        let first_page = start & !(page_size - 1);
+        let last_page = (start + segment.len as u64 - 1) & !(page_size - 1);
+
+        let protection = kernel_protection(segment.writable, segment.executable, no_execute);
+
+        mapper
+            .protect(
+                root_table,
+                first_page,
+                MapType::Page4Kib,
+                (last_page - first_page) / page_size + 1,
+                protection,
+            )
+            .expect("Failed to protect kernel segment.");
+
+        let mut first_page_protection = protection;
+
+        if let Some((page, previous_protection)) = previous_last_page {
+            if page == first_page {
+                first_page_protection = protection.union(previous_protection);
+
+                if no_execute && first_page_protection.writable && first_page_protection.executable
+                {
+                    panic!(
+                        "Kernel segments with writable and executable permissions share the page \
+                         at {:#X}.",
+                        page
+                    );
+                }
+
+                mapper
+                    .protect(
+                        root_table,
+                        page,
+                        MapType::Page4Kib,
+                        1,
+                        first_page_protection,
+                    )
+                    .expect("Failed to protect kernel segment.");
+            }
+        }
+
+        let last_page_protection = if first_page == last_page {
+            first_page_protection
+        } else {
+            protection
+        };
+
+        previous_last_page = Some((last_page, last_page_protection));
+
+        debug!(
+            "Protected kernel segment at {:#X} as r{}{}.",
+            start,
+            if segment.writable { "w" } else { "-" },
+            if segment.executable { "x" } else { "-" },
+        );
+    }
 
     // Map kernel stack.
 
@@ -162,9 +321,19 @@ pub unsafe fn init(args: &Args) {
                 MapType::Page4Kib,
                 page_count,
             )
-            .expect("Failed to map kernel.");
+            .expect("Failed to map kernel stack.");
 
-        println!(
+        mapper
+            .protect(
+                root_table,
+                kernel_stack_virtual,
+                MapType::Page4Kib,
+                page_count,
+                kernel_protection(true, false, no_execute),
+            )
+            .expect("Failed to protect kernel stack.");
+
+        debug!(
             "Created kernel stack mapping for section at {:#X} using {} pages.",
             segment.start(),
             page_count,
@@ -173,22 +342,110 @@ pub unsafe fn init(args: &Args) {
         kernel_stack_virtual += segment.len() as u64;
     }
 
-    println!("Created all kernel stack mappings.");
+    debug!("Created all kernel stack mappings.");
+
+    // Map UEFI runtime regions where SetVirtualAddressMap was told they would be. Firmware may
+    // keep data in code regions so those are writable and executable. Data regions can't be
+    // executed.
+
+    for descriptor in args.system_info.uefi_runtime_map.as_slice() {
+        let len = descriptor.number_of_pages * MapType::Page4Kib.size_in_bytes();
+
+        let virtual_address = match uefi_runtime_virtual_address(descriptor.physical_start, len) {
+            Some(virtual_address) => virtual_address,
+            None => {
+                warn!(
+                    "UEFI runtime region at {:#X} is outside of the UEFI runtime area.",
+                    descriptor.physical_start
+                );
+                continue;
+            }
+        };
+
+        mapper
+            .map(
+                root_table,
+                virtual_address,
+                descriptor.physical_start,
+                MapType::Page4Kib,
+                descriptor.number_of_pages,
+            )
+            .expect("Failed to map UEFI runtime region.");
+
+        let executable = descriptor.region_type == UefiMemoryType::RUNTIME_SERVICES_CODE
+            && !descriptor.attribute.contains(MemoryAttributes::XP);
+
+        mapper
+            .protect(
+                root_table,
+                virtual_address,
+                MapType::Page4Kib,
+                descriptor.number_of_pages,
+                kernel_protection(true, executable, no_execute),
+            )
+            .expect("Failed to protect UEFI runtime region.");
+
+        let cache_type = uefi_runtime_cache_type(descriptor, features.pat());
+
+        mapper
+            .set_cache_type(
+                root_table,
+                virtual_address,
+                MapType::Page4Kib,
+                descriptor.number_of_pages,
+                cache_type,
+            )
+            .expect("Failed to set the cache type of UEFI runtime region.");
+
+        debug!(
+            "Created {:?} UEFI runtime mapping for region at {:#X} using {} pages.",
+            cache_type, descriptor.physical_start, descriptor.number_of_pages,
+        );
+    }
+
+    // User address spaces share the kernel half by copying the root table entries above
+    // KERNEL_ROOT_INDEX. Every one of them gets a table now so mappings the kernel adds later are
+    // seen by all address spaces.
+
+    match root_table {
+        RootTable::Pml5(table_ptr) => {
+            for entry in (&mut *table_ptr).iter_mut().skip(KERNEL_ROOT_INDEX) {
+                if let Pml5Value::None = entry.value() {
+                    entry
+                        .set_value(Pml5Value::Pml4Table(allocator.alloc_table()))
+                        .expect("Failed to create kernel half root table entry.");
+                }
+            }
+        }
+        RootTable::Pml4(table_ptr) => {
+            for entry in (&mut *table_ptr).iter_mut().skip(KERNEL_ROOT_INDEX) {
+                if let Pml4Value::None = entry.value() {
+                    entry
+                        .set_value(Pml4Value::DirectoryPtrTable(allocator.alloc_table()))
+                        .expect("Failed to create kernel half root table entry.");
+                }
+            }
+        }
+    }
+
+    debug!("Created kernel half root table entries.");
 
     // Update CR3 with kernel page table.
 
     cr3::write(cr3::FlagsValue::new(root_table_address, false, false).unwrap());
 
-    println!("Wrote new root page table to CR3.");
+    debug!("Wrote new root page table to CR3.");
 
     // Finish initialization.
 
     *state = Some(State {
         kernel_table: final_root_table,
+        kernel_table_address: root_table_address,
+        no_execute,
         heap_start: Address64::new(PHYSICAL_MAP_VIRTUAL_START + physical_map_size.into_inner()),
     });
 
-    println!("VMM initialized.");
+    info!("VMM initialized.");
 }
 
 pub unsafe fn allocate_pages<TVirtualAddress: TryInto<u64>>(
@@ -212,7 +469,7 @@ pub unsafe fn allocate_pages<TVirtualAddress: TryInto<u64>>(
     for i in 0..len {
         // TODO Consider option to have mapper allocate memory for page.
 
-        let page = pmm::allocate_frame();
+        let page = pmm::allocate_frame().expect("Out of physical memory.");
 
         let next_virtual_address = converted_virtual_address + (4096 * i as u64);
         let next_physical_address = page.segment().start();
@@ -227,6 +484,16 @@ pub unsafe fn allocate_pages<TVirtualAddress: TryInto<u64>>(
             )
             .expect("Failed to map page.");
 
+        mapper
+            .protect(
+                state.kernel_table,
+                next_virtual_address,
+                MapType::Page4Kib,
+                1,
+                kernel_protection(true, false, state.no_execute),
+            )
+            .expect("Failed to protect page.");
+
         ptr::write_bytes(
             next_virtual_address as *mut u8,
             0,
@@ -235,6 +502,61 @@ pub unsafe fn allocate_pages<TVirtualAddress: TryInto<u64>>(
     }
 }
 
+pub unsafe fn free_pages<TVirtualAddress: TryInto<u64>>(
+    virtual_address: TVirtualAddress,
+    len: usize,
+) {
+    let converted_virtual_address = virtual_address
+        .try_into()
+        .ok()
+        .expect("Invalid virtual address for unmapping.");
+
+    for i in 0..len {
+        let next_virtual_address = converted_virtual_address + (4096 * i as u64);
+
+        // The lock is released before freeing the frame since the PMM may need to grow the heap.
+
+        let value = {
+            let mut state_lock = STATE.lock();
+
+            let state = state_lock.as_mut().expect("VMM not initialized.");
+
+            let mut allocator = KernelSpaceMapperInterface;
+            let mut mapper = paging::Mapper::new(&mut allocator);
+
+            let value = mapper
+                .unmap(state.kernel_table, next_virtual_address)
+                .expect("Failed to unmap page.");
+
+            x86::paging::invalidate_page(next_virtual_address);
+
+            value
+        };
+
+        if let MapValue::Page4Kib(physical_address) = value {
+            pmm::free_frame(Frame::from_address(u64::from(physical_address) as usize));
+        }
+    }
+}
+
+pub fn is_mapped(virtual_address: u64) -> bool {
+    let state_lock = STATE.lock();
+
+    let state = match state_lock.as_ref() {
+        Some(state) => state,
+        None => return false,
+    };
+
+    let mut allocator = KernelSpaceMapperInterface;
+    let mapper = paging::Mapper::new(&mut allocator);
+
+    unsafe {
+        mapper
+            .translate(state.kernel_table, virtual_address)
+            .map_or(false, MapValue::is_mapped)
+    }
+}
+
 pub unsafe fn convert_physical_ptr_mut<T>(ptr: *mut T) -> *mut T {
     let working_ptr = ptr as *mut u8;
     working_ptr.add(PHYSICAL_MAP_VIRTUAL_START as usize) as *mut T
@@ -245,6 +567,53 @@ pub unsafe fn convert_physical_ptr<T>(ptr: *const T) -> *const T {
     working_ptr.add(PHYSICAL_MAP_VIRTUAL_START as usize) as *const T
 }
 
+// Memory mapped IO is always uncached. Other regions use the fastest type the firmware says they
+// support.
+
+fn uefi_runtime_cache_type(descriptor: &UefiMemoryDescriptor, pat: bool) -> CacheType {
+    let attributes = descriptor.attribute;
+
+    if descriptor.region_type == UefiMemoryType::MEMORY_MAPPED_IO
+        || descriptor.region_type == UefiMemoryType::MEMORY_MAPPED_IO_PORT_SPACE
+    {
+        CacheType::Uncached
+    } else if attributes.contains(MemoryAttributes::WB) {
+        CacheType::WriteBack
+    } else if attributes.contains(MemoryAttributes::WT) {
+        CacheType::WriteThrough
+    } else if attributes.contains(MemoryAttributes::WC) && pat {
+        CacheType::WriteCombining
+    } else {
+        CacheType::Uncached
+    }
+}
+
+// The virtual address UEFI runtime memory is mapped to. Returns None if the region does not fit in
+// the UEFI runtime area.
+
+pub fn uefi_runtime_virtual_address(physical_address: u64, len: u64) -> Option<u64> {
+    let virtual_address = UEFI_RUNTIME_VIRTUAL_START.checked_add(physical_address)?;
+
+    if virtual_address.checked_add(len)? <= UEFI_RUNTIME_VIRTUAL_END {
+        Some(virtual_address)
+    } else {
+        None
+    }
+}
+
+// Switches the current CPU back to the kernel's own address space, such as before the address
+// space of a task is dropped.
+
+pub unsafe fn activate_kernel_address_space() {
+    let root_table_address = STATE
+        .lock()
+        .as_ref()
+        .expect("VMM not initialized.")
+        .kernel_table_address;
+
+    cr3::write(cr3::FlagsValue::new(root_table_address, false, false).unwrap());
+}
+
 pub fn heap_start() -> Address64 {
     STATE
         .lock()
@@ -253,9 +622,333 @@ pub fn heap_start() -> Address64 {
         .heap_start
 }
 
+// An address space for a user task. The lower half belongs to the task and the kernel half is
+// shared with every other address space. Pages are mapped as user accessible and freed along with
+// the tables that hold them when the address space is dropped. Shared ranges are only unmapped
+// since their memory belongs to someone else.
+
+#[derive(Debug)]
+pub struct AddressSpace {
+    root_table: RootTable,
+    root_table_address: PhysicalAddress52,
+    no_execute: bool,
+    shared: Vec<(u64, usize)>,
+}
+
+impl AddressSpace {
+    pub unsafe fn new() -> Self {
+        let (kernel_table, no_execute) = {
+            let state_lock = STATE.lock();
+            let state = state_lock.as_ref().expect("VMM not initialized.");
+            (state.kernel_table, state.no_execute)
+        };
+
+        let mut allocator = KernelSpaceMapperInterface;
+        let root_table_address = allocator.alloc_table();
+        let root_table_ptr = allocator.convert_to_virtual_ptr::<u64>(root_table_address);
+
+        let (root_table, kernel_root_table_ptr) = match kernel_table {
+            RootTable::Pml5(kernel_ptr) => (
+                RootTable::Pml5(root_table_ptr as *mut Pml5Table),
+                kernel_ptr as *const u64,
+            ),
+            RootTable::Pml4(kernel_ptr) => (
+                RootTable::Pml4(root_table_ptr as *mut Pml4Table),
+                kernel_ptr as *const u64,
+            ),
+        };
+
+        ptr::copy_nonoverlapping(
+            kernel_root_table_ptr.add(KERNEL_ROOT_INDEX),
+            root_table_ptr.add(KERNEL_ROOT_INDEX),
+            ROOT_ENTRIES - KERNEL_ROOT_INDEX,
+        );
+
+        Self {
+            root_table,
+            root_table_address,
+            no_execute,
+            shared: Vec::new(),
+        }
+    }
+
+    // The physical address of the root table for CR3.
+
+    pub fn root_table_address(&self) -> PhysicalAddress52 {
+        self.root_table_address
+    }
+
+    // Makes this the address space of the current CPU. It must stay alive until another one is
+    // activated.
+
+    pub unsafe fn activate(&self) {
+        cr3::write(cr3::FlagsValue::new(self.root_table_address, false, false).unwrap());
+    }
+
+    // Maps cleared pages. None of the pages may be mapped already.
+
+    pub unsafe fn allocate_pages(
+        &mut self,
+        virtual_address: u64,
+        count: usize,
+        protection: Protection,
+    ) -> Result<(), MapError> {
+        Self::check_range(virtual_address, count)?;
+
+        let mut allocator = KernelSpaceMapperInterface;
+        let mut mapper = paging::Mapper::new(&mut allocator);
+
+        for i in 0..count {
+            let next_virtual_address = virtual_address + (PAGE_SIZE * i) as u64;
+
+            if mapper
+                .translate(self.root_table, next_virtual_address)?
+                .is_mapped()
+            {
+                return Err(MapError::AlreadyMapped);
+            }
+
+            let frame = pmm::allocate_frame().ok_or(MapError::AllocationFailed)?;
+            let physical_address = frame.segment().start();
+
+            ptr::write_bytes(
+                convert_physical_ptr_mut(physical_address as *mut u8),
+                0,
+                PAGE_SIZE,
+            );
+
+            // Pages mapped before a failure are freed with the address space but this frame is not
+            // in it yet.
+
+            if let Err(error) = mapper.map(
+                self.root_table,
+                next_virtual_address,
+                physical_address,
+                MapType::Page4Kib,
+                1,
+            ) {
+                pmm::free_frame(frame);
+                return Err(error);
+            }
+        }
+
+        self.protect(virtual_address, count, protection)
+    }
+
+    // Maps memory the address space does not own such as memory shared with the kernel. None of the
+    // pages may be mapped already.
+
+    pub unsafe fn map_shared(
+        &mut self,
+        virtual_address: u64,
+        physical_address: u64,
+        count: usize,
+        protection: Protection,
+    ) -> Result<(), MapError> {
+        Self::check_range(virtual_address, count)?;
+
+        let mut allocator = KernelSpaceMapperInterface;
+        let mut mapper = paging::Mapper::new(&mut allocator);
+
+        for i in 0..count {
+            if mapper
+                .translate(self.root_table, virtual_address + (PAGE_SIZE * i) as u64)?
+                .is_mapped()
+            {
+                return Err(MapError::AlreadyMapped);
+            }
+        }
+
+        // The range is recorded first so pages mapped before a failure are not freed either.
+
+        self.shared.push((virtual_address, count));
+
+        mapper.map(
+            self.root_table,
+            virtual_address,
+            physical_address,
+            MapType::Page4Kib,
+            count as u64,
+        )?;
+
+        self.protect(virtual_address, count, protection)
+    }
+
+    pub unsafe fn protect(
+        &mut self,
+        virtual_address: u64,
+        count: usize,
+        protection: Protection,
+    ) -> Result<(), MapError> {
+        Self::check_range(virtual_address, count)?;
+
+        let protection = Protection {
+            executable: protection.executable || !self.no_execute,
+            user_accessible: true,
+            ..protection
+        };
+
+        let mut allocator = KernelSpaceMapperInterface;
+        let mut mapper = paging::Mapper::new(&mut allocator);
+
+        mapper.protect(
+            self.root_table,
+            virtual_address,
+            MapType::Page4Kib,
+            count as u64,
+            protection,
+        )
+    }
+
+    // Copies data into mapped pages through the physical memory mapping so the address space does
+    // not need to be active.
+
+    pub unsafe fn write(&mut self, virtual_address: u64, data: &[u8]) -> Result<(), MapError> {
+        let mut allocator = KernelSpaceMapperInterface;
+        let mapper = paging::Mapper::new(&mut allocator);
+
+        let mut written = 0;
+
+        while written < data.len() {
+            let next_virtual_address = virtual_address
+                .checked_add(written as u64)
+                .filter(|&address| address < USER_VIRTUAL_END)
+                .ok_or(MapError::InvalidVirtualAddress)?;
+
+            let page_offset = (next_virtual_address % PAGE_SIZE as u64) as usize;
+            let len = (PAGE_SIZE - page_offset).min(data.len() - written);
+
+            let physical_address = match mapper.translate(self.root_table, next_virtual_address)? {
+                MapValue::Page4Kib(address) => u64::from(address) as usize + page_offset,
+                _ => return Err(MapError::NotMapped),
+            };
+
+            let destination = slice::from_raw_parts_mut(
+                convert_physical_ptr_mut(physical_address as *mut u8),
+                len,
+            );
+            destination.copy_from_slice(&data[written..written + len]);
+
+            written += len;
+        }
+
+        Ok(())
+    }
+
+    fn check_range(virtual_address: u64, count: usize) -> Result<(), MapError> {
+        let end = (count as u64)
+            .checked_mul(PAGE_SIZE as u64)
+            .and_then(|len| virtual_address.checked_add(len));
+
+        match end {
+            Some(end)
+                if virtual_address % PAGE_SIZE as u64 == 0
+                    && virtual_address >= USER_VIRTUAL_START
+                    && end <= USER_VIRTUAL_END =>
+            {
+                Ok(())
+            }
+            _ => Err(MapError::InvalidVirtualAddress),
+        }
+    }
+}
+
+impl Drop for AddressSpace {
+    fn drop(&mut self) {
+        // Only the user half is freed. The kernel half tables are shared.
+
+        unsafe {
+            let mut allocator = KernelSpaceMapperInterface;
+            let mut mapper = paging::Mapper::new(&mut allocator);
+
+            for &(virtual_address, count) in self.shared.iter() {
+                for i in 0..count {
+                    let _ = mapper.unmap(self.root_table, virtual_address + (PAGE_SIZE * i) as u64);
+                }
+            }
+
+            if let RootTable::Pml5(table_ptr) = self.root_table {
+                // The user half is limited to the first level 4 table.
+
+                if let Pml5Value::Pml4Table(address) = (&*table_ptr)[0].value() {
+                    free_user_pml_4_table(address);
+                }
+
+                free_frame(self.root_table_address);
+            } else {
+                free_user_pml_4_table(self.root_table_address);
+            }
+        }
+    }
+}
+
+unsafe impl Send for AddressSpace {}
+
+const PAGE_SIZE: usize = 4096;
+
+// Kernel pages are never user accessible. Without execute disable bits every page that can be
+// read can also be executed.
+
+fn kernel_protection(writable: bool, executable: bool, no_execute: bool) -> Protection {
+    Protection {
+        writable,
+        executable: executable || !no_execute,
+        user_accessible: false,
+    }
+}
+
+// Address spaces only map 4 KiB pages so larger pages are never found in the user half.
+
+unsafe fn free_user_pml_4_table(address: PhysicalAddress52) {
+    let table = &*convert_physical_ptr(address.as_mut_ptr::<Pml4Table>());
+
+    for entry in table.iter().take(KERNEL_ROOT_INDEX) {
+        if let Pml4Value::DirectoryPtrTable(address) = entry.value() {
+            let directory_ptr_table =
+                &*convert_physical_ptr(address.as_mut_ptr::<DirectoryPtrTable>());
+
+            for entry in directory_ptr_table.iter() {
+                if let DirectoryPtrValue::DirectoryTable(address) = entry.value() {
+                    free_directory_table(address);
+                }
+            }
+
+            free_frame(address);
+        }
+    }
+
+    free_frame(address);
+}
+
+unsafe fn free_directory_table(address: PhysicalAddress52) {
+    let directory_table = &*convert_physical_ptr(address.as_mut_ptr::<DirectoryTable>());
+
+    for entry in directory_table.iter() {
+        if let DirectoryValue::Table(address) = entry.value() {
+            let table = &*convert_physical_ptr(address.as_mut_ptr::<Table>());
+
+            for entry in table.iter() {
+                if let TableValue::Page4Kib(address) = entry.value() {
+                    free_frame(address);
+                }
+            }
+
+            free_frame(address);
+        }
+    }
+
+    free_frame(address);
+}
+
+unsafe fn free_frame(address: PhysicalAddress52) {
+    pmm::free_frame(Frame::from_address(u64::from(address) as usize));
+}
+
 #[derive(Debug)]
 struct State {
     kernel_table: RootTable,
+    kernel_table_address: PhysicalAddress52,
+    no_execute: bool,
     heap_start: Address64,
 }
 
@@ -268,12 +961,12 @@ unsafe impl Send for State {}
 struct IdentityMapperInterface;
 
 impl paging::MapperInterface for IdentityMapperInterface {
+    // Frames are not cleared by the PMM so tables are cleared here before any entry is read.
+
     unsafe fn alloc_table(&mut self) -> PhysicalAddress52 {
-        pmm::allocate_frame()
-            .segment()
-            .start()
-            .try_into()
-            .expect("Failed to allocate page table.")
+        let address = allocate_table_frame();
+        ptr::write_bytes(address.as_mut_ptr::<u8>(), 0, TABLE_SIZE);
+        address
     }
 
     unsafe fn dealloc_table(&mut self, address: PhysicalAddress52) {
@@ -282,16 +975,28 @@ impl paging::MapperInterface for IdentityMapperInterface {
     }
 }
 
+const TABLE_SIZE: usize = 4096;
+
+unsafe fn allocate_table_frame() -> PhysicalAddress52 {
+    pmm::allocate_frame()
+        .expect("Out of physical memory for page tables.")
+        .segment()
+        .start()
+        .try_into()
+        .expect("Failed to allocate page table.")
+}
+
 // This interface is for use after physical memory is mapped into kernel space. Its virtual pointer
-// conversion will offset the physical address by the kernel physical mapping start address. Other
-// methods will just use the identity mapper interface implementation.
+// conversion will offset the physical address by the kernel physical mapping start address. Tables
+// are allocated the same way as the identity mapper interface but cleared through that mapping.
 
 struct KernelSpaceMapperInterface;
 
 impl paging::MapperInterface for KernelSpaceMapperInterface {
     unsafe fn alloc_table(&mut self) -> PhysicalAddress52 {
-        let mut allocator = IdentityMapperInterface;
-        allocator.alloc_table()
+        let address = allocate_table_frame();
+        ptr::write_bytes(self.convert_to_virtual_ptr::<u8>(address), 0, TABLE_SIZE);
+        address
     }
 
     unsafe fn dealloc_table(&mut self, address: PhysicalAddress52) {
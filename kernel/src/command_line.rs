@@ -0,0 +1,305 @@
+//**************************************************************************************************
+// command_line.rs                                                                                 *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::log::{self, Level};
+use crate::spinlock::Spinlock;
+use core::fmt;
+use kernel_interface::init::{Args, BaudDivisor, CommandLine, DebugConfig, Port};
+
+// The kernel command line is a list of options separated by whitespace. Options are either flags
+// like "nokaslr" or "key=value" pairs. Later options override earlier ones.
+//
+// log=<level>              Default log level (error, warn, info, debug or trace).
+// log.<module>=<level>     Log level for a module and its children such as "log.kernel::tm=debug".
+// debug=<on|off>           Enables or disables output over the debug serial port.
+// serial=<port>            Debug serial port (com1 to com4 or an I/O port address like 0x3F8).
+// baud=<rate>              Baud rate used by both the debug and GDB serial ports.
+// gdb=<port|off>           Enables the GDB remote stub on a serial port.
+// timer=<id>               Prefers the timer whose ID starts with the text, ignoring case.
+// nokaslr                  The boot loader keeps the kernel at the address it was linked at.
+// mem=<size>               Ignores physical memory above the size. Accepts K, M and G suffixes.
+// init=<path>              Path of the first user task in the initial image.
+
+const BAUD_BASE: u32 = 115200;
+
+// The text is copied out of the boot loader's arguments once during init and never changed
+// afterwards so options can borrow from it for the lifetime of the kernel.
+
+static mut TEXT: CommandLine = CommandLine::new();
+
+static OPTIONS: Spinlock<Options> = Spinlock::new(Options::new());
+
+pub unsafe fn init(args: &Args) {
+    TEXT = args.command_line;
+
+    let text = text();
+    let mut options = Options::new();
+
+    for option in text.split_whitespace() {
+        if let Err(error) = options.apply(option) {
+            warn!(
+                "Ignoring kernel command line option \"{}\". {}",
+                option, error
+            );
+        }
+    }
+
+    if let Some(level) = options.log_level {
+        log::set_level(level);
+    }
+
+    *OPTIONS.lock() = options;
+
+    if !text.is_empty() {
+        info!("Kernel command line is \"{}\".", text);
+    }
+}
+
+pub fn text() -> &'static str {
+    unsafe { TEXT.as_str() }
+}
+
+pub fn options() -> Options {
+    *OPTIONS.lock()
+}
+
+// Applies the serial options on top of the configuration from the boot loader.
+
+pub fn debug_config(base: &DebugConfig) -> DebugConfig {
+    let options = options();
+    let mut config = *base;
+
+    if let Some(enabled) = options.debug {
+        config.enabled = enabled;
+    }
+
+    if let Some(port) = options.serial_port {
+        config.port_number = port;
+    }
+
+    if let Some(baud_divisor) = options.baud_divisor {
+        config.baud_divisor = baud_divisor;
+    }
+
+    match options.gdb_port {
+        Some(Some(port)) => {
+            config.gdb_enabled = true;
+            config.gdb_port_number = port;
+        }
+        Some(None) => config.gdb_enabled = false,
+        None => {}
+    }
+
+    config
+}
+
+// Options that were not given are None so the defaults of each subsystem apply.
+
+#[derive(Copy, Clone, Debug)]
+pub struct Options {
+    pub log_level: Option<Level>,
+    pub debug: Option<bool>,
+    pub serial_port: Option<Port>,
+    pub baud_divisor: Option<BaudDivisor>,
+    // Some(None) disables the GDB stub.
+    pub gdb_port: Option<Option<Port>>,
+    pub timer: Option<&'static str>,
+    pub memory_limit: Option<u64>,
+    pub init: Option<&'static str>,
+}
+
+impl Options {
+    pub const fn new() -> Self {
+        Self {
+            log_level: None,
+            debug: None,
+            serial_port: None,
+            baud_divisor: None,
+            gdb_port: None,
+            timer: None,
+            memory_limit: None,
+            init: None,
+        }
+    }
+
+    // Checks if a timer device ID was selected by the timer option.
+
+    pub fn is_timer_preferred(&self, id: &str) -> bool {
+        match self.timer {
+            Some(timer) => id
+                .get(..timer.len())
+                .map_or(false, |prefix| prefix.eq_ignore_ascii_case(timer)),
+            None => false,
+        }
+    }
+
+    fn apply(&mut self, option: &'static str) -> Result<(), OptionError> {
+        let (key, value) = match option.find('=') {
+            Some(index) => (&option[..index], Some(&option[index + 1..])),
+            None => (option, None),
+        };
+
+        if let Some(module) = key.strip_prefix("log.") {
+            let level = parse_level(value)?;
+
+            return if log::set_module_level(module, level) {
+                Ok(())
+            } else {
+                Err(OptionError::TooManyFilters)
+            };
+        }
+
+        match key {
+            "log" => self.log_level = Some(parse_level(value)?),
+            "debug" => self.debug = Some(parse_switch(value)?),
+            "serial" => self.serial_port = Some(parse_port(value)?),
+            "baud" => self.baud_divisor = Some(parse_baud(value)?),
+            "gdb" => {
+                let value = value.ok_or(OptionError::MissingValue)?;
+
+                self.gdb_port = Some(if value == "off" {
+                    None
+                } else {
+                    Some(parse_port(Some(value))?)
+                });
+            }
+            "timer" => match value {
+                Some(value) if !value.is_empty() => self.timer = Some(value),
+                _ => return Err(OptionError::MissingValue),
+            },
+            // Read by the boot loader. It is only checked here so it isn't reported as unknown.
+            "nokaslr" => {
+                parse_flag(value)?;
+            }
+            "mem" => self.memory_limit = Some(parse_size(value)?),
+            "init" => match value {
+                Some(value) if !value.is_empty() => self.init = Some(value),
+                _ => return Err(OptionError::MissingValue),
+            },
+            _ => return Err(OptionError::Unknown),
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum OptionError {
+    Unknown,
+    MissingValue,
+    UnexpectedValue,
+    InvalidValue,
+    TooManyFilters,
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionError::Unknown => write!(f, "The option is unknown."),
+            OptionError::MissingValue => write!(f, "The option requires a value."),
+            OptionError::UnexpectedValue => write!(f, "The option does not take a value."),
+            OptionError::InvalidValue => write!(f, "The value is invalid."),
+            OptionError::TooManyFilters => write!(f, "There are too many log filters."),
+        }
+    }
+}
+
+fn parse_level(value: Option<&str>) -> Result<Level, OptionError> {
+    let value = value.ok_or(OptionError::MissingValue)?;
+    Level::from_name(value).ok_or(OptionError::InvalidValue)
+}
+
+fn parse_flag(value: Option<&str>) -> Result<bool, OptionError> {
+    match value {
+        Some(_) => Err(OptionError::UnexpectedValue),
+        None => Ok(true),
+    }
+}
+
+fn parse_switch(value: Option<&str>) -> Result<bool, OptionError> {
+    match value.ok_or(OptionError::MissingValue)? {
+        "on" | "1" | "true" => Ok(true),
+        "off" | "0" | "false" => Ok(false),
+        _ => Err(OptionError::InvalidValue),
+    }
+}
+
+fn parse_port(value: Option<&str>) -> Result<Port, OptionError> {
+    let value = value.ok_or(OptionError::MissingValue)?;
+
+    // This runs before the heap is available so names are compared without allocating.
+
+    let named_ports = [
+        ("com1", Port::COM_1),
+        ("com2", Port::COM_2),
+        ("com3", Port::COM_3),
+        ("com4", Port::COM_4),
+    ];
+
+    if let Some(&(_, port)) = named_ports
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(value))
+    {
+        return Ok(port);
+    }
+
+    parse_integer(value)
+        .filter(|&address| address != 0 && address <= u16::MAX as u64)
+        .map(|address| Port::new(address as u16))
+        .ok_or(OptionError::InvalidValue)
+}
+
+// Rates that do not divide the UART clock evenly are rounded to the closest divisor.
+
+fn parse_baud(value: Option<&str>) -> Result<BaudDivisor, OptionError> {
+    let value = value.ok_or(OptionError::MissingValue)?;
+
+    let rate = parse_integer(value)
+        .filter(|&rate| rate != 0 && rate <= BAUD_BASE as u64)
+        .ok_or(OptionError::InvalidValue)? as u32;
+
+    let divisor = (BAUD_BASE + rate / 2) / rate;
+
+    if divisor > u16::MAX as u32 {
+        return Err(OptionError::InvalidValue);
+    }
+
+    Ok(BaudDivisor::new(divisor as u16))
+}
+
+fn parse_size(value: Option<&str>) -> Result<u64, OptionError> {
+    let value = value.ok_or(OptionError::MissingValue)?;
+
+    let (number, shift) = match value.as_bytes().last() {
+        Some(b'K') | Some(b'k') => (&value[..value.len() - 1], 10),
+        Some(b'M') | Some(b'm') => (&value[..value.len() - 1], 20),
+        Some(b'G') | Some(b'g') => (&value[..value.len() - 1], 30),
+        _ => (value, 0),
+    };
+
+    parse_integer(number)
+        .and_then(|number| number.checked_mul(1 << shift))
+        .filter(|&size| size != 0)
+        .ok_or(OptionError::InvalidValue)
+}
+
+// Accepts decimal or hexadecimal with a "0x" prefix.
+
+fn parse_integer(value: &str) -> Option<u64> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
@@ -0,0 +1,130 @@
+//**************************************************************************************************
+// framebuffer.rs                                                                                  *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use core::ptr;
+use kernel_interface::init::{FramebufferInfo, PixelFormat};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Color {
+    pub const fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+}
+
+pub struct Framebuffer {
+    buffer: *mut u32,
+    width: usize,
+    height: usize,
+    stride: usize,
+    pixel_format: PixelFormat,
+    red_mask: u32,
+    green_mask: u32,
+    blue_mask: u32,
+}
+
+impl Framebuffer {
+    // The buffer must be the virtual address of the framebuffer described by the info.
+
+    pub unsafe fn new(info: &FramebufferInfo, buffer: *mut u32) -> Self {
+        Self {
+            buffer,
+            width: info.width as usize,
+            height: info.height as usize,
+            stride: info.stride as usize,
+            pixel_format: info.pixel_format,
+            red_mask: info.red_mask,
+            green_mask: info.green_mask,
+            blue_mask: info.blue_mask,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn encode(&self, color: Color) -> u32 {
+        match self.pixel_format {
+            PixelFormat::BGR => u32::from_le_bytes([color.blue, color.green, color.red, 0]),
+            PixelFormat::BIT_MASK => {
+                encode_channel(color.red, self.red_mask)
+                    | encode_channel(color.green, self.green_mask)
+                    | encode_channel(color.blue, self.blue_mask)
+            }
+            _ => u32::from_le_bytes([color.red, color.green, color.blue, 0]),
+        }
+    }
+
+    pub fn write_pixel(&mut self, x: usize, y: usize, value: u32) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        unsafe {
+            ptr::write_volatile(self.buffer.add(y * self.stride + x), value);
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, value: u32) {
+        let end_x = x.saturating_add(width).min(self.width);
+        let end_y = y.saturating_add(height).min(self.height);
+
+        for row in y..end_y {
+            for column in x..end_x {
+                unsafe {
+                    ptr::write_volatile(self.buffer.add(row * self.stride + column), value);
+                }
+            }
+        }
+    }
+
+    // Moves the contents up by the number of pixel rows and fills the rows left at the bottom.
+
+    pub fn scroll_up(&mut self, rows: usize, value: u32) {
+        let rows = rows.min(self.height);
+        let moved_rows = self.height - rows;
+
+        unsafe {
+            ptr::copy(
+                self.buffer.add(rows * self.stride),
+                self.buffer,
+                moved_rows * self.stride,
+            );
+        }
+
+        self.fill_rect(0, moved_rows, self.width, rows, value);
+    }
+}
+
+unsafe impl Send for Framebuffer {}
+
+// Scales an 8 bit channel to the width of its mask.
+
+fn encode_channel(value: u8, mask: u32) -> u32 {
+    if mask == 0 {
+        return 0;
+    }
+
+    let shift = mask.trailing_zeros();
+    let bits = (mask >> shift).count_ones();
+
+    let scaled = if bits >= 8 {
+        (value as u32) << (bits - 8)
+    } else {
+        (value as u32) >> (8 - bits)
+    };
+
+    (scaled << shift) & mask
+}
@@ -0,0 +1,337 @@
+//**************************************************************************************************
+// mod.rs                                                                                          *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+mod framebuffer;
+
+pub use framebuffer::*;
+
+use crate::arch::vmm;
+use crate::spinlock::Spinlock;
+use core::fmt::{self, Write};
+use kernel_interface::init::Args;
+use psf::Font;
+
+// Text console drawn on the framebuffer set up by the boot loader. It understands a subset of
+// ANSI escape sequences for colours so output meant for serial terminals looks the same.
+
+static STATE: Spinlock<Option<Console>> = Spinlock::new(None);
+
+const TAB_WIDTH: usize = 8;
+
+const MAX_ESCAPE_PARAMETERS: usize = 4;
+
+// The standard VGA text mode palette in ANSI order. The last 8 are the bright variants.
+
+const PALETTE: [Color; 16] = [
+    Color::new(0, 0, 0),
+    Color::new(170, 0, 0),
+    Color::new(0, 170, 0),
+    Color::new(170, 85, 0),
+    Color::new(0, 0, 170),
+    Color::new(170, 0, 170),
+    Color::new(0, 170, 170),
+    Color::new(170, 170, 170),
+    Color::new(85, 85, 85),
+    Color::new(255, 85, 85),
+    Color::new(85, 255, 85),
+    Color::new(255, 255, 85),
+    Color::new(85, 85, 255),
+    Color::new(255, 85, 255),
+    Color::new(85, 255, 255),
+    Color::new(255, 255, 255),
+];
+
+const DEFAULT_FOREGROUND: usize = 7;
+
+const DEFAULT_BACKGROUND: usize = 0;
+
+pub unsafe fn init(args: &Args) {
+    let info = &args.framebuffer;
+
+    if !info.is_available() {
+        info!("No framebuffer was provided. Output is only available over serial.");
+        return;
+    }
+
+    let font = match Font::new(psf::BUILTIN_FONT) {
+        Ok(font) => font,
+        Err(error) => {
+            error!("Failed to load the console font. {}", error);
+            return;
+        }
+    };
+
+    let buffer = vmm::convert_physical_ptr_mut(info.address.as_mut_ptr::<u32>());
+    let framebuffer = Framebuffer::new(info, buffer);
+
+    let mut console = Console::new(framebuffer, font);
+    console.clear();
+
+    let (columns, rows) = (console.columns, console.rows);
+
+    *STATE.lock() = Some(console);
+
+    info!(
+        "Framebuffer console initialized with {}x{} characters at {}x{}.",
+        columns, rows, info.width, info.height
+    );
+}
+
+pub fn _print(args: fmt::Arguments) {
+    if let Some(console) = STATE.lock().as_mut() {
+        let _ = console.write_fmt(args);
+    }
+}
+
+// Used by the emergency output path. The lock is only broken while panicking since the CPU that
+// holds it will never continue drawing.
+
+pub fn _emergency_print(args: fmt::Arguments) {
+    let mut state = match STATE.try_lock() {
+        Some(state) => state,
+        None if crate::is_panicking() => unsafe {
+            STATE.force_unlock();
+
+            match STATE.try_lock() {
+                Some(state) => state,
+                None => return,
+            }
+        },
+        None => return,
+    };
+
+    if let Some(console) = state.as_mut() {
+        let _ = console.write_fmt(args);
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum EscapeState {
+    None,
+    Escape,
+    ControlSequence,
+}
+
+struct Console {
+    framebuffer: Framebuffer,
+    font: Font<'static>,
+    columns: usize,
+    rows: usize,
+    column: usize,
+    row: usize,
+    foreground: usize,
+    background: usize,
+    bold: bool,
+    escape_state: EscapeState,
+    escape_parameters: [u16; MAX_ESCAPE_PARAMETERS],
+    escape_parameter_count: usize,
+}
+
+impl Console {
+    fn new(framebuffer: Framebuffer, font: Font<'static>) -> Self {
+        Self {
+            columns: (framebuffer.width() / font.width()).max(1),
+            rows: (framebuffer.height() / font.height()).max(1),
+            framebuffer,
+            font,
+            column: 0,
+            row: 0,
+            foreground: DEFAULT_FOREGROUND,
+            background: DEFAULT_BACKGROUND,
+            bold: false,
+            escape_state: EscapeState::None,
+            escape_parameters: [0; MAX_ESCAPE_PARAMETERS],
+            escape_parameter_count: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        let background = self.background_value();
+        let (width, height) = (self.framebuffer.width(), self.framebuffer.height());
+
+        self.framebuffer.fill_rect(0, 0, width, height, background);
+        self.column = 0;
+        self.row = 0;
+    }
+
+    fn foreground_value(&self) -> u32 {
+        // Bold brightens the normal colours like most terminals do.
+
+        let index = if self.bold && self.foreground < 8 {
+            self.foreground + 8
+        } else {
+            self.foreground
+        };
+
+        self.framebuffer.encode(PALETTE[index])
+    }
+
+    fn background_value(&self) -> u32 {
+        self.framebuffer.encode(PALETTE[self.background])
+    }
+
+    fn write_char(&mut self, character: char) {
+        match self.escape_state {
+            EscapeState::None => {}
+            EscapeState::Escape => {
+                self.escape_state = if character == '[' {
+                    self.escape_parameters = [0; MAX_ESCAPE_PARAMETERS];
+                    self.escape_parameter_count = 0;
+                    EscapeState::ControlSequence
+                } else {
+                    EscapeState::None
+                };
+                return;
+            }
+            EscapeState::ControlSequence => {
+                self.write_control_sequence_char(character);
+                return;
+            }
+        }
+
+        match character {
+            '\x1B' => self.escape_state = EscapeState::Escape,
+            '\n' => self.new_line(),
+            '\r' => self.column = 0,
+            '\t' => {
+                let next = (self.column / TAB_WIDTH + 1) * TAB_WIDTH;
+
+                while self.column < next.min(self.columns) {
+                    self.write_glyph(' ');
+                }
+            }
+            '\x08' => self.column = self.column.saturating_sub(1),
+            _ => self.write_glyph(character),
+        }
+    }
+
+    fn write_control_sequence_char(&mut self, character: char) {
+        match character {
+            '0'..='9' => {
+                if self.escape_parameter_count == 0 {
+                    self.escape_parameter_count = 1;
+                }
+
+                if let Some(parameter) = self
+                    .escape_parameters
+                    .get_mut(self.escape_parameter_count - 1)
+                {
+                    let digit = character as u16 - '0' as u16;
+                    *parameter = parameter.saturating_mul(10).saturating_add(digit);
+                }
+            }
+            ';' => {
+                if self.escape_parameter_count == 0 {
+                    self.escape_parameter_count = 1;
+                }
+
+                self.escape_parameter_count += 1;
+            }
+            'm' => {
+                self.select_graphic_rendition();
+                self.escape_state = EscapeState::None;
+            }
+            // Other sequences are not supported and are dropped once they end.
+            '\x40'..='\x7E' => self.escape_state = EscapeState::None,
+            _ => {}
+        }
+    }
+
+    fn select_graphic_rendition(&mut self) {
+        let count = self
+            .escape_parameter_count
+            .max(1)
+            .min(MAX_ESCAPE_PARAMETERS);
+
+        for index in 0..count {
+            match self.escape_parameters[index] {
+                0 => {
+                    self.foreground = DEFAULT_FOREGROUND;
+                    self.background = DEFAULT_BACKGROUND;
+                    self.bold = false;
+                }
+                1 => self.bold = true,
+                22 => self.bold = false,
+                value @ 30..=37 => self.foreground = (value - 30) as usize,
+                39 => self.foreground = DEFAULT_FOREGROUND,
+                value @ 40..=47 => self.background = (value - 40) as usize,
+                49 => self.background = DEFAULT_BACKGROUND,
+                value @ 90..=97 => self.foreground = (value - 90) as usize + 8,
+                value @ 100..=107 => self.background = (value - 100) as usize + 8,
+                _ => {}
+            }
+        }
+    }
+
+    fn write_glyph(&mut self, character: char) {
+        if self.column >= self.columns {
+            self.new_line();
+        }
+
+        let glyph = match self.font.glyph(character).or_else(|| self.font.glyph('?')) {
+            Some(glyph) => glyph,
+            None => return,
+        };
+
+        let foreground = self.foreground_value();
+        let background = self.background_value();
+
+        let width = self.font.width();
+        let height = self.font.height();
+        let bytes_per_row = self.font.bytes_per_row();
+
+        let origin_x = self.column * width;
+        let origin_y = self.row * height;
+
+        for y in 0..height {
+            let row = &glyph[y * bytes_per_row..(y + 1) * bytes_per_row];
+
+            for x in 0..width {
+                let set = row[x / 8] & (0x80 >> (x % 8)) != 0;
+                let value = if set { foreground } else { background };
+
+                self.framebuffer
+                    .write_pixel(origin_x + x, origin_y + y, value);
+            }
+        }
+
+        self.column += 1;
+    }
+
+    fn new_line(&mut self) {
+        self.column = 0;
+
+        if self.row + 1 < self.rows {
+            self.row += 1;
+            return;
+        }
+
+        let background = self.background_value();
+        let height = self.font.height();
+
+        // The last text row may not end at the bottom of the framebuffer so it is cleared on its
+        // own.
+
+        self.framebuffer.scroll_up(height, background);
+        self.framebuffer.fill_rect(
+            0,
+            self.row * height,
+            self.framebuffer.width(),
+            height,
+            background,
+        );
+    }
+}
+
+impl Write for Console {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for character in s.chars() {
+            self.write_char(character);
+        }
+
+        Ok(())
+    }
+}
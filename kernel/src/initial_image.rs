@@ -0,0 +1,111 @@
+//**************************************************************************************************
+// initial_image.rs                                                                                *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::arch::vmm;
+use crate::spinlock::Spinlock;
+use core::slice;
+use cpio::Archive;
+use kernel_interface::init::{Args, MemorySection, MemoryType};
+use memory::Segment;
+
+// The initial image holds the services and drivers needed before any storage driver is running.
+// The kernel only keeps it in memory and hands it out read only. The kernel finds the first user
+// task in it and maps the whole image into that task's address space.
+
+static STATE: Spinlock<Option<Image>> = Spinlock::new(None);
+
+#[derive(Copy, Clone)]
+struct Image {
+    data: &'static [u8],
+    physical_address: u64,
+}
+
+pub unsafe fn init(args: &Args) {
+    let info = &args.initial_image;
+
+    if !info.is_available() {
+        info!("No initial image was provided.");
+        return;
+    }
+
+    // The image must lie in memory the boot loader reserved for it or the PMM could hand the same
+    // frames out.
+
+    let segment = Segment::with_len(u64::from(info.address) as usize, info.len);
+
+    let memory_map = slice::from_raw_parts(
+        vmm::convert_physical_ptr(args.memory_map.ptr as *const MemorySection),
+        args.memory_map.len,
+    );
+
+    let is_reserved = memory_map
+        .iter()
+        .filter(|section| section.memory_type == MemoryType::INITIAL_IMAGE)
+        .any(|section| {
+            let section = section.as_segment();
+            section.start() <= segment.start() && section.end() >= segment.end()
+        });
+
+    if !is_reserved {
+        error!(
+            "The initial image at {:#X} is not in initial image memory. It will be ignored.",
+            segment.start()
+        );
+        return;
+    }
+
+    let data = slice::from_raw_parts(
+        vmm::convert_physical_ptr(info.address.as_ptr::<u8>()),
+        info.len,
+    );
+
+    // The image is a newc cpio archive. It is checked once here so lookups later can only fail
+    // if the file is missing.
+
+    let entry_count = match Archive::new(data).validate() {
+        Ok(entry_count) => entry_count,
+        Err(error) => {
+            error!("The initial image is not a valid archive. {}", error);
+            return;
+        }
+    };
+
+    *STATE.lock() = Some(Image {
+        data,
+        physical_address: segment.start() as u64,
+    });
+
+    info!(
+        "Initial image of {} byte(s) with {} entries found at {:#X}.",
+        info.len,
+        entry_count,
+        segment.start()
+    );
+}
+
+pub fn data() -> Option<&'static [u8]> {
+    STATE.lock().map(|image| image.data)
+}
+
+pub fn physical_address() -> Option<u64> {
+    STATE.lock().map(|image| image.physical_address)
+}
+
+pub fn archive() -> Option<Archive<'static>> {
+    data().map(Archive::new)
+}
+
+// Returns the contents of a regular file in the image.
+
+pub fn find_file(path: &str) -> Option<&'static [u8]> {
+    let entry = archive()?.find(path).ok()??;
+
+    if entry.is_file() {
+        Some(entry.data())
+    } else {
+        None
+    }
+}
@@ -0,0 +1,623 @@
+//**************************************************************************************************
+// log.rs                                                                                          *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::arch;
+use crate::console;
+use crate::spinlock::Spinlock;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::ptr;
+use core::str;
+use core::sync::atomic::{fence, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use units::{Nanoseconds, Time};
+
+// Records are kept in a fixed size ring buffer so they are retained even when no console is
+// attached. Writers never wait on each other or on readers. Once the buffer is full the oldest
+// records are overwritten and readers that fall behind skip them.
+//
+// Records are written to the serial port and console by whichever CPU gets the output lock. A
+// CPU that finds the lock taken leaves its record to the holder instead of waiting.
+
+const CAPACITY: usize = 512;
+
+const MODULE_LEN: usize = 40;
+
+const MESSAGE_LEN: usize = 192;
+
+const MAX_FILTERS: usize = 16;
+
+static BUFFER: RingBuffer = RingBuffer::new();
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+static FILTERS: Filters = Filters::new();
+
+static OUTPUT: Spinlock<Reader> = Spinlock::new(Reader::from_start());
+
+macro_rules! log {
+    ($level:expr, $($arg:tt)*) => {{
+        let module = module_path!();
+        if $crate::log::is_enabled($level, module) {
+            $crate::log::_log($level, module, format_args!($($arg)*));
+        }
+    }};
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => (log!($crate::log::Level::Error, $($arg)*));
+}
+
+macro_rules! warn {
+    ($($arg:tt)*) => (log!($crate::log::Level::Warning, $($arg)*));
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => (log!($crate::log::Level::Info, $($arg)*));
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => (log!($crate::log::Level::Debug, $($arg)*));
+}
+
+#[allow(unused_macros)]
+macro_rules! trace {
+    ($($arg:tt)*) => (log!($crate::log::Level::Trace, $($arg)*));
+}
+
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Level {
+    Error = 0,
+    Warning = 1,
+    Info = 2,
+    Debug = 3,
+    Trace = 4,
+}
+
+impl Level {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Level::Error),
+            1 => Some(Level::Warning),
+            2 => Some(Level::Info),
+            3 => Some(Level::Debug),
+            4 => Some(Level::Trace),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warning => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+
+    // Parses a level name ignoring case. "warning" is accepted as well as the short name.
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Level::Error,
+            Level::Warning,
+            Level::Info,
+            Level::Debug,
+            Level::Trace,
+        ]
+        .iter()
+        .copied()
+        .find(|level| level.name().eq_ignore_ascii_case(name))
+        .or_else(|| {
+            if name.eq_ignore_ascii_case("warning") {
+                Some(Level::Warning)
+            } else {
+                None
+            }
+        })
+    }
+
+    // ANSI foreground colour used for the level name on the console.
+
+    fn color_code(self) -> u8 {
+        match self {
+            Level::Error => 91,
+            Level::Warning => 93,
+            Level::Info => 92,
+            Level::Debug => 96,
+            Level::Trace => 90,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+pub fn level() -> Level {
+    Level::from_u8(LEVEL.load(Ordering::Relaxed)).unwrap_or(Level::Info)
+}
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+// Overrides the level for a module and its children. The most specific filter applies. Returns
+// false if there is no room for another filter.
+
+pub fn set_module_level(module: &'static str, level: Level) -> bool {
+    let _guard = FILTERS.lock.lock();
+
+    let count = FILTERS.count.load(Ordering::Relaxed);
+
+    if let Some(filter) = FILTERS.slots[..count]
+        .iter()
+        .find(|filter| filter.module() == module)
+    {
+        filter.level.store(level as u8, Ordering::Relaxed);
+        return true;
+    }
+
+    let filter = match FILTERS.slots.get(count) {
+        Some(filter) => filter,
+        None => return false,
+    };
+
+    unsafe {
+        *filter.module.get() = module;
+    }
+
+    filter.level.store(level as u8, Ordering::Relaxed);
+    FILTERS.count.store(count + 1, Ordering::Release);
+
+    true
+}
+
+pub fn is_enabled(level: Level, module: &str) -> bool {
+    let count = FILTERS.count.load(Ordering::Acquire);
+
+    let threshold = FILTERS.slots[..count]
+        .iter()
+        .filter(|filter| filter.matches(module))
+        .max_by_key(|filter| filter.module().len())
+        .map_or_else(self::level, |filter| filter.level());
+
+    level <= threshold
+}
+
+pub fn _log(level: Level, module: &'static str, args: fmt::Arguments) {
+    let cpu = arch::cpu_id();
+    let timestamp = arch::timestamp();
+
+    let mut record = Record {
+        level,
+        cpu,
+        timestamp,
+        module: [0; MODULE_LEN],
+        module_len: 0,
+        message: [0; MESSAGE_LEN],
+        message_len: 0,
+    };
+
+    record.module_len = copy_truncated(&mut record.module, module) as u8;
+
+    let mut writer = TruncatingWriter {
+        buffer: &mut record.message,
+        len: 0,
+    };
+
+    let _ = fmt::write(&mut writer, args);
+    record.message_len = writer.len as u8;
+
+    let sequence = BUFFER.push(&record);
+
+    flush(sequence);
+}
+
+// Writes every committed record to the outputs. A record that is still being written is left to
+// the CPU writing it, which flushes again once it is committed.
+
+fn flush(mut sequence: u64) {
+    loop {
+        let mut reader = match OUTPUT.try_lock() {
+            Some(reader) => reader,
+            None => return,
+        };
+
+        let mut dropped = reader.dropped();
+
+        while let Some(record) = reader.read() {
+            if reader.dropped() != dropped {
+                arch::debug::_print(format_args!(
+                    "{} log records were overwritten before they were written out.\n",
+                    reader.dropped() - dropped
+                ));
+
+                dropped = reader.dropped();
+            }
+
+            // Serial output stays plain text since it is often captured to a file.
+
+            arch::debug::_print_serial(format_args!("{}\n", record));
+
+            console::_print(format_args!(
+                "{} CPU {} \x1B[{}m{:<5}\x1B[0m {}: {}\n",
+                Timestamp(record.timestamp),
+                record.cpu,
+                record.level.color_code(),
+                record.level,
+                record.module(),
+                record.message()
+            ));
+        }
+
+        // A record committed after the reader stopped but before the lock was released would
+        // otherwise stay in the buffer until the next record is logged.
+
+        sequence = sequence.max(reader.sequence);
+        drop(reader);
+
+        if !BUFFER.is_committed(sequence) {
+            return;
+        }
+    }
+}
+
+// Filters are only ever added. The module of a slot is written before the count is raised past
+// it and never changes afterwards so it can be read without the lock. The lock only keeps writers
+// from taking the same slot.
+
+struct Filters {
+    lock: Spinlock<()>,
+    count: AtomicUsize,
+    slots: [Filter; MAX_FILTERS],
+}
+
+impl Filters {
+    const fn new() -> Self {
+        Self {
+            lock: Spinlock::new(()),
+            count: AtomicUsize::new(0),
+            slots: [EMPTY_FILTER; MAX_FILTERS],
+        }
+    }
+}
+
+unsafe impl Sync for Filters {}
+
+struct Filter {
+    module: UnsafeCell<&'static str>,
+    level: AtomicU8,
+}
+
+// Only used to initialize the filter array.
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_FILTER: Filter = Filter {
+    module: UnsafeCell::new(""),
+    level: AtomicU8::new(Level::Info as u8),
+};
+
+impl Filter {
+    fn module(&self) -> &'static str {
+        unsafe { *self.module.get() }
+    }
+
+    fn level(&self) -> Level {
+        Level::from_u8(self.level.load(Ordering::Relaxed)).unwrap_or(Level::Info)
+    }
+
+    fn matches(&self, module: &str) -> bool {
+        match module.strip_prefix(self.module()) {
+            Some(rest) => rest.is_empty() || rest.starts_with("::"),
+            None => false,
+        }
+    }
+}
+
+// Shown as seconds since boot once the TSC is calibrated. Earlier records show the raw count since
+// boot instead.
+
+#[derive(Copy, Clone, Debug)]
+struct Timestamp(u64);
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match arch::timestamp_to_time(self.0) {
+            Some(time) => {
+                let microseconds = time.into_inner() / 1000;
+
+                write!(
+                    f,
+                    "[{:>9}.{:06}]",
+                    microseconds / 1_000_000,
+                    microseconds % 1_000_000
+                )
+            }
+            None => write!(f, "[{:>16}]", self.0),
+        }
+    }
+}
+
+// The timestamp counts from when the kernel was entered. See Timestamp for how it is shown.
+
+#[derive(Copy, Clone)]
+pub struct Record {
+    level: Level,
+    cpu: u32,
+    timestamp: u64,
+    module: [u8; MODULE_LEN],
+    module_len: u8,
+    message: [u8; MESSAGE_LEN],
+    message_len: u8,
+}
+
+impl Record {
+    const fn empty() -> Self {
+        Self {
+            level: Level::Info,
+            cpu: 0,
+            timestamp: 0,
+            module: [0; MODULE_LEN],
+            module_len: 0,
+            message: [0; MESSAGE_LEN],
+            message_len: 0,
+        }
+    }
+
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    pub fn cpu(&self) -> u32 {
+        self.cpu
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    // Time since boot or None if the timestamp can't be converted yet.
+
+    pub fn time(&self) -> Option<Nanoseconds<u64>> {
+        arch::timestamp_to_time(self.timestamp)
+    }
+
+    pub fn module(&self) -> &str {
+        str::from_utf8(&self.module[..self.module_len as usize]).unwrap_or("")
+    }
+
+    pub fn message(&self) -> &str {
+        str::from_utf8(&self.message[..self.message_len as usize]).unwrap_or("")
+    }
+}
+
+impl fmt::Debug for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Record")
+            .field("level", &self.level)
+            .field("cpu", &self.cpu)
+            .field("timestamp", &self.timestamp)
+            .field("module", &self.module())
+            .field("message", &self.message())
+            .finish()
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} CPU {} {:<5} {}: {}",
+            Timestamp(self.timestamp),
+            self.cpu,
+            self.level,
+            self.module(),
+            self.message()
+        )
+    }
+}
+
+// Reads records from the ring buffer in the order they were logged. Each reader keeps its own
+// position so the buffer can be drained without affecting other readers.
+
+#[derive(Clone, Debug)]
+pub struct Reader {
+    sequence: u64,
+    dropped: u64,
+}
+
+impl Reader {
+    const fn from_start() -> Self {
+        Self {
+            sequence: 0,
+            dropped: 0,
+        }
+    }
+
+    // Starts at the oldest record still in the buffer.
+
+    pub fn new() -> Self {
+        let next = BUFFER.next.load(Ordering::Acquire);
+
+        Self {
+            sequence: next.saturating_sub(CAPACITY as u64),
+            dropped: 0,
+        }
+    }
+
+    // Number of records that were overwritten before they could be read.
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn read(&mut self) -> Option<Record> {
+        loop {
+            let next = BUFFER.next.load(Ordering::Acquire);
+
+            if self.sequence >= next {
+                return None;
+            }
+
+            let oldest = next.saturating_sub(CAPACITY as u64);
+
+            if self.sequence < oldest {
+                self.dropped += oldest - self.sequence;
+                self.sequence = oldest;
+            }
+
+            match BUFFER.read(self.sequence) {
+                SlotRead::Record(record) => {
+                    self.sequence += 1;
+                    return Some(record);
+                }
+                SlotRead::Pending => return None,
+                SlotRead::Overwritten => {
+                    self.dropped += 1;
+                    self.sequence += 1;
+                }
+            }
+        }
+    }
+}
+
+impl Default for Reader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+enum SlotRead {
+    Record(Record),
+    Pending,
+    Overwritten,
+}
+
+// A slot's sequence is 0 while empty, odd while a record is being written and even once it is
+// committed. Readers check that the sequence did not change while copying the record.
+
+struct Slot {
+    sequence: AtomicU64,
+    record: UnsafeCell<Record>,
+}
+
+impl Slot {
+    const fn new() -> Self {
+        Self {
+            sequence: AtomicU64::new(0),
+            record: UnsafeCell::new(Record::empty()),
+        }
+    }
+}
+
+// Only used to initialize the slot array.
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_SLOT: Slot = Slot::new();
+
+struct RingBuffer {
+    next: AtomicU64,
+    slots: [Slot; CAPACITY],
+}
+
+impl RingBuffer {
+    const fn new() -> Self {
+        Self {
+            next: AtomicU64::new(0),
+            slots: [EMPTY_SLOT; CAPACITY],
+        }
+    }
+
+    // Returns the sequence the record was stored at.
+
+    fn push(&self, record: &Record) -> u64 {
+        let sequence = self.next.fetch_add(1, Ordering::AcqRel);
+        let slot = &self.slots[sequence as usize % CAPACITY];
+
+        slot.sequence
+            .store(writing_sequence(sequence), Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        unsafe {
+            ptr::write_volatile(slot.record.get(), *record);
+        }
+
+        slot.sequence
+            .store(committed_sequence(sequence), Ordering::Release);
+
+        sequence
+    }
+
+    fn is_committed(&self, sequence: u64) -> bool {
+        let slot = &self.slots[sequence as usize % CAPACITY];
+
+        slot.sequence.load(Ordering::Acquire) >= committed_sequence(sequence)
+    }
+
+    fn read(&self, sequence: u64) -> SlotRead {
+        let slot = &self.slots[sequence as usize % CAPACITY];
+
+        let before = slot.sequence.load(Ordering::Acquire);
+
+        if before < committed_sequence(sequence) {
+            return SlotRead::Pending;
+        }
+
+        if before > committed_sequence(sequence) {
+            return SlotRead::Overwritten;
+        }
+
+        let record = unsafe { ptr::read_volatile(slot.record.get()) };
+
+        fence(Ordering::Acquire);
+
+        if slot.sequence.load(Ordering::Relaxed) != before {
+            return SlotRead::Overwritten;
+        }
+
+        SlotRead::Record(record)
+    }
+}
+
+unsafe impl Sync for RingBuffer {}
+
+fn writing_sequence(sequence: u64) -> u64 {
+    sequence * 2 + 1
+}
+
+fn committed_sequence(sequence: u64) -> u64 {
+    sequence * 2 + 2
+}
+
+struct TruncatingWriter<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl fmt::Write for TruncatingWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.len += copy_truncated(&mut self.buffer[self.len..], s);
+        Ok(())
+    }
+}
+
+// Copies as much of the string as fits without splitting a character and returns the length.
+
+fn copy_truncated(buffer: &mut [u8], source: &str) -> usize {
+    let mut len = source.len().min(buffer.len());
+
+    while !source.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    buffer[..len].copy_from_slice(&source.as_bytes()[..len]);
+
+    len
+}
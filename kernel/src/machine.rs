@@ -0,0 +1,105 @@
+//**************************************************************************************************
+// machine.rs                                                                                      *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::arch::vmm;
+use core::slice;
+use kernel_interface::init::Args;
+use smbios::{EntryPoint, Table};
+
+// Identifies the machine from the SMBIOS tables passed by the boot loader. Nothing depends on it
+// yet so it is only logged to make bug reports more useful.
+
+pub unsafe fn init(args: &Args) {
+    let address = args.system_info.smbios;
+
+    if address.is_null() {
+        info!("No SMBIOS entry point was provided.");
+        return;
+    }
+
+    let entry_point_source = slice::from_raw_parts(
+        vmm::convert_physical_ptr(address.as_ptr::<u8>()),
+        EntryPoint::MAX_LEN,
+    );
+
+    let entry_point = match EntryPoint::read(entry_point_source) {
+        Ok(entry_point) => entry_point,
+        Err(error) => {
+            error!("The SMBIOS entry point is invalid. {}", error);
+            return;
+        }
+    };
+
+    let version = entry_point.version();
+
+    info!(
+        "SMBIOS {}.{} table found at {:#X}.",
+        version.major,
+        version.minor,
+        entry_point.table_address()
+    );
+
+    let table = Table::new(slice::from_raw_parts(
+        vmm::convert_physical_ptr(entry_point.table_address() as *const u8),
+        entry_point.table_len(),
+    ));
+
+    log_machine(&table);
+}
+
+fn log_machine(table: &Table) {
+    match table.system_information() {
+        Ok(Some(system)) => info!(
+            "Machine is {} {} (version {}).",
+            system.manufacturer.unwrap_or("Unknown"),
+            system.product_name.unwrap_or("Unknown"),
+            system.version.unwrap_or("unknown")
+        ),
+        Ok(None) => info!("SMBIOS does not describe the machine."),
+        Err(error) => warn!("Failed to read SMBIOS system information. {}", error),
+    }
+
+    match table.bios_information() {
+        Ok(Some(bios)) => info!(
+            "Firmware is {} {} released {}.",
+            bios.vendor.unwrap_or("Unknown"),
+            bios.version.unwrap_or("unknown"),
+            bios.release_date.unwrap_or("on an unknown date")
+        ),
+        Ok(None) => info!("SMBIOS does not describe the firmware."),
+        Err(error) => warn!("Failed to read SMBIOS BIOS information. {}", error),
+    }
+
+    for processor in table.processors() {
+        match processor {
+            Ok(processor) if processor.is_populated => info!(
+                "Processor in {} is {} with {} core(s) at up to {} MHz.",
+                processor.socket_designation.unwrap_or("unknown socket"),
+                processor.version.unwrap_or("unknown"),
+                processor.core_count.unwrap_or(0),
+                processor.max_speed_mhz.unwrap_or(0)
+            ),
+            Ok(_) => {}
+            Err(error) => warn!("Failed to read SMBIOS processor information. {}", error),
+        }
+    }
+
+    for device in table.memory_devices() {
+        match device {
+            Ok(device) if device.is_installed() => info!(
+                "Memory device in {} is {:?} with {} MiB at {} MT/s from {} ({}).",
+                device.device_locator.unwrap_or("unknown slot"),
+                device.memory_type,
+                device.size.map_or(0, |size| size / (1024 * 1024)),
+                device.speed_mts.unwrap_or(0),
+                device.manufacturer.unwrap_or("unknown"),
+                device.part_number.unwrap_or("unknown part")
+            ),
+            Ok(_) => {}
+            Err(error) => warn!("Failed to read SMBIOS memory device. {}", error),
+        }
+    }
+}
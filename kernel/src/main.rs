@@ -6,7 +6,7 @@
 
 #![no_std]
 #![no_main]
-#![feature(abi_x86_interrupt)]
+#![feature(global_asm)]
 #![feature(asm)]
 #![feature(alloc_layout_extra)]
 #![feature(alloc_error_handler)]
@@ -19,25 +19,37 @@ extern crate alloc;
 
 use crate::spinlock::Spinlock;
 use core::alloc::Layout;
+use core::fmt;
 use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, Ordering};
 use kernel_interface::init::Args;
 
+#[macro_use]
+pub mod log;
 #[macro_use]
 mod arch;
 mod acpi_interface;
+mod command_line;
+mod console;
 pub mod drivers;
 mod frame;
 mod heap;
+pub mod initial_image;
 pub mod icm;
+mod machine;
 mod pmm;
 mod spinlock;
+mod stacks;
+mod symbols;
 mod tasks;
 pub mod tm;
+pub mod uefi_runtime;
 
 pub use acpi_interface::*;
 
 pub unsafe fn main(args: &'static Args) -> ! {
-    loop {}
+    tasks::start_init();
+    tasks::idle()
 }
 
 pub unsafe fn main_ap() -> ! {
@@ -46,14 +58,51 @@ pub unsafe fn main_ap() -> ! {
 
 #[alloc_error_handler]
 fn on_oom(layout: Layout) -> ! {
-    println!("Kernel heap has run out of memory.");
-    println!("{:?}", layout);
+    error!("Kernel heap has run out of memory. {:?}", layout);
     unsafe { arch::stall() }
 }
 
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+pub fn is_panicking() -> bool {
+    PANICKING.load(Ordering::SeqCst)
+}
+
+// Set when the panic message already contains a backtrace, such as the one fault handlers take
+// from the faulting instruction. The panic handler would only repeat it with its own frames on
+// top.
+
+static BACKTRACE_IN_MESSAGE: AtomicBool = AtomicBool::new(false);
+
+pub fn panic_with_backtrace(message: fmt::Arguments) -> ! {
+    BACKTRACE_IN_MESSAGE.store(true, Ordering::SeqCst);
+    panic!("{}", message)
+}
+
+// Panics use the emergency output path since the panic may have been raised while the console
+// was locked.
+
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
-    println!("Kernel panic.");
-    println!("{}", info);
+    // Printing a backtrace reads memory through frame pointers that may be corrupted so a second
+    // panic stops here instead of recursing. This also catches a panic on another CPU that was
+    // raised before it received the NMI.
+
+    if PANICKING.swap(true, Ordering::SeqCst) {
+        emergency_println!("Kernel panicked while panicking.");
+        emergency_println!("{}", info);
+        unsafe { arch::stall() }
+    }
+
+    arch::halt_other_cpus();
+
+    emergency_println!("Kernel panic.");
+    emergency_println!("{}", info);
+
+    if !BACKTRACE_IN_MESSAGE.load(Ordering::SeqCst) {
+        emergency_println!("Backtrace:");
+        emergency_println!("{}", arch::backtrace::Backtrace::current());
+    }
+
     unsafe { arch::stall() }
 }
@@ -5,6 +5,7 @@
 //**************************************************************************************************
 
 use super::arch::vmm;
+use crate::command_line;
 use crate::frame::Frame;
 use crate::spinlock::Spinlock;
 use alloc::vec::Vec;
@@ -14,6 +15,10 @@ use kernel_interface::init::{Args, MemoryMap, MemorySection};
 
 static STATE: Spinlock<Option<State>> = Spinlock::new(None);
 
+// Smaller memory limits are raised to this so the kernel keeps enough memory to boot.
+
+const MIN_MEMORY_LIMIT: u64 = 64 * 1024 * 1024;
+
 pub unsafe fn init_stage_one(args: &Args) {
     assert!(!args.memory_map.ptr.is_null(), "Memory map is null.");
     assert_ne!(args.memory_map.len, 0, "Memory map count is 0.");
@@ -24,7 +29,7 @@ pub unsafe fn init_stage_one(args: &Args) {
 
     for memory_section in args.memory_map.as_slice() {
         let segment = memory_section.as_segment();
-        println!(
+        debug!(
             "Memory from {:#X} to {:#X} is {:?}.",
             segment.start(),
             segment.end(),
@@ -32,13 +37,42 @@ pub unsafe fn init_stage_one(args: &Args) {
         );
     }
 
+    // Frames are handed out in order up to the end of the memory map. The memory limit moves the
+    // end down instead of being checked on every allocation.
+
+    let map_end = args
+        .memory_map
+        .as_slice()
+        .iter()
+        .map(|section| section.as_segment().end())
+        .max()
+        .unwrap_or(0) as u64;
+
+    let mut end = map_end;
+
+    if let Some(mut memory_limit) = command_line::options().memory_limit {
+        if memory_limit < MIN_MEMORY_LIMIT {
+            warn!(
+                "The memory limit {:#X} is too low. Using {:#X} instead.",
+                memory_limit, MIN_MEMORY_LIMIT
+            );
+            memory_limit = MIN_MEMORY_LIMIT;
+        }
+
+        if memory_limit < map_end {
+            info!("Ignoring physical memory above {:#X}.", memory_limit);
+            end = memory_limit;
+        }
+    }
+
     *state_lock = Some(State {
         memory_map: args.memory_map,
+        end: Frame::from_address(end as usize).index(),
         next: 1,
         free: Vec::new(),
     });
 
-    println!("PMM stage one initialized.");
+    info!("PMM stage one initialized.");
 }
 
 pub unsafe fn init_stage_two() {
@@ -50,7 +84,7 @@ pub unsafe fn init_stage_two() {
 
     state.memory_map.ptr = vmm::convert_physical_ptr_mut(state.memory_map.ptr);
 
-    println!("PMM stage two initialized.");
+    info!("PMM stage two initialized.");
 }
 
 pub unsafe fn init_stage_three() {
@@ -71,10 +105,12 @@ pub unsafe fn init_stage_three() {
     // Now reclaim boot memory, ACPI, and other temporary startup memory. This consumes the old
     // memory map area.
 
-    println!("PMM stage three initialized.");
+    info!("PMM stage three initialized.");
 }
 
-pub unsafe fn allocate_frame() -> Frame {
+// Returns None once all physical memory is in use.
+
+pub unsafe fn allocate_frame() -> Option<Frame> {
     STATE
         .lock()
         .as_mut()
@@ -93,25 +129,31 @@ pub unsafe fn free_frame(frame: Frame) {
 #[derive(Debug)]
 struct State {
     memory_map: MemoryMap,
+    // Index of the first frame that is never handed out.
+    end: usize,
     next: usize,
     free: Vec<Frame>,
 }
 
 impl State {
-    pub unsafe fn allocate(&mut self) -> Frame {
+    pub unsafe fn allocate(&mut self) -> Option<Frame> {
         if let Some(freed_frame) = self.free.pop() {
-            freed_frame
-        } else {
-            loop {
-                let frame = Frame::new(self.next);
-                self.next += 1;
-                if self.is_free(frame) {
-                    return frame;
-                }
+            return Some(freed_frame);
+        }
+
+        while self.next < self.end {
+            let frame = Frame::new(self.next);
+            self.next += 1;
+            if self.is_free(frame) {
+                return Some(frame);
             }
         }
+
+        None
     }
 
+    // Frames in holes of the memory map are not free.
+
     unsafe fn is_free(&self, frame: Frame) -> bool {
         for memory_section in self.memory_map.as_slice() {
             if memory_section.as_segment().intersects(frame.segment()) {
@@ -119,7 +161,7 @@ impl State {
             }
         }
 
-        panic!("Out of physical memory.");
+        false
     }
 
     pub unsafe fn free(&mut self, frame: Frame) {
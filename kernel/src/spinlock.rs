@@ -29,18 +29,40 @@ impl<T> Spinlock<T> {
 
 impl<T: ?Sized> Spinlock<T> {
     pub fn try_lock(&self) -> Option<SpinlockGuard<T>> {
-        if self.lock.compare_and_swap(false, true, Ordering::Acquire) {
-            Some(SpinlockGuard::new(&self))
+        // Interrupts are disabled before taking the lock so an interrupt handler on this CPU
+        // can't spin on a lock the interrupted code holds.
+
+        let arch_state = arch::sync::start_lock();
+
+        if self
+            .lock
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            Some(SpinlockGuard::new(&self, arch_state))
         } else {
+            arch::sync::end_lock(arch_state);
             None
         }
     }
 
     pub fn lock(&self) -> SpinlockGuard<T> {
-        while !self.lock.compare_and_swap(false, true, Ordering::Acquire) {
-            arch::sync::spin_loop_hint();
+        loop {
+            if let Some(guard) = self.try_lock() {
+                return guard;
+            }
+
+            while self.lock.load(Ordering::Relaxed) {
+                arch::sync::spin_loop_hint();
+            }
         }
-        SpinlockGuard::new(&self)
+    }
+
+    // Releases the lock without a guard. Only used on paths that can never return to the holder
+    // such as a panic.
+
+    pub unsafe fn force_unlock(&self) {
+        self.lock.store(false, Ordering::Release);
     }
 }
 
@@ -54,10 +76,10 @@ pub struct SpinlockGuard<'a, T: ?Sized + 'a> {
 }
 
 impl<'a, T: ?Sized> SpinlockGuard<'a, T> {
-    fn new(spinlock: &'a Spinlock<T>) -> Self {
+    fn new(spinlock: &'a Spinlock<T>, arch_state: arch::sync::LockState) -> Self {
         SpinlockGuard {
             spinlock,
-            arch_state: arch::sync::start_lock(),
+            arch_state,
         }
     }
 }
@@ -78,8 +100,8 @@ impl<'a, T: ?Sized> DerefMut for SpinlockGuard<'a, T> {
 
 impl<'a, T: ?Sized> Drop for SpinlockGuard<'a, T> {
     fn drop(&mut self) {
-        arch::sync::end_lock(self.arch_state);
         self.spinlock.lock.store(false, Ordering::Release);
+        arch::sync::end_lock(self.arch_state);
     }
 }
 
@@ -0,0 +1,175 @@
+//**************************************************************************************************
+// stacks.rs                                                                                       *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::arch::{self, vmm};
+use crate::spinlock::Spinlock;
+use alloc::vec;
+use alloc::vec::Vec;
+use kernel_interface::init::{BP_STACK_VIRTUAL_BOTTOM, STACK_PAGES};
+use memory::Segment;
+
+pub const DEFAULT_PAGES: usize = 4;
+
+pub const GUARD_PAGES: usize = 1;
+
+pub const BP_TASK_ID: u64 = 0;
+
+static STATE: Spinlock<Option<State>> = Spinlock::new(None);
+
+pub unsafe fn init() {
+    let mut state = STATE.lock();
+
+    assert!(state.is_none(), "Kernel stacks have already been initialized.");
+
+    // The BP stack is mapped by the boot loader directly below the kernel. The area below it is
+    // reserved as its guard so it only needs to be recorded.
+
+    let bp_stack = KernelStack {
+        task_id: BP_TASK_ID,
+        bottom: BP_STACK_VIRTUAL_BOTTOM as usize,
+        pages: STACK_PAGES as usize,
+    };
+
+    *state = Some(State {
+        next: vmm::KERNEL_STACKS_VIRTUAL_START as usize,
+        end: bp_stack.guard_segment().start(),
+        used: vec![bp_stack],
+        free: Vec::new(),
+    });
+
+    info!("Kernel stacks initialized.");
+}
+
+pub unsafe fn allocate(task_id: u64, pages: usize) -> KernelStack {
+    assert_ne!(pages, 0, "Kernel stack must have at least one page.");
+
+    let stack = {
+        let mut state_lock = STATE.lock();
+
+        let state = state_lock
+            .as_mut()
+            .expect("Kernel stacks not initialized before allocating.");
+
+        let bottom = state
+            .reserve(pages)
+            .expect("Out of virtual memory for kernel stacks.");
+
+        let stack = KernelStack {
+            task_id,
+            bottom,
+            pages,
+        };
+
+        state.used.push(stack);
+
+        stack
+    };
+
+    // Only the stack pages are mapped. The guard pages below the stack are left unmapped so
+    // overflowing the stack causes a page fault instead of corrupting other memory.
+
+    vmm::allocate_pages(stack.bottom, stack.pages);
+
+    stack
+}
+
+pub unsafe fn free(stack: KernelStack) {
+    assert_ne!(
+        stack.bottom, BP_STACK_VIRTUAL_BOTTOM as usize,
+        "The BP stack cannot be freed."
+    );
+
+    vmm::free_pages(stack.bottom, stack.pages);
+
+    let mut state_lock = STATE.lock();
+
+    let state = state_lock
+        .as_mut()
+        .expect("Kernel stacks not initialized before freeing.");
+
+    let index = state
+        .used
+        .iter()
+        .position(|used| used.bottom == stack.bottom)
+        .expect("Kernel stack was not allocated.");
+
+    state.used.swap_remove(index);
+    state.free.push((stack.bottom, stack.pages));
+}
+
+// The fault may have been raised while the state was locked so it is not waited on.
+
+pub fn find_guard_hit(address: usize) -> Option<KernelStack> {
+    STATE
+        .try_lock()?
+        .as_ref()?
+        .used
+        .iter()
+        .copied()
+        .find(|stack| stack.guard_segment().contains_address(address))
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct KernelStack {
+    task_id: u64,
+    bottom: usize,
+    pages: usize,
+}
+
+impl KernelStack {
+    pub fn task_id(self) -> u64 {
+        self.task_id
+    }
+
+    pub fn pages(self) -> usize {
+        self.pages
+    }
+
+    pub fn top(self) -> usize {
+        self.segment().end()
+    }
+
+    pub fn segment(self) -> Segment {
+        Segment::with_len(self.bottom, self.pages * arch::PAGE_SIZE)
+    }
+
+    pub fn guard_segment(self) -> Segment {
+        Segment::with_end(self.bottom - guard_len(), self.bottom)
+    }
+}
+
+struct State {
+    next: usize,
+    end: usize,
+    used: Vec<KernelStack>,
+    free: Vec<(usize, usize)>,
+}
+
+impl State {
+    fn reserve(&mut self, pages: usize) -> Option<usize> {
+        // Reuse a freed area of the same size if possible. Areas are never split or merged since
+        // almost all stacks use the default size.
+
+        if let Some(index) = self.free.iter().position(|&(_, len)| len == pages) {
+            return Some(self.free.swap_remove(index).0);
+        }
+
+        let bottom = self.next + guard_len();
+        let top = bottom + pages * arch::PAGE_SIZE;
+
+        if top > self.end {
+            return None;
+        }
+
+        self.next = top;
+
+        Some(bottom)
+    }
+}
+
+const fn guard_len() -> usize {
+    GUARD_PAGES * arch::PAGE_SIZE
+}
@@ -0,0 +1,205 @@
+//**************************************************************************************************
+// symbols.rs                                                                                      *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::arch::vmm;
+use crate::spinlock::Spinlock;
+use core::fmt;
+use core::slice;
+use elf::{Class, Data, StringTable, SymbolTable, SymbolType};
+use kernel_interface::init::Args;
+
+static STATE: Spinlock<Option<State>> = Spinlock::new(None);
+
+pub unsafe fn init(args: &Args) {
+    let info = &args.symbols;
+
+    if !info.is_available() {
+        warn!("No kernel symbols were provided. Backtraces will not be symbolized.");
+        return;
+    }
+
+    let symbol_table = slice::from_raw_parts(
+        vmm::convert_physical_ptr(info.symbol_table.as_ptr::<u8>()),
+        info.symbol_table_len,
+    );
+
+    let string_table = slice::from_raw_parts(
+        vmm::convert_physical_ptr(info.string_table.as_ptr::<u8>()),
+        info.string_table_len,
+    );
+
+    let symbol_table = SymbolTable::new(symbol_table, Class::SIXTY_FOUR, Data::LITTLE_ENDIAN)
+        .expect("Kernel symbol table is invalid.");
+
+    *STATE.lock() = Some(State {
+        symbol_table,
+        string_table: StringTable::new(string_table),
+        slide: args.kernel_slide as usize,
+    });
+
+    info!(
+        "Kernel symbols initialized with {} symbol(s).",
+        symbol_table.len()
+    );
+}
+
+// Backtraces are printed while panicking so the state is not waited on in case the panic was
+// raised while it was locked.
+
+pub fn resolve(address: usize) -> Option<Location> {
+    let state_lock = STATE.try_lock()?;
+    let state = state_lock.as_ref()?;
+
+    // Symbols have the addresses the kernel was linked at.
+
+    let address = address.checked_sub(state.slide)?;
+
+    let symbol = state.symbol_table.iter().find(|symbol| {
+        symbol.symbol_type() == SymbolType::FUNCTION && symbol.contains_address(address as u64)
+    })?;
+
+    Some(Location {
+        name: state.string_table.get(symbol.name)?,
+        offset: address - symbol.value as usize,
+    })
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Location {
+    name: &'static str,
+    offset: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}+{:#X}", Demangled(self.name), self.offset)
+    }
+}
+
+struct State {
+    symbol_table: SymbolTable<'static>,
+    string_table: StringTable<'static>,
+    slide: usize,
+}
+
+// Rust's legacy mangling wraps length prefixed path segments in _ZN and E with a hash as the last
+// segment. Names that are not mangled this way are printed as is.
+
+struct Demangled<'a>(&'a str);
+
+impl Demangled<'_> {
+    fn inner(&self) -> Option<&str> {
+        let inner = self.0.strip_prefix("_ZN")?.strip_suffix('E')?;
+
+        let mut remaining = inner;
+
+        while !remaining.is_empty() {
+            let (_, rest) = split_segment(remaining)?;
+            remaining = rest;
+        }
+
+        Some(inner)
+    }
+}
+
+impl fmt::Display for Demangled<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut remaining = match self.inner() {
+            Some(inner) => inner,
+            None => return f.write_str(self.0),
+        };
+
+        let mut first = true;
+
+        while let Some((segment, rest)) = split_segment(remaining) {
+            remaining = rest;
+
+            if remaining.is_empty() && is_hash(segment) {
+                break;
+            }
+
+            if !first {
+                f.write_str("::")?;
+            }
+
+            write_segment(f, segment)?;
+            first = false;
+        }
+
+        Ok(())
+    }
+}
+
+fn split_segment(source: &str) -> Option<(&str, &str)> {
+    let digits = source.bytes().take_while(u8::is_ascii_digit).count();
+    let len: usize = source.get(..digits)?.parse().ok()?;
+    let end = digits.checked_add(len)?;
+
+    Some((source.get(digits..end)?, source.get(end..)?))
+}
+
+fn is_hash(segment: &str) -> bool {
+    segment.len() == 17
+        && segment.starts_with('h')
+        && segment[1..].bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+fn write_segment(f: &mut fmt::Formatter<'_>, segment: &str) -> fmt::Result {
+    // Segments starting with an escape are prefixed by an underscore to make them valid
+    // identifiers.
+
+    let mut remaining = if segment.starts_with("_$") {
+        &segment[1..]
+    } else {
+        segment
+    };
+
+    while !remaining.is_empty() {
+        if let Some(rest) = remaining.strip_prefix("..") {
+            f.write_str("::")?;
+            remaining = rest;
+        } else if let Some(rest) = remaining.strip_prefix('$') {
+            let end = match rest.find('$') {
+                Some(end) => end,
+                None => return f.write_str(remaining),
+            };
+
+            match unescape(&rest[..end]) {
+                Some(character) => write!(f, "{}", character)?,
+                None => write!(f, "${}$", &rest[..end])?,
+            }
+
+            remaining = &rest[end + 1..];
+        } else {
+            let end = remaining
+                .find(|c| c == '.' || c == '$')
+                .unwrap_or(remaining.len());
+            let end = if end == 0 { 1 } else { end };
+
+            f.write_str(&remaining[..end])?;
+            remaining = &remaining[end..];
+        }
+    }
+
+    Ok(())
+}
+
+fn unescape(escape: &str) -> Option<char> {
+    match escape {
+        "SP" => Some('@'),
+        "BP" => Some('*'),
+        "RF" => Some('&'),
+        "LT" => Some('<'),
+        "GT" => Some('>'),
+        "LP" => Some('('),
+        "RP" => Some(')'),
+        "C" => Some(','),
+        _ => {
+            let code = u32::from_str_radix(escape.strip_prefix('u')?, 16).ok()?;
+            core::char::from_u32(code)
+        }
+    }
+}
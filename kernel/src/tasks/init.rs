@@ -0,0 +1,142 @@
+//**************************************************************************************************
+// init.rs                                                                                         *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use super::{load, Task};
+use crate::arch::user;
+use crate::arch::vmm::{self, AddressSpace, Protection};
+use crate::arch::{self, PAGE_SIZE};
+use crate::spinlock::Spinlock;
+use crate::{command_line, initial_image};
+use alloc::format;
+
+// The first user task is loaded from the initial image and starts the rest of user space. The
+// init option on the command line replaces the default path.
+
+pub const DEFAULT_INIT_PATH: &str = "system/init";
+
+// The initial image is mapped read only from here so init can start services from it without a
+// storage driver. It keeps its offset into its first page. Init is told where it is with the
+// INITIAL_IMAGE environment variable set to the address and length in hexadecimal.
+
+pub const INITIAL_IMAGE_VIRTUAL_START: u64 = 0x7F00_0000_0000;
+
+// Task 0 is the bootstrap processor's own task.
+
+const INIT_TASK_ID: u64 = 1;
+
+// The user task running on the bootstrap processor.
+//TODO Move this into the scheduler once it runs more than one task.
+
+static CURRENT: Spinlock<Option<UserTask>> = Spinlock::new(None);
+
+// A terminated task whose kernel stack was still in use. It is freed once idle runs.
+
+static TERMINATED: Spinlock<Option<Task>> = Spinlock::new(None);
+
+struct UserTask {
+    task: Task,
+    address_space: AddressSpace,
+}
+
+// Loads the first user task and switches to it. Only returns if it could not be started.
+
+pub unsafe fn start_init() {
+    let path = command_line::options().init.unwrap_or(DEFAULT_INIT_PATH);
+
+    let source = match initial_image::find_file(path) {
+        Some(source) => source,
+        None => {
+            error!(
+                "The init executable \"{}\" is not in the initial image.",
+                path
+            );
+            return;
+        }
+    };
+
+    let (image, image_physical_address) =
+        match initial_image::data().zip(initial_image::physical_address()) {
+            Some(image) => image,
+            None => return,
+        };
+
+    let image_offset = image_physical_address % PAGE_SIZE as u64;
+    let image_address = INITIAL_IMAGE_VIRTUAL_START + image_offset;
+    let image_variable = format!("INITIAL_IMAGE={:#X},{:#X}", image_address, image.len());
+
+    let mut program = match load(source, &[path], &[&image_variable]) {
+        Ok(program) => program,
+        Err(error) => {
+            error!("Failed to load the init executable \"{}\". {}", path, error);
+            return;
+        }
+    };
+
+    let image_pages = (image_offset as usize + image.len() + PAGE_SIZE - 1) / PAGE_SIZE;
+
+    if let Err(error) = program.address_space.map_shared(
+        INITIAL_IMAGE_VIRTUAL_START,
+        image_physical_address - image_offset,
+        image_pages,
+        Protection {
+            writable: false,
+            executable: false,
+            user_accessible: true,
+        },
+    ) {
+        error!(
+            "Failed to map the initial image for \"{}\". {}",
+            path, error
+        );
+        return;
+    }
+
+    let task = Task::new(INIT_TASK_ID, INIT_TASK_ID);
+    let kernel_stack_top = task.kernel_stack().top() as u64;
+
+    info!("Starting \"{}\" at {:#X}.", path, program.entry);
+
+    // Moving the address space doesn't move its tables so it can be activated before it is stored.
+
+    program.address_space.activate();
+
+    *CURRENT.lock() = Some(UserTask {
+        task,
+        address_space: program.address_space,
+    });
+
+    user::enter(program.entry, program.stack_pointer, kernel_stack_top)
+}
+
+// Frees the address space of the user task running on the bootstrap processor. Returns false if
+// there is none. The task itself is freed by idle since its kernel stack is still in use.
+
+pub unsafe fn terminate_current() -> bool {
+    let user_task = match CURRENT.lock().take() {
+        Some(user_task) => user_task,
+        None => return false,
+    };
+
+    vmm::activate_kernel_address_space();
+
+    drop(user_task.address_space);
+
+    *TERMINATED.lock() = Some(user_task.task);
+
+    true
+}
+
+// Runs on the bootstrap processor once there is no user task left.
+
+pub extern "C" fn idle() -> ! {
+    if let Some(task) = TERMINATED.lock().take() {
+        info!("Task {} was terminated.", task.id());
+    }
+
+    loop {
+        arch::wait_for_interrupt();
+    }
+}
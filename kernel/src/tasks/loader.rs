@@ -0,0 +1,507 @@
+//**************************************************************************************************
+// loader.rs                                                                                       *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::arch::vmm::{self, AddressSpace, MapError, Protection};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use elf::{
+    Class, Data, DynamicTag, File, Machine, ObjectType, ProgramHeader, ProgramSegmentType, Version,
+};
+
+// Loads user executables into a new address space. Every loadable segment gets its own pages with
+// the permissions from its flags. Executables are placed at their linked addresses and position
+// independent executables are moved up to DYNAMIC_BASE with their relative relocations applied.
+// Executables that need a dynamic linker are not supported. Everything in the file is untrusted
+// so every offset and address is checked before it is used.
+
+pub const DYNAMIC_BASE: u64 = 0x40_0000;
+
+// The stack ends one page below the end of the user half. Nothing is mapped below it so running
+// past the bottom faults.
+
+pub const USER_STACK_TOP: u64 = vmm::USER_VIRTUAL_END - PAGE_SIZE;
+
+pub const USER_STACK_PAGES: usize = 32;
+
+pub const USER_STACK_BOTTOM: u64 = USER_STACK_TOP - USER_STACK_PAGES as u64 * PAGE_SIZE;
+
+const PAGE_SIZE: u64 = 4096;
+
+const PROGRAM_HEADER_SIZE: u16 = 56;
+
+// Relocations are applied to a copy of the whole image on the kernel heap so its size is limited.
+
+const MAX_RELOCATED_LEN: usize = 64 * 1024 * 1024;
+
+// Auxiliary vector types from the System V ABI.
+
+const AT_NULL: u64 = 0;
+const AT_PHDR: u64 = 3;
+const AT_PHENT: u64 = 4;
+const AT_PHNUM: u64 = 5;
+const AT_PAGESZ: u64 = 6;
+const AT_BASE: u64 = 7;
+const AT_ENTRY: u64 = 9;
+
+// An executable ready to run. The stack pointer points at the argument count as the System V ABI
+// expects at process entry.
+
+#[derive(Debug)]
+pub struct Program {
+    pub address_space: AddressSpace,
+    pub entry: u64,
+    pub stack_pointer: u64,
+}
+
+pub unsafe fn load(
+    source: &[u8],
+    arguments: &[&str],
+    environment: &[&str],
+) -> Result<Program, LoadError> {
+    let file = File::new(source);
+
+    let identity_header = file.read_identity_header()?;
+
+    if !identity_header.is_valid() {
+        return Err(LoadError::NotElf);
+    }
+
+    if identity_header.class != Class::SIXTY_FOUR || identity_header.data != Data::LITTLE_ENDIAN {
+        return Err(LoadError::UnsupportedFormat);
+    }
+
+    let header = file.read_header()?;
+
+    if header.machine != Machine::X86_64 || header.version != Version::CURRENT {
+        return Err(LoadError::UnsupportedFormat);
+    }
+
+    let base = match header.object_type {
+        ObjectType::EXECUTABLE => 0,
+        ObjectType::DYNAMIC => DYNAMIC_BASE,
+        _ => return Err(LoadError::NotExecutable),
+    };
+
+    // The elf library checks the layout of the file. The checks below are specific to running it
+    // in user space.
+
+    file.validate()?;
+
+    let table_len =
+        header.program_header_entry_count as u64 * header.program_header_entry_size as u64;
+    let table_end = table_len.checked_add(header.program_header_table_offset);
+
+    if header.program_header_entry_size != PROGRAM_HEADER_SIZE
+        || table_end.map_or(true, |end| end > source.len() as u64)
+    {
+        return Err(LoadError::InvalidProgramHeaders);
+    }
+
+    let entry = header
+        .entry
+        .checked_add(base)
+        .ok_or(LoadError::InvalidEntry)?;
+
+    let mut address_space = AddressSpace::new();
+    let mut previous: Option<LoadedSegment> = None;
+    let mut program_headers_address = None;
+    let mut entry_is_executable = false;
+
+    for index in 0..header.program_header_entry_count {
+        let program_header = file.read_program_header(index)?;
+
+        match program_header.segment_type {
+            ProgramSegmentType::LOAD => {}
+            ProgramSegmentType::INTERPRETER => return Err(LoadError::NeedsInterpreter),
+            ProgramSegmentType::PROGRAM_HEADER => {
+                program_headers_address = program_header.virtual_address.checked_add(base);
+                continue;
+            }
+            _ => continue,
+        }
+
+        let segment = match load_segment(
+            &mut address_space,
+            source,
+            &program_header,
+            base,
+            previous.as_ref(),
+        ) {
+            Ok(Some(segment)) => segment,
+            Ok(None) => continue,
+            Err(kind) => return Err(LoadError::Segment { index, kind }),
+        };
+
+        // Without a PT_PHDR segment the program headers can still be found if a segment loads
+        // the whole part of the file they are in.
+
+        if program_headers_address.is_none() {
+            program_headers_address = header
+                .program_header_table_offset
+                .checked_sub(program_header.offset)
+                .filter(|&offset| {
+                    offset
+                        .checked_add(table_len)
+                        .map_or(false, |end| end <= program_header.file_size)
+                })
+                .map(|offset| segment.start + offset);
+        }
+
+        if segment.protection.executable && segment.start <= entry && entry < segment.end {
+            entry_is_executable = true;
+        }
+
+        previous = Some(segment);
+    }
+
+    if previous.is_none() {
+        return Err(LoadError::NoLoadSegments);
+    }
+
+    if !entry_is_executable {
+        return Err(LoadError::InvalidEntry);
+    }
+
+    if base != 0 {
+        relocate(&mut address_space, &file, base)?;
+    }
+
+    let mut auxiliary_vector = Vec::new();
+
+    if let Some(address) = program_headers_address {
+        auxiliary_vector.push((AT_PHDR, address));
+    }
+
+    auxiliary_vector.push((AT_PHENT, PROGRAM_HEADER_SIZE as u64));
+    auxiliary_vector.push((AT_PHNUM, header.program_header_entry_count as u64));
+    auxiliary_vector.push((AT_PAGESZ, PAGE_SIZE));
+    auxiliary_vector.push((AT_BASE, 0));
+    auxiliary_vector.push((AT_ENTRY, entry));
+    //TODO Add AT_RANDOM once the kernel has a source of random numbers.
+
+    let stack_pointer = create_stack(
+        &mut address_space,
+        arguments,
+        environment,
+        &auxiliary_vector,
+    )?;
+
+    Ok(Program {
+        address_space,
+        entry,
+        stack_pointer,
+    })
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LoadError {
+    Elf(elf::Error),
+    NotElf,
+    UnsupportedFormat,
+    NotExecutable,
+    NeedsInterpreter,
+    InvalidProgramHeaders,
+    Segment { index: u16, kind: SegmentError },
+    NoLoadSegments,
+    InvalidEntry,
+    TooLargeToRelocate,
+    ArgumentsTooLarge,
+    Map(MapError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Elf(error) => write!(f, "{}", error),
+            LoadError::NotElf => write!(f, "The file is not an ELF file."),
+            LoadError::UnsupportedFormat => write!(
+                f,
+                "Only 64-bit little endian x86-64 executables are supported."
+            ),
+            LoadError::NotExecutable => write!(f, "The file is not an executable."),
+            LoadError::NeedsInterpreter => write!(
+                f,
+                "The executable needs a dynamic linker which is not supported."
+            ),
+            LoadError::InvalidProgramHeaders => write!(
+                f,
+                "The program header table has an invalid entry size or is outside of the file."
+            ),
+            LoadError::Segment { index, kind } => write!(f, "Program header {}: {}", index, kind),
+            LoadError::NoLoadSegments => write!(f, "The executable has no segments to load."),
+            LoadError::InvalidEntry => {
+                write!(f, "The entry point is not inside an executable segment.")
+            }
+            LoadError::TooLargeToRelocate => write!(
+                f,
+                "The executable is too large to have its relocations applied."
+            ),
+            LoadError::ArgumentsTooLarge => {
+                write!(f, "The arguments and environment do not fit on the stack.")
+            }
+            LoadError::Map(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<elf::Error> for LoadError {
+    fn from(error: elf::Error) -> Self {
+        LoadError::Elf(error)
+    }
+}
+
+impl From<MapError> for LoadError {
+    fn from(error: MapError) -> Self {
+        LoadError::Map(error)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SegmentError {
+    FileSizeTooLarge,
+    OutsideOfFile,
+    InvalidAlignment,
+    OutOfRange,
+    Overlapping,
+    Map(MapError),
+}
+
+impl fmt::Display for SegmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SegmentError::FileSizeTooLarge => {
+                write!(
+                    f,
+                    "The segment has a file size larger than its memory size."
+                )
+            }
+            SegmentError::OutsideOfFile => write!(f, "The segment data is outside of the file."),
+            SegmentError::InvalidAlignment => write!(f, "The segment alignment is invalid."),
+            SegmentError::OutOfRange => {
+                write!(f, "The segment is outside of the user address range.")
+            }
+            SegmentError::Overlapping => write!(
+                f,
+                "The segment overlaps or comes before the previous segment."
+            ),
+            SegmentError::Map(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<MapError> for SegmentError {
+    fn from(error: MapError) -> Self {
+        SegmentError::Map(error)
+    }
+}
+
+struct LoadedSegment {
+    start: u64,
+    end: u64,
+    protection: Protection,
+}
+
+// Segments must be sorted by address as the ELF specification requires. Two segments may share a
+// page if their contents don't overlap and the page gets the permissions of both. Pages are
+// cleared when they are allocated so the part of the segment past its file data, the BSS, is
+// already zero.
+
+unsafe fn load_segment(
+    address_space: &mut AddressSpace,
+    source: &[u8],
+    program_header: &ProgramHeader,
+    base: u64,
+    previous: Option<&LoadedSegment>,
+) -> Result<Option<LoadedSegment>, SegmentError> {
+    if program_header.memory_size == 0 {
+        return Ok(None);
+    }
+
+    if program_header.file_size > program_header.memory_size {
+        return Err(SegmentError::FileSizeTooLarge);
+    }
+
+    let file_start = program_header.offset;
+    let file_end = file_start
+        .checked_add(program_header.file_size)
+        .filter(|&end| end <= source.len() as u64)
+        .ok_or(SegmentError::OutsideOfFile)?;
+
+    let alignment = program_header.alignment;
+
+    if alignment > 1 && (!alignment.is_power_of_two() || base % alignment != 0) {
+        return Err(SegmentError::InvalidAlignment);
+    }
+
+    let start = program_header
+        .virtual_address
+        .checked_add(base)
+        .ok_or(SegmentError::OutOfRange)?;
+    let end = start
+        .checked_add(program_header.memory_size)
+        .ok_or(SegmentError::OutOfRange)?;
+
+    if start < vmm::USER_VIRTUAL_START || end > USER_STACK_BOTTOM {
+        return Err(SegmentError::OutOfRange);
+    }
+
+    let protection = Protection {
+        writable: program_header.is_writable(),
+        executable: program_header.is_executable(),
+        user_accessible: true,
+    };
+
+    let mut first_page = start - start % PAGE_SIZE;
+    let end_page = (end + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE;
+
+    if let Some(previous) = previous {
+        if start < previous.end {
+            return Err(SegmentError::Overlapping);
+        }
+
+        let previous_last_page = (previous.end - 1) / PAGE_SIZE * PAGE_SIZE;
+
+        if previous_last_page == first_page {
+            address_space.protect(first_page, 1, previous.protection.union(protection))?;
+            first_page += PAGE_SIZE;
+        }
+    }
+
+    if first_page < end_page {
+        address_space.allocate_pages(
+            first_page,
+            ((end_page - first_page) / PAGE_SIZE) as usize,
+            protection,
+        )?;
+    }
+
+    address_space.write(start, &source[file_start as usize..file_end as usize])?;
+
+    Ok(Some(LoadedSegment {
+        start,
+        end,
+        protection,
+    }))
+}
+
+// Applies the relocations of a position independent executable moved to the base. They are applied
+// to a copy of the image laid out like it is in memory and the copy is then written over the
+// loaded segments, which are checked already.
+
+unsafe fn relocate(
+    address_space: &mut AddressSpace,
+    file: &File,
+    base: u64,
+) -> Result<(), LoadError> {
+    let has_relocations = file
+        .read_dynamic_table()?
+        .map_or(false, |table| table.find(DynamicTag::RELA).is_some());
+
+    if !has_relocations {
+        return Ok(());
+    }
+
+    let image_segment = file.load_memory_segment()?;
+
+    if image_segment.len() > MAX_RELOCATED_LEN {
+        return Err(LoadError::TooLargeToRelocate);
+    }
+
+    let mut image = vec![0; image_segment.len()];
+
+    file.load_to(&mut image)?;
+    file.relocate(&mut image, base)?;
+
+    for segment in file.load_segments()? {
+        let segment = segment?;
+
+        if segment.memory_size == 0 {
+            continue;
+        }
+
+        let offset = segment.virtual_address as usize - image_segment.start();
+        let len = segment.memory_size as usize;
+
+        address_space.write(segment.virtual_address + base, &image[offset..offset + len])?;
+    }
+
+    Ok(())
+}
+
+// Lays out the stack as the System V ABI expects at process entry. From the stack pointer up
+// there is the argument count, the argument pointers, the environment pointers and the auxiliary
+// vector, each list ending in a null entry. The strings themselves are at the top of the stack.
+
+unsafe fn create_stack(
+    address_space: &mut AddressSpace,
+    arguments: &[&str],
+    environment: &[&str],
+    auxiliary_vector: &[(u64, u64)],
+) -> Result<u64, LoadError> {
+    address_space.allocate_pages(
+        USER_STACK_BOTTOM,
+        USER_STACK_PAGES,
+        Protection {
+            writable: true,
+            executable: false,
+            user_accessible: true,
+        },
+    )?;
+
+    let strings_len = arguments
+        .iter()
+        .chain(environment)
+        .map(|string| string.len() as u64 + 1)
+        .sum::<u64>();
+
+    let vector_len =
+        1 + (arguments.len() + 1) + (environment.len() + 1) + (auxiliary_vector.len() + 1) * 2;
+
+    let strings_start = USER_STACK_TOP
+        .checked_sub(strings_len)
+        .filter(|&start| start >= USER_STACK_BOTTOM)
+        .ok_or(LoadError::ArgumentsTooLarge)?;
+
+    let stack_pointer = strings_start
+        .checked_sub(vector_len as u64 * 8)
+        .map(|address| address & !0xF)
+        .filter(|&address| address >= USER_STACK_BOTTOM)
+        .ok_or(LoadError::ArgumentsTooLarge)?;
+
+    let mut strings = Vec::with_capacity(strings_len as usize);
+    let mut vector = Vec::with_capacity(vector_len);
+
+    vector.push(arguments.len() as u64);
+
+    for list in [arguments, environment].iter() {
+        for string in list.iter() {
+            vector.push(strings_start + strings.len() as u64);
+            strings.extend_from_slice(string.as_bytes());
+            strings.push(0);
+        }
+
+        vector.push(0);
+    }
+
+    for &(key, value) in auxiliary_vector {
+        vector.push(key);
+        vector.push(value);
+    }
+
+    vector.push(AT_NULL);
+    vector.push(0);
+
+    let vector_bytes = vector
+        .iter()
+        .flat_map(|value| value.to_le_bytes().to_vec())
+        .collect::<Vec<u8>>();
+
+    address_space.write(stack_pointer, &vector_bytes)?;
+    address_space.write(strings_start, &strings)?;
+
+    Ok(stack_pointer)
+}
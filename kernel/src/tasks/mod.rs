@@ -4,9 +4,13 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+mod init;
+mod loader;
 mod scheduler;
 mod task;
 
+pub use init::*;
+pub use loader::*;
 pub use scheduler::*;
 pub use task::*;
 
@@ -4,7 +4,42 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+use crate::stacks::{self, KernelStack};
+
 pub struct Task {
     id: u64,
-    group_id: u64
-}
\ No newline at end of file
+    group_id: u64,
+    kernel_stack: KernelStack,
+}
+
+impl Task {
+    pub unsafe fn new(id: u64, group_id: u64) -> Self {
+        Self::with_stack_pages(id, group_id, stacks::DEFAULT_PAGES)
+    }
+
+    pub unsafe fn with_stack_pages(id: u64, group_id: u64, stack_pages: usize) -> Self {
+        Self {
+            id,
+            group_id,
+            kernel_stack: stacks::allocate(id, stack_pages),
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn group_id(&self) -> u64 {
+        self.group_id
+    }
+
+    pub fn kernel_stack(&self) -> KernelStack {
+        self.kernel_stack
+    }
+}
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        unsafe { stacks::free(self.kernel_stack) }
+    }
+}
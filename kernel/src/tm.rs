@@ -5,6 +5,7 @@
 //**************************************************************************************************
 
 use crate::arch::drivers::timers::create_devices as create_arch_devices;
+use crate::command_line;
 use crate::drivers::timers::{create_devices, Device as TimerDevice};
 use crate::spinlock::Spinlock;
 use alloc::vec::Vec;
@@ -25,7 +26,7 @@ pub unsafe fn init_bp(args: &Args) {
     let scheduler_timer =
         find_scheduler_timer(&device_list).expect("No available timer for scheduling.");
 
-    println!("Using \"{}\" for scheduler timer.", scheduler_timer.id());
+    info!("Using \"{}\" for scheduler timer.", scheduler_timer.id());
 
     scheduler_timer.init(args);
 
@@ -33,7 +34,7 @@ pub unsafe fn init_bp(args: &Args) {
 
     let clock_timer = find_clock_timer(&device_list).expect("No available timer for clock.");
 
-    println!("Using \"{}\" for clock timer.", clock_timer.id());
+    info!("Using \"{}\" for clock timer.", clock_timer.id());
 
     clock_timer.init(args);
 
@@ -47,7 +48,7 @@ pub unsafe fn init_bp(args: &Args) {
         let calibration_timer =
             find_calibration_timer(&device_list).expect("No available timer for calibration.");
 
-        println!(
+        info!(
             "Using \"{}\" for calibration timer.",
             calibration_timer.id()
         );
@@ -81,17 +82,36 @@ pub unsafe fn init_ap() {
 }
 
 fn find_scheduler_timer(device_list: &Vec<TimerDevice>) -> Option<TimerDevice> {
-    None
+    find_preferred_timer(device_list)
 }
 
 fn find_clock_timer(device_list: &Vec<TimerDevice>) -> Option<TimerDevice> {
-    None
+    find_preferred_timer(device_list)
 }
 
 fn find_calibration_timer(device_list: &Vec<TimerDevice>) -> Option<TimerDevice> {
     None
 }
 
+// The timer selected on the command line is used for both scheduling and the clock.
+
+fn find_preferred_timer(device_list: &[TimerDevice]) -> Option<TimerDevice> {
+    let options = command_line::options();
+
+    let timer = device_list
+        .iter()
+        .find(|device| options.is_timer_preferred(device.id()))
+        .copied();
+
+    if timer.is_none() {
+        if let Some(id) = options.timer {
+            warn!("The timer \"{}\" from the command line is not available.", id);
+        }
+    }
+
+    timer
+}
+
 fn calibrate_timer(timer: &TimerDevice, calibration_timer: &TimerDevice) {
     let calibration_time: Nanoseconds<u64> = Miliseconds::new(10).convert();
 
@@ -0,0 +1,155 @@
+//**************************************************************************************************
+// uefi_runtime.rs                                                                                 *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                             *
+//**************************************************************************************************
+
+use crate::arch;
+use crate::arch::vmm;
+use crate::spinlock::Spinlock;
+use alloc::vec::Vec;
+use kernel_interface::init::Args;
+use uefi::memory::PAGE_SIZE;
+use uefi::runtime::{ResetType, RuntimeServices};
+use uefi::{Error, Guid};
+
+pub use uefi::runtime::Time;
+pub use uefi::variable::{Variable, VariableAttributes, VariableName, GLOBAL_VARIABLE};
+
+// The runtime services are not reentrant so every call is made while the state is locked.
+
+static STATE: Spinlock<Option<RuntimeServices>> = Spinlock::new(None);
+
+// Moves the runtime services to the addresses the VMM maps them to. This must be done before the
+// VMM is initialized since firmware runs identity mapped until SetVirtualAddressMap returns.
+
+pub unsafe fn init(args: &Args) {
+    let info = &args.system_info;
+
+    if info.uefi_runtime.is_null() {
+        info!("No UEFI runtime services were provided.");
+        return;
+    }
+
+    let services = match RuntimeServices::new(info.uefi_runtime_ptr() as *mut _) {
+        Ok(services) => services,
+        Err(error) => {
+            error!("The UEFI runtime services table is invalid. {}", error);
+            return;
+        }
+    };
+
+    let map = info.uefi_runtime_map.as_mut_slice();
+
+    for descriptor in map.iter_mut() {
+        let len = descriptor.number_of_pages * PAGE_SIZE as u64;
+
+        descriptor.virtual_start =
+            match vmm::uefi_runtime_virtual_address(descriptor.physical_start, len) {
+                Some(virtual_address) => virtual_address,
+                None => {
+                    error!(
+                        "The UEFI runtime region at {:#X} can't be mapped. Runtime services will \
+                         not be used.",
+                        descriptor.physical_start
+                    );
+                    return;
+                }
+            };
+    }
+
+    match services.set_virtual_address_map(map) {
+        Ok(services) => {
+            info!(
+                "UEFI runtime services moved to {:#X} with {} region(s).",
+                services.as_ptr() as usize,
+                map.len()
+            );
+
+            *STATE.lock() = Some(services);
+        }
+        Err(error) => error!("Failed to set the UEFI virtual address map. {}", error),
+    }
+}
+
+pub fn is_available() -> bool {
+    STATE.lock().is_some()
+}
+
+pub fn time() -> Result<Time, Error> {
+    with_services(|services| services.time())
+}
+
+pub fn set_time(time: &Time) -> Result<(), Error> {
+    with_services(|services| services.set_time(time))
+}
+
+pub fn next_high_monotonic_count() -> Result<u32, Error> {
+    with_services(|services| services.next_high_monotonic_count())
+}
+
+// Only variables with the runtime access attribute can be seen by the kernel.
+
+pub fn variable(name: &str, vendor: &Guid) -> Result<Variable, Error> {
+    with_services(|services| services.variable(name, vendor))
+}
+
+pub fn set_variable(
+    name: &str,
+    vendor: &Guid,
+    attributes: VariableAttributes,
+    data: &[u8],
+) -> Result<(), Error> {
+    with_services(|services| services.set_variable(name, vendor, attributes, data))
+}
+
+pub fn delete_variable(name: &str, vendor: &Guid) -> Result<(), Error> {
+    with_services(|services| services.delete_variable(name, vendor))
+}
+
+// The names are collected at once since the firmware keeps its position between calls.
+
+pub fn variable_names() -> Result<Vec<VariableName>, Error> {
+    with_services(|services| services.variable_names().collect())
+}
+
+pub fn boot_order() -> Result<Vec<u16>, Error> {
+    with_services(|services| services.boot_order())
+}
+
+pub fn boot_current() -> Result<u16, Error> {
+    with_services(|services| services.boot_current())
+}
+
+pub fn reboot() -> ! {
+    reset(ResetType::Cold)
+}
+
+pub fn shutdown() -> ! {
+    reset(ResetType::Shutdown)
+}
+
+// The CPU is halted instead if firmware can't reset the system. The state is unlocked first so
+// the lock isn't held forever.
+
+fn reset(reset_type: ResetType) -> ! {
+    match STATE.lock().as_ref() {
+        Some(services) => {
+            info!("Resetting the system with {:?}.", reset_type);
+            services.reset(reset_type);
+            error!("Firmware failed to reset the system. The system will be halted instead.");
+        }
+        None => {
+            error!("UEFI runtime services are unavailable. The system will be halted instead.")
+        }
+    }
+
+    unsafe { arch::stall() }
+}
+
+fn with_services<T>(f: impl FnOnce(&RuntimeServices) -> Result<T, Error>) -> Result<T, Error> {
+    match STATE.lock().as_ref() {
+        Some(services) => f(services),
+        None => Err(Error::NotSupported),
+    }
+}
@@ -18,6 +18,38 @@ macro_rules! create_ipi_base {
             pub fn new() -> Self {
                 Self(0)
             }
+
+            pub fn vector(self) -> u8 {
+                self.0.get_bits(0, 0, 8) as u8
+            }
+
+            pub fn set_vector(&mut self, vector: u8) {
+                self.0.set_bits_assign(vector as u64, 0, 0, 8);
+            }
+
+            pub fn delivery_mode(self) -> DeliveryMode {
+                DeliveryMode::new(self.0.get_bits(8, 0, 3) as u8)
+            }
+
+            pub fn set_delivery_mode(&mut self, mode: DeliveryMode) {
+                self.0.set_bits_assign(u8::from(mode) as u64, 8, 0, 3);
+            }
+
+            pub fn level_assert(self) -> bool {
+                self.0.get_bit(14)
+            }
+
+            pub fn set_level_assert(&mut self, value: bool) {
+                self.0.set_bit_assign(14, value);
+            }
+
+            pub fn destination_shorthand(self) -> DestinationShorthand {
+                DestinationShorthand::new(self.0.get_bits(18, 0, 2) as u8)
+            }
+
+            pub fn set_destination_shorthand(&mut self, shorthand: DestinationShorthand) {
+                self.0.set_bits_assign(u8::from(shorthand) as u64, 18, 0, 2);
+            }
         }
 
         impl From<u64> for $name {
@@ -34,6 +66,17 @@ macro_rules! create_ipi_base {
     };
 }
 
+c_enum!(
+    pub enum DeliveryMode : u8 {
+        FIXED = 0b000,
+        LOWEST_PRIORITY = 0b001,
+        SMI = 0b010,
+        NMI = 0b100,
+        INIT = 0b101,
+        START_UP = 0b110,
+    }
+);
+
 c_enum!(
     pub enum DestinationShorthand : u8 {
         NO_SHORTHAND = 0b00,
@@ -51,7 +94,7 @@ impl Ipi {
     }
 
     pub fn set_destination_id(&mut self, id: u8) {
-        self.0.set_bits_assign(id as u64, 56, 0, 0);
+        self.0.set_bits_assign(id as u64, 56, 0, 8);
     }
 
     pub fn destination_id(self) -> u8 {
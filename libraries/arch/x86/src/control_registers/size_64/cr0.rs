@@ -0,0 +1,65 @@
+//**************************************************************************************************
+// cr0.rs                                                                                          *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use memory::{GetBit, SetBitAssign};
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, Default)]
+pub struct Value(u64);
+
+impl Value {
+    pub const fn new() -> Self {
+        Value(0)
+    }
+
+    pub fn protection_enabled(self) -> bool {
+        self.0.get_bit(0)
+    }
+
+    pub fn set_protection_enabled(&mut self, value: bool) {
+        self.0.set_bit_assign(0, value)
+    }
+
+    pub fn write_protect(self) -> bool {
+        self.0.get_bit(16)
+    }
+
+    pub fn set_write_protect(&mut self, value: bool) {
+        self.0.set_bit_assign(16, value)
+    }
+
+    pub fn paging_enabled(self) -> bool {
+        self.0.get_bit(31)
+    }
+
+    pub fn set_paging_enabled(&mut self, value: bool) {
+        self.0.set_bit_assign(31, value)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Value {
+        Value(value)
+    }
+}
+
+impl From<Value> for u64 {
+    fn from(value: Value) -> u64 {
+        value.0
+    }
+}
+
+pub fn read() -> Value {
+    let value: Value;
+    unsafe {
+        llvm_asm!("mov %cr0, $0" : "=r"(value) ::: "volatile");
+    }
+    value
+}
+
+pub unsafe fn write(value: Value) {
+    llvm_asm!("mov $0, %cr0" :: "r"(value) :: "volatile")
+}
@@ -0,0 +1,16 @@
+//**************************************************************************************************
+// cr2.rs                                                                                          *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+// CR2 only holds the linear address that caused the last page fault so it is read as a plain u64
+// instead of a flags value.
+
+pub fn read() -> u64 {
+    let value: u64;
+    unsafe {
+        llvm_asm!("mov %cr2, $0" : "=r"(value) ::: "volatile");
+    }
+    value
+}
@@ -4,5 +4,7 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+pub mod cr0;
+pub mod cr2;
 pub mod cr3;
 pub mod cr4;
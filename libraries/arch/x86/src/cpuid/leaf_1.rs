@@ -34,6 +34,12 @@ impl From<VersionInformation> for u32 {
 #[repr(transparent)]
 pub struct AdditionalInformation(u32);
 
+impl AdditionalInformation {
+    pub fn initial_apic_id(self) -> u8 {
+        self.0.get_bits(24, 0, 8) as u8
+    }
+}
+
 impl From<u32> for AdditionalInformation {
     fn from(value: u32) -> Self {
         AdditionalInformation(value)
@@ -75,6 +81,10 @@ impl Features {
         self.ecx.get_bit(24)
     }
 
+    pub fn rdrand(self) -> bool {
+        self.ecx.get_bit(30)
+    }
+
     // EDX
 
     pub fn tsc(self) -> bool {
@@ -84,6 +94,10 @@ impl Features {
     pub fn apic(self) -> bool {
         self.edx.get_bit(9)
     }
+
+    pub fn pat(self) -> bool {
+        self.edx.get_bit(16)
+    }
 }
 
 pub unsafe fn read() -> (VersionInformation, AdditionalInformation, Features) {
@@ -31,12 +31,16 @@ impl Features {
         self.edx
     }
 
+    pub fn execute_disable(self) -> bool {
+        self.edx.get_bit(20)
+    }
+
     pub fn pages_1gib(self) -> bool {
         self.edx.get_bit(26)
     }
 }
 
 pub unsafe fn read() -> Features {
-    let result = __cpuid(1);
+    let result = __cpuid(0x8000_0001);
     Features::from_register_values(result.ecx, result.edx)
 }
@@ -0,0 +1,99 @@
+//**************************************************************************************************
+// error_code.rs                                                                                   *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use memory::GetBit;
+
+// Error code pushed by #TS, #NP, #SS and #GP when the exception is related to a selector or an
+// IDT vector.
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, Default)]
+pub struct SelectorErrorCode(u64);
+
+impl SelectorErrorCode {
+    pub fn is_null(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_external(self) -> bool {
+        self.0.get_bit(0)
+    }
+
+    pub fn table(self) -> DescriptorTable {
+        if self.0.get_bit(1) {
+            DescriptorTable::Idt
+        } else if self.0.get_bit(2) {
+            DescriptorTable::Ldt
+        } else {
+            DescriptorTable::Gdt
+        }
+    }
+
+    pub fn index(self) -> u16 {
+        ((self.0 >> 3) & 0x1FFF) as u16
+    }
+}
+
+impl From<u64> for SelectorErrorCode {
+    fn from(value: u64) -> Self {
+        SelectorErrorCode(value)
+    }
+}
+
+impl From<SelectorErrorCode> for u64 {
+    fn from(value: SelectorErrorCode) -> Self {
+        value.0
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DescriptorTable {
+    Gdt,
+    Idt,
+    Ldt,
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, Default)]
+pub struct PageFaultErrorCode(u64);
+
+impl PageFaultErrorCode {
+    pub fn is_protection_violation(self) -> bool {
+        self.0.get_bit(0)
+    }
+
+    pub fn is_write(self) -> bool {
+        self.0.get_bit(1)
+    }
+
+    pub fn is_user(self) -> bool {
+        self.0.get_bit(2)
+    }
+
+    pub fn is_reserved_bit_violation(self) -> bool {
+        self.0.get_bit(3)
+    }
+
+    pub fn is_instruction_fetch(self) -> bool {
+        self.0.get_bit(4)
+    }
+
+    pub fn is_protection_key_violation(self) -> bool {
+        self.0.get_bit(5)
+    }
+}
+
+impl From<u64> for PageFaultErrorCode {
+    fn from(value: u64) -> Self {
+        PageFaultErrorCode(value)
+    }
+}
+
+impl From<PageFaultErrorCode> for u64 {
+    fn from(value: PageFaultErrorCode) -> Self {
+        value.0
+    }
+}
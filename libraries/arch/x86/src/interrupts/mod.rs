@@ -4,8 +4,13 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+use memory::GetBit;
+
+mod error_code;
 pub mod size_64;
 
+pub use error_code::*;
+
 pub unsafe fn enable() {
     llvm_asm!("sti" :::: "volatile");
 }
@@ -13,3 +18,15 @@ pub unsafe fn enable() {
 pub unsafe fn disable() {
     llvm_asm!("cli" :::: "volatile");
 }
+
+pub fn are_enabled() -> bool {
+    let flags: u64;
+
+    unsafe {
+        llvm_asm!("pushfq
+        pop $0"
+        : "=r"(flags) ::: "volatile");
+    }
+
+    flags.get_bit(9)
+}
@@ -32,11 +32,11 @@ impl Descriptor {
     }
 
     pub fn is_present(self) -> bool {
-        self.upper.get_bit(15)
+        self.middle.get_bit(15)
     }
 
     pub fn set_is_present(&mut self, value: bool) {
-        self.upper.set_bit_assign(15, value);
+        self.middle.set_bit_assign(15, value);
     }
 
     pub fn set_offset(&mut self, offset: u64) {
@@ -67,12 +67,17 @@ impl Descriptor {
         self.middle = (self.middle & !0x6000) | ((privilege as u32) << 13);
     }
 
-    pub fn ist(self) -> IstIndex {
-        IstIndex::try_from((self.middle & 0x7) as u8).unwrap()
+    // An IST index of zero means the interrupt does not switch stacks through the IST.
+
+    pub fn ist(self) -> Option<IstIndex> {
+        match self.middle & 0x7 {
+            0 => None,
+            index => Some(IstIndex::try_from(index as u8).unwrap()),
+        }
     }
 
-    pub fn set_ist(&mut self, ist: IstIndex) {
-        self.middle = (self.middle & !0x7) | (ist as u32);
+    pub fn set_ist(&mut self, ist: Option<IstIndex>) {
+        self.middle = (self.middle & !0x7) | ist.map_or(0, |ist| ist as u32);
     }
 
     pub fn descriptor_type(self) -> DescriptorType {
@@ -117,13 +122,13 @@ numeric_enum!(
 numeric_enum!(
     #[derive(Copy, Clone, PartialEq, Eq)]
     pub enum IstIndex {
-        One = 0,
-        Two = 1,
-        Three = 2,
-        Four = 3,
-        Five = 4,
-        Six = 5,
-        Seven = 6,
+        One = 1,
+        Two = 2,
+        Three = 3,
+        Four = 4,
+        Five = 5,
+        Six = 6,
+        Seven = 7,
     }
 
     impl TryFrom<u8>;
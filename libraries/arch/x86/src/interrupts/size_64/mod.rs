@@ -22,7 +22,9 @@ impl IdtRegisterValue {
         if entry_count > 256 {
             return Err(());
         }
-        let limit = u16::try_from(entry_count * mem::size_of::<u64>() - 1).map_err(|_| ())?;
+        let limit =
+            u16::try_from(entry_count * mem::size_of::<interrupt_trap_gate::Descriptor>() - 1)
+                .map_err(|_| ())?;
         Ok(Self { limit, entries })
     }
 }
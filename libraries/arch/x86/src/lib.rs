@@ -17,6 +17,7 @@ pub mod msr;
 pub mod paging;
 mod physical_address;
 mod privilege;
+pub mod random;
 pub mod segmentation;
 mod selector;
 pub mod tasks;
@@ -0,0 +1,49 @@
+//**************************************************************************************************
+// ia32_efer.rs                                                                                    *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::msr::Msr;
+use memory::{GetBit, SetBitAssign};
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct Value(u64);
+
+impl Value {
+    pub fn long_mode_active(&self) -> bool {
+        self.0.get_bit(10)
+    }
+
+    // Execute disable bits in paging entries are reserved while this is off.
+
+    pub fn no_execute_enabled(&self) -> bool {
+        self.0.get_bit(11)
+    }
+
+    pub fn set_no_execute_enabled(&mut self, value: bool) {
+        self.0.set_bit_assign(11, value);
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Value(value)
+    }
+}
+
+impl From<Value> for u64 {
+    fn from(value: Value) -> Self {
+        value.0
+    }
+}
+
+const MSR: Msr = Msr::new(0xC000_0080);
+
+pub unsafe fn read() -> Value {
+    MSR.read().into()
+}
+
+pub unsafe fn write(value: Value) {
+    MSR.write(value.into());
+}
@@ -0,0 +1,23 @@
+//**************************************************************************************************
+// ia32_pat.rs                                                                                     *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::msr::Msr;
+
+// The memory types selected by the PAT, PCD and PWT bits of paging entries. The first four
+// entries keep their power on values of write back, write through, uncached minus and uncached.
+// The fifth is write combining instead of write back. See paging::size_64::CacheType.
+
+pub const VALUE: u64 = 0x0007_0401_0007_0406;
+
+const MSR: Msr = Msr::new(0x277);
+
+pub unsafe fn read() -> u64 {
+    MSR.read()
+}
+
+pub unsafe fn write(value: u64) {
+    MSR.write(value);
+}
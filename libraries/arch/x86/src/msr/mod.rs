@@ -5,6 +5,8 @@
 //**************************************************************************************************
 
 pub mod ia32_apic_base;
+pub mod ia32_efer;
+pub mod ia32_pat;
 pub mod ia32_tsc_deadline;
 
 use memory::split::Halves;
@@ -15,6 +15,38 @@ macro_rules! u64_paging_entry {
         $visibility struct $name(u64);
 
         impl $name {
+            pub fn writable(self) -> bool {
+                self.0.get_bit(1)
+            }
+
+            pub fn set_writable(&mut self, value: bool) {
+                self.0.set_bit_assign(1, value);
+            }
+
+            pub fn write_through(self) -> bool {
+                self.0.get_bit(3)
+            }
+
+            pub fn set_write_through(&mut self, value: bool) {
+                self.0.set_bit_assign(3, value);
+            }
+
+            pub fn cache_disabled(self) -> bool {
+                self.0.get_bit(4)
+            }
+
+            pub fn set_cache_disabled(&mut self, value: bool) {
+                self.0.set_bit_assign(4, value);
+            }
+
+            pub fn user_accessible(self) -> bool {
+                self.0.get_bit(2)
+            }
+
+            pub fn set_user_accessible(&mut self, value: bool) {
+                self.0.set_bit_assign(2, value);
+            }
+
             pub fn execute_disabled(self) -> bool {
                 self.0.get_bit(63)
             }
@@ -22,6 +54,12 @@ macro_rules! u64_paging_entry {
             pub fn set_execute_disabled(&mut self, value: bool) {
                 self.0.set_bit_assign(63, value);
             }
+
+            pub fn set_protection(&mut self, protection: $crate::paging::size_64::Protection) {
+                self.set_writable(protection.writable);
+                self.set_user_accessible(protection.user_accessible);
+                self.set_execute_disabled(!protection.executable);
+            }
         }
 
         impl core::convert::From<u64> for $name {
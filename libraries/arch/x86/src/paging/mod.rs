@@ -20,3 +20,7 @@ pub const PAGE_2_MIB_SIZE_IN_BYTES: u64 = PAGE_4_KIB_SIZE_IN_BYTES * 512;
 pub const PAGE_4_MIB_SIZE_IN_BYTES: u64 = PAGE_4_KIB_SIZE_IN_BYTES * 1024;
 
 pub const PAGE_1_GIB_SIZE_IN_BYTES: u64 = PAGE_2_MIB_SIZE_IN_BYTES * 512;
+
+pub unsafe fn invalidate_page(virtual_address: u64) {
+    llvm_asm!("invlpg ($0)" :: "r"(virtual_address) : "memory" : "volatile");
+}
@@ -5,6 +5,7 @@
 //**************************************************************************************************
 
 use super::directory::DirectoryTable;
+use super::CacheType;
 use crate::paging::PAGE_1_GIB_SIZE_IN_BYTES;
 use crate::PhysicalAddress52;
 use core::convert::TryFrom;
@@ -85,6 +86,15 @@ impl DirectoryPtrValue {
 u64_paging_entry!(pub struct DirectoryPtrEntry);
 
 impl DirectoryPtrEntry {
+    // Only for entries that map a 1 GiB page. The PAT bit is part of the table address otherwise.
+
+    pub fn set_cache_type(&mut self, cache_type: CacheType) {
+        let (pat, cache_disabled, write_through) = cache_type.pat_bits();
+        self.0.set_bit_assign(12, pat);
+        self.set_cache_disabled(cache_disabled);
+        self.set_write_through(write_through);
+    }
+
     pub fn value(self) -> DirectoryPtrValue {
         if self.0.get_bit(0) {
             if self.0.get_bit(7) {
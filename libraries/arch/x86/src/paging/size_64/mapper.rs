@@ -5,8 +5,8 @@
 //**************************************************************************************************
 
 use crate::paging::size_64::{
-    DirectoryPtrTable, DirectoryPtrValue, DirectoryTable, DirectoryValue, MapType, Pml4Table,
-    Pml4Value, Pml5Table, Pml5Value, RootTable, Table, TableValue,
+    CacheType, DirectoryPtrTable, DirectoryPtrValue, DirectoryTable, DirectoryValue, MapType,
+    MapValue, Pml4Table, Pml4Value, Pml5Table, Pml5Value, Protection, RootTable, Table, TableValue,
 };
 use crate::paging::{PAGE_1_GIB_SIZE_IN_BYTES, PAGE_2_MIB_SIZE_IN_BYTES, PAGE_4_KIB_SIZE_IN_BYTES};
 use crate::{
@@ -75,11 +75,14 @@ impl<'a, TAllocator: MapperInterface> Mapper<'a, TAllocator> {
         }
     }
 
+    // Unmapping returns the mapping that was removed so the caller can free the physical memory
+    // behind it. Tables left empty are not freed.
+
     pub unsafe fn unmap<TVirtualAddress: TryInto<VirtualAddress48> + TryInto<VirtualAddress57>>(
         &mut self,
         root_table: RootTable,
         virtual_address: TVirtualAddress,
-    ) -> Result<(), MapError> {
+    ) -> Result<MapValue, MapError> {
         match root_table {
             RootTable::Pml5(pml5_table_ptr) => {
                 let virtual_address_57: VirtualAddress57 = virtual_address
@@ -96,6 +99,142 @@ impl<'a, TAllocator: MapperInterface> Mapper<'a, TAllocator> {
         }
     }
 
+    // Looks up the mapping of a virtual address without changing it.
+
+    pub unsafe fn translate<
+        TVirtualAddress: TryInto<VirtualAddress48> + TryInto<VirtualAddress57>,
+    >(
+        &self,
+        root_table: RootTable,
+        virtual_address: TVirtualAddress,
+    ) -> Result<MapValue, MapError> {
+        match root_table {
+            RootTable::Pml5(pml5_table_ptr) => {
+                let virtual_address_57: VirtualAddress57 = virtual_address
+                    .try_into()
+                    .map_err(|_| MapError::InvalidVirtualAddress)?;
+
+                let pml_5_table = &mut *pml5_table_ptr;
+
+                match pml_5_table
+                    .index_mut(virtual_address_57.pml_5_index())
+                    .value()
+                {
+                    Pml5Value::None => Ok(MapValue::None),
+                    Pml5Value::Pml4Table(address) => self.translate_with_pml_4(
+                        self.interface.convert_to_virtual_ptr(address),
+                        virtual_address_57,
+                    ),
+                }
+            }
+            RootTable::Pml4(pml4_table_ptr) => {
+                let virtual_address_48: VirtualAddress48 = virtual_address
+                    .try_into()
+                    .map_err(|_| MapError::InvalidVirtualAddress)?;
+                self.translate_with_pml_4(pml4_table_ptr, virtual_address_48)
+            }
+        }
+    }
+
+    // Changes the protection of pages that are already mapped. Every page of the given size in the
+    // range must be mapped. Tables above user accessible pages are made user accessible as well
+    // since the processor checks every level. Stale translations must be invalidated by the
+    // caller.
+
+    pub unsafe fn protect<
+        TVirtualAddress: TryInto<VirtualAddress48> + TryInto<VirtualAddress57>,
+    >(
+        &mut self,
+        root_table: RootTable,
+        virtual_address: TVirtualAddress,
+        map_type: MapType,
+        count: u64,
+        protection: Protection,
+    ) -> Result<(), MapError> {
+        self.update(
+            root_table,
+            virtual_address,
+            map_type,
+            count,
+            LeafUpdate::Protection(protection),
+        )
+    }
+
+    // Changes the memory type of pages that are already mapped. Every page of the given size in
+    // the range must be mapped. Stale translations and cached lines of the old type must be
+    // flushed by the caller.
+
+    pub unsafe fn set_cache_type<
+        TVirtualAddress: TryInto<VirtualAddress48> + TryInto<VirtualAddress57>,
+    >(
+        &mut self,
+        root_table: RootTable,
+        virtual_address: TVirtualAddress,
+        map_type: MapType,
+        count: u64,
+        cache_type: CacheType,
+    ) -> Result<(), MapError> {
+        self.update(
+            root_table,
+            virtual_address,
+            map_type,
+            count,
+            LeafUpdate::CacheType(cache_type),
+        )
+    }
+
+    unsafe fn update<TVirtualAddress: TryInto<VirtualAddress48> + TryInto<VirtualAddress57>>(
+        &mut self,
+        root_table: RootTable,
+        virtual_address: TVirtualAddress,
+        map_type: MapType,
+        count: u64,
+        update: LeafUpdate,
+    ) -> Result<(), MapError> {
+        match root_table {
+            RootTable::Pml5(pml5_table_ptr) => {
+                let virtual_address_57: VirtualAddress57 = virtual_address
+                    .try_into()
+                    .map_err(|_| MapError::InvalidVirtualAddress)?;
+
+                for i in 0..count {
+                    let next_virtual_address = Self::add_pages(virtual_address_57, map_type, i)?;
+
+                    let pml_5_entry =
+                        (&mut *pml5_table_ptr).index_mut(next_virtual_address.pml_5_index());
+
+                    let pml4_table_address = match pml_5_entry.value() {
+                        Pml5Value::None => return Err(MapError::NotMapped),
+                        Pml5Value::Pml4Table(address) => address,
+                    };
+
+                    if update.is_user_accessible() {
+                        pml_5_entry.set_user_accessible(true);
+                    }
+
+                    self.update_with_pml_4(
+                        self.interface.convert_to_virtual_ptr(pml4_table_address),
+                        next_virtual_address,
+                        update,
+                    )?;
+                }
+            }
+            RootTable::Pml4(pml4_table_ptr) => {
+                let virtual_address_48: VirtualAddress48 = virtual_address
+                    .try_into()
+                    .map_err(|_| MapError::InvalidVirtualAddress)?;
+
+                for i in 0..count {
+                    let next_virtual_address = Self::add_pages(virtual_address_48, map_type, i)?;
+
+                    self.update_with_pml_4(pml4_table_ptr, next_virtual_address, update)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     pub unsafe fn map_level_4<
         TVirtualAddress: TryInto<VirtualAddress48>,
         TPhysicalAddress: TryInto<PhysicalAddress52>,
@@ -165,7 +304,7 @@ impl<'a, TAllocator: MapperInterface> Mapper<'a, TAllocator> {
         &mut self,
         pml4_table_ptr: *mut Pml4Table,
         virtual_address: TVirtualAddress,
-    ) -> Result<(), MapError> {
+    ) -> Result<MapValue, MapError> {
         self.map_none_with_pml_4(pml4_table_ptr, virtual_address)
     }
 
@@ -243,12 +382,12 @@ impl<'a, TAllocator: MapperInterface> Mapper<'a, TAllocator> {
         &mut self,
         pml5_table_ptr: *mut Pml5Table,
         virtual_address: VirtualAddress57,
-    ) -> Result<(), MapError> {
+    ) -> Result<MapValue, MapError> {
         let pml_5_table = &mut *pml5_table_ptr;
 
         let pml4_table_address: PhysicalAddress52;
         match pml_5_table.index_mut(virtual_address.pml_5_index()).value() {
-            Pml5Value::None => return Ok(()),
+            Pml5Value::None => return Ok(MapValue::None),
             Pml5Value::Pml4Table(address) => pml4_table_address = address,
         }
 
@@ -296,59 +435,208 @@ impl<'a, TAllocator: MapperInterface> Mapper<'a, TAllocator> {
         Ok(())
     }
 
-    unsafe fn map_none_with_pml_4<TVirtualAddress: VirtualAddress64>(
+    fn add_pages<TVirtualAddress: VirtualAddress64>(
+        virtual_address: TVirtualAddress,
+        map_type: MapType,
+        count: u64,
+    ) -> Result<TVirtualAddress, MapError> {
+        Ok(match map_type {
+            MapType::Page4Kib => virtual_address.add_table_index(count, false)?,
+            MapType::Page2Mib => virtual_address.add_directory_index(count, false)?,
+            MapType::Page1Gib => virtual_address.add_directory_ptr_index(count, false)?,
+        })
+    }
+
+    unsafe fn update_with_pml_4<TVirtualAddress: VirtualAddress64>(
         &mut self,
         pml4_table_ptr: *mut Pml4Table,
         virtual_address: TVirtualAddress,
+        update: LeafUpdate,
     ) -> Result<(), MapError> {
+        let pml_4_entry = (&mut *pml4_table_ptr).index_mut(virtual_address.pml4_index());
+
+        let directory_ptr_table_address = match pml_4_entry.value() {
+            Pml4Value::None => return Err(MapError::NotMapped),
+            Pml4Value::DirectoryPtrTable(address) => address,
+        };
+
+        if update.is_user_accessible() {
+            pml_4_entry.set_user_accessible(true);
+        }
+
+        let directory_ptr_table = &mut *self
+            .interface
+            .convert_to_virtual_ptr::<DirectoryPtrTable>(directory_ptr_table_address);
+        let directory_ptr_entry =
+            directory_ptr_table.index_mut(virtual_address.directory_ptr_index());
+
+        let directory_table_address = match directory_ptr_entry.value() {
+            DirectoryPtrValue::None => return Err(MapError::NotMapped),
+            DirectoryPtrValue::Page1Gib(_) => {
+                match update {
+                    LeafUpdate::Protection(protection) => {
+                        directory_ptr_entry.set_protection(protection)
+                    }
+                    LeafUpdate::CacheType(cache_type) => {
+                        directory_ptr_entry.set_cache_type(cache_type)
+                    }
+                }
+                return Ok(());
+            }
+            DirectoryPtrValue::DirectoryTable(address) => address,
+        };
+
+        if update.is_user_accessible() {
+            directory_ptr_entry.set_user_accessible(true);
+        }
+
+        let directory_table = &mut *self
+            .interface
+            .convert_to_virtual_ptr::<DirectoryTable>(directory_table_address);
+        let directory_entry = directory_table.index_mut(virtual_address.directory_index());
+
+        let table_address = match directory_entry.value() {
+            DirectoryValue::None => return Err(MapError::NotMapped),
+            DirectoryValue::Page2Mib(_) => {
+                match update {
+                    LeafUpdate::Protection(protection) => {
+                        directory_entry.set_protection(protection)
+                    }
+                    LeafUpdate::CacheType(cache_type) => directory_entry.set_cache_type(cache_type),
+                }
+                return Ok(());
+            }
+            DirectoryValue::Table(address) => address,
+        };
+
+        if update.is_user_accessible() {
+            directory_entry.set_user_accessible(true);
+        }
+
+        let table = &mut *self
+            .interface
+            .convert_to_virtual_ptr::<Table>(table_address);
+        let table_entry = table.index_mut(virtual_address.table_index());
+
+        match table_entry.value() {
+            TableValue::None => Err(MapError::NotMapped),
+            TableValue::Page4Kib(_) => {
+                match update {
+                    LeafUpdate::Protection(protection) => table_entry.set_protection(protection),
+                    LeafUpdate::CacheType(cache_type) => table_entry.set_cache_type(cache_type),
+                }
+                Ok(())
+            }
+        }
+    }
+
+    unsafe fn translate_with_pml_4<TVirtualAddress: VirtualAddress64>(
+        &self,
+        pml4_table_ptr: *mut Pml4Table,
+        virtual_address: TVirtualAddress,
+    ) -> Result<MapValue, MapError> {
+        let pml_4_table = &mut *pml4_table_ptr;
+
+        let directory_ptr_table_address =
+            match pml_4_table.index_mut(virtual_address.pml4_index()).value() {
+                Pml4Value::None => return Ok(MapValue::None),
+                Pml4Value::DirectoryPtrTable(address) => address,
+            };
+
+        let directory_ptr_table = &mut *self
+            .interface
+            .convert_to_virtual_ptr::<DirectoryPtrTable>(directory_ptr_table_address);
+
+        let directory_table_address = match directory_ptr_table
+            .index_mut(virtual_address.directory_ptr_index())
+            .value()
+        {
+            DirectoryPtrValue::None => return Ok(MapValue::None),
+            DirectoryPtrValue::Page1Gib(address) => return Ok(MapValue::Page1Gib(address)),
+            DirectoryPtrValue::DirectoryTable(address) => address,
+        };
+
+        let directory_table = &mut *self
+            .interface
+            .convert_to_virtual_ptr::<DirectoryTable>(directory_table_address);
+
+        let table_address = match directory_table
+            .index_mut(virtual_address.directory_index())
+            .value()
+        {
+            DirectoryValue::None => return Ok(MapValue::None),
+            DirectoryValue::Page2Mib(address) => return Ok(MapValue::Page2Mib(address)),
+            DirectoryValue::Table(address) => address,
+        };
+
+        let table = &mut *self
+            .interface
+            .convert_to_virtual_ptr::<Table>(table_address);
+
+        match table.index_mut(virtual_address.table_index()).value() {
+            TableValue::None => Ok(MapValue::None),
+            TableValue::Page4Kib(address) => Ok(MapValue::Page4Kib(address)),
+        }
+    }
+
+    unsafe fn map_none_with_pml_4<TVirtualAddress: VirtualAddress64>(
+        &mut self,
+        pml4_table_ptr: *mut Pml4Table,
+        virtual_address: TVirtualAddress,
+    ) -> Result<MapValue, MapError> {
         let pml_4_table = &mut *pml4_table_ptr;
 
         let directory_ptr_table_address: PhysicalAddress52;
         match pml_4_table.index_mut(virtual_address.pml4_index()).value() {
-            Pml4Value::None => return Ok(()),
+            Pml4Value::None => return Ok(MapValue::None),
             Pml4Value::DirectoryPtrTable(address) => directory_ptr_table_address = address,
         }
 
-        let directory_ptr_table =
-            &mut *directory_ptr_table_address.as_mut_ptr::<DirectoryPtrTable>();
+        let directory_ptr_table = &mut *self
+            .interface
+            .convert_to_virtual_ptr::<DirectoryPtrTable>(directory_ptr_table_address);
         let directory_ptr_table_entry =
             directory_ptr_table.index_mut(virtual_address.directory_ptr_index());
 
         let directory_table_address: PhysicalAddress52;
         match directory_ptr_table_entry.value() {
-            DirectoryPtrValue::None => return Ok(()),
-            DirectoryPtrValue::Page1Gib(_) => {
+            DirectoryPtrValue::None => return Ok(MapValue::None),
+            DirectoryPtrValue::Page1Gib(address) => {
                 directory_ptr_table_entry
                     .set_value(DirectoryPtrValue::None)
                     .unwrap();
-                return Ok(());
+                return Ok(MapValue::Page1Gib(address));
             }
             DirectoryPtrValue::DirectoryTable(address) => directory_table_address = address,
         }
 
-        let directory_table = &mut *directory_table_address.as_mut_ptr::<DirectoryTable>();
+        let directory_table = &mut *self
+            .interface
+            .convert_to_virtual_ptr::<DirectoryTable>(directory_table_address);
         let directory_table_entry = directory_table.index_mut(virtual_address.directory_index());
 
         let table_address: PhysicalAddress52;
         match directory_table_entry.value() {
-            DirectoryValue::None => return Ok(()),
-            DirectoryValue::Page2Mib(_) => {
+            DirectoryValue::None => return Ok(MapValue::None),
+            DirectoryValue::Page2Mib(address) => {
                 directory_table_entry
                     .set_value(DirectoryValue::None)
                     .unwrap();
-                return Ok(());
+                return Ok(MapValue::Page2Mib(address));
             }
             DirectoryValue::Table(address) => table_address = address,
         }
 
-        let table = &mut *table_address.as_mut_ptr::<Table>();
+        let table = &mut *self
+            .interface
+            .convert_to_virtual_ptr::<Table>(table_address);
         let table_entry = table.index_mut(virtual_address.table_index());
 
         return match table_entry.value() {
-            TableValue::None => Ok(()),
-            TableValue::Page4Kib(_) => {
+            TableValue::None => Ok(MapValue::None),
+            TableValue::Page4Kib(address) => {
                 table_entry.set_value(TableValue::None).unwrap();
-                Ok(())
+                Ok(MapValue::Page4Kib(address))
             }
         };
     }
@@ -371,6 +659,7 @@ impl<'a, TAllocator: MapperInterface> Mapper<'a, TAllocator> {
         entry
             .set_value(TableValue::Page4Kib(physical_address))
             .unwrap();
+        entry.set_protection(Protection::KERNEL);
 
         Ok(())
     }
@@ -396,6 +685,7 @@ impl<'a, TAllocator: MapperInterface> Mapper<'a, TAllocator> {
         entry
             .set_value(DirectoryValue::Page2Mib(physical_address))
             .unwrap();
+        entry.set_protection(Protection::KERNEL);
 
         Ok(())
     }
@@ -419,6 +709,7 @@ impl<'a, TAllocator: MapperInterface> Mapper<'a, TAllocator> {
         entry
             .set_value(DirectoryPtrValue::Page1Gib(physical_address))
             .unwrap();
+        entry.set_protection(Protection::KERNEL);
 
         Ok(())
     }
@@ -533,6 +824,25 @@ impl<'a, TAllocator: MapperInterface> Mapper<'a, TAllocator> {
     }
 }
 
+// A change made to the entry that maps a page by Mapper::protect or Mapper::set_cache_type.
+
+#[derive(Copy, Clone, Debug)]
+enum LeafUpdate {
+    Protection(Protection),
+    CacheType(CacheType),
+}
+
+impl LeafUpdate {
+    // Tables above user accessible pages have to be user accessible as well.
+
+    fn is_user_accessible(self) -> bool {
+        match self {
+            LeafUpdate::Protection(protection) => protection.user_accessible,
+            LeafUpdate::CacheType(_) => false,
+        }
+    }
+}
+
 pub trait MapperInterface {
     unsafe fn alloc_table(&mut self) -> PhysicalAddress52;
     unsafe fn dealloc_table(&mut self, address: PhysicalAddress52);
@@ -549,6 +859,8 @@ pub enum MapError {
     NullTable,
     InvalidVirtualAddress,
     InvalidPhysicalAddress,
+    NotMapped,
+    AlreadyMapped,
 }
 
 impl From<VirtualAddress64Error> for MapError {
@@ -581,6 +893,10 @@ impl fmt::Display for MapError {
             MapError::InvalidPhysicalAddress => {
                 write!(f, "The physical address specified is invalid.")
             }
+            MapError::NotMapped => write!(f, "The virtual address specified is not mapped."),
+            MapError::AlreadyMapped => {
+                write!(f, "The virtual address specified is already mapped.")
+            }
         }
     }
 }
@@ -45,6 +45,58 @@ impl MapType {
     }
 }
 
+// Access rights of a mapped page. Pages are mapped with kernel protection and can be changed
+// afterwards with Mapper::protect. Pages that are not executable may only be used after
+// IA32_EFER.NXE is set since the execute disable bit is reserved otherwise.
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Protection {
+    pub writable: bool,
+    pub executable: bool,
+    pub user_accessible: bool,
+}
+
+impl Protection {
+    pub const KERNEL: Self = Self {
+        writable: true,
+        executable: true,
+        user_accessible: false,
+    };
+
+    pub const fn union(self, other: Self) -> Self {
+        Self {
+            writable: self.writable || other.writable,
+            executable: self.executable || other.executable,
+            user_accessible: self.user_accessible || other.user_accessible,
+        }
+    }
+}
+
+// Memory type of a mapped page. It selects an entry of IA32_PAT with the PAT, PCD and PWT bits of
+// the page's entry. Write combining needs IA32_PAT set to msr::ia32_pat::VALUE, the others use
+// entries that keep their power on values.
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CacheType {
+    WriteBack,
+    WriteThrough,
+    WriteCombining,
+    Uncached,
+}
+
+impl CacheType {
+    // The PAT, PCD and PWT bits in that order.
+
+    pub fn pat_bits(self) -> (bool, bool, bool) {
+        match self {
+            CacheType::WriteBack => (false, false, false),
+            CacheType::WriteThrough => (false, false, true),
+            CacheType::WriteCombining => (true, false, false),
+            CacheType::Uncached => (false, true, true),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum MapValue {
     None,
@@ -4,6 +4,7 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+use super::CacheType;
 use crate::PhysicalAddress52;
 use core::convert::TryFrom;
 use core::ops::{Index, IndexMut};
@@ -70,6 +71,13 @@ impl TableValue {
 u64_paging_entry!(pub struct TableEntry);
 
 impl TableEntry {
+    pub fn set_cache_type(&mut self, cache_type: CacheType) {
+        let (pat, cache_disabled, write_through) = cache_type.pat_bits();
+        self.0.set_bit_assign(7, pat);
+        self.set_cache_disabled(cache_disabled);
+        self.set_write_through(write_through);
+    }
+
     pub fn value(self) -> TableValue {
         if self.0.get_bit(0) {
             let address = self.0.get_bits(12, 12, 40);
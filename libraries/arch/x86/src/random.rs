@@ -0,0 +1,36 @@
+//**************************************************************************************************
+// random.rs                                                                                       *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::cpuid;
+
+// RDRAND fails when its entropy is drained for a moment. Intel recommends retrying 10 times before
+// giving up.
+
+const RDRAND_RETRIES: usize = 10;
+
+pub unsafe fn is_rdrand_supported() -> bool {
+    let (_, _, features) = cpuid::leaf_1::read();
+    features.rdrand()
+}
+
+// Returns None if the instruction kept failing. Support must be checked before calling this.
+
+pub unsafe fn rdrand_u64() -> Option<u64> {
+    for _ in 0..RDRAND_RETRIES {
+        let value: u64;
+        let success: u8;
+
+        llvm_asm!("rdrand $0
+            setc $1"
+            : "=r"(value), "=r"(success) ::: "volatile");
+
+        if success != 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
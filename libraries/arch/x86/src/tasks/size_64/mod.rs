@@ -6,6 +6,8 @@
 
 pub mod tss_ldt;
 
+use crate::interrupts::size_64::interrupt_trap_gate::IstIndex;
+
 #[repr(C, packed)]
 #[derive(Default)]
 pub struct Tss {
@@ -46,4 +48,38 @@ impl Tss {
             io_map_base_address: 0,
         }
     }
+
+    pub fn rsp_0(&self) -> u64 {
+        self.rsp_0
+    }
+
+    // The stack loaded when an interrupt or exception arrives while running in ring 3.
+
+    pub fn set_rsp_0(&mut self, stack_top: u64) {
+        self.rsp_0 = stack_top;
+    }
+
+    pub fn ist(&self, index: IstIndex) -> u64 {
+        match index {
+            IstIndex::One => self.ist_1,
+            IstIndex::Two => self.ist_2,
+            IstIndex::Three => self.ist_3,
+            IstIndex::Four => self.ist_4,
+            IstIndex::Five => self.ist_5,
+            IstIndex::Six => self.ist_6,
+            IstIndex::Seven => self.ist_7,
+        }
+    }
+
+    pub fn set_ist(&mut self, index: IstIndex, stack_top: u64) {
+        match index {
+            IstIndex::One => self.ist_1 = stack_top,
+            IstIndex::Two => self.ist_2 = stack_top,
+            IstIndex::Three => self.ist_3 = stack_top,
+            IstIndex::Four => self.ist_4 = stack_top,
+            IstIndex::Five => self.ist_5 = stack_top,
+            IstIndex::Six => self.ist_6 = stack_top,
+            IstIndex::Seven => self.ist_7 = stack_top,
+        }
+    }
 }
@@ -31,48 +31,51 @@ impl Descriptor {
     }
 
     pub fn is_present(self) -> bool {
-        self.upper.get_bit(15)
+        self.middle.get_bit(15)
     }
 
     pub fn set_is_present(&mut self, value: bool) {
-        self.upper.set_bit_assign(15, value);
+        self.middle.set_bit_assign(15, value);
     }
 
     pub fn avl_enabled(self) -> bool {
-        self.upper.get_bit(20)
+        self.middle.get_bit(20)
     }
 
     pub fn set_avl_enabled(&mut self, value: bool) {
-        self.upper.set_bit_assign(20, value);
+        self.middle.set_bit_assign(20, value);
     }
 
     pub fn granularity_enabled(self) -> bool {
-        self.upper.get_bit(23)
+        self.middle.get_bit(23)
     }
 
     pub fn set_granularity_enabled(&mut self, value: bool) {
-        self.upper.set_bit_assign(23, value);
+        self.middle.set_bit_assign(23, value);
     }
 
     pub fn base_address(self) -> u64 {
-        ((self.lower as u64) & 0xFFFF)
-            | ((self.middle as u64) & !0xFFFF)
+        ((self.lower as u64) >> 16)
+            | (((self.middle as u64) & 0xFF) << 16)
+            | ((self.middle as u64) & 0xFF00_0000)
             | ((self.upper as u64) << 32)
     }
 
-    pub fn set_base_address(&mut self, offset: u64) {
-        self.lower = (self.lower & !0xFFFF) | ((offset & 0xFFFF) as u32);
-        self.middle = (self.middle & 0xFFFF) | ((offset & !0xFFFF) as u32);
-        self.upper = (offset >> 32) as u32;
+    pub fn set_base_address(&mut self, address: u64) {
+        self.lower = (self.lower & 0xFFFF) | (((address & 0xFFFF) as u32) << 16);
+        self.middle = (self.middle & 0x00FF_FF00)
+            | (((address >> 16) & 0xFF) as u32)
+            | ((address & 0xFF00_0000) as u32);
+        self.upper = (address >> 32) as u32;
     }
 
     pub const fn limit(self) -> u32 {
-        (self.lower & 0xFFFF) | ((self.upper & 0xF0000) << 16)
+        (self.lower & 0xFFFF) | (self.middle & 0xF_0000)
     }
 
     pub fn set_limit(&mut self, value: u32) {
         self.lower = (self.lower & !0xFFFF) | (value & 0xFFFF);
-        self.upper = (self.upper & !0xF_0000) | (value & 0xF_0000);
+        self.middle = (self.middle & !0xF_0000) | (value & 0xF_0000);
     }
 
     pub fn privilege_level(self) -> ProtectionRing {
@@ -92,6 +95,26 @@ impl Descriptor {
     }
 }
 
+impl From<u128> for Descriptor {
+    fn from(value: u128) -> Self {
+        Descriptor {
+            lower: value as u32,
+            middle: (value >> 32) as u32,
+            upper: (value >> 64) as u32,
+            reserved: (value >> 96) as u32,
+        }
+    }
+}
+
+impl From<Descriptor> for u128 {
+    fn from(value: Descriptor) -> Self {
+        (value.lower as u128)
+            | ((value.middle as u128) << 32)
+            | ((value.upper as u128) << 64)
+            | ((value.reserved as u128) << 96)
+    }
+}
+
 numeric_enum!(
     #[derive(Copy, Clone, PartialEq, Eq)]
     pub enum DescriptorType {
@@ -0,0 +1,111 @@
+//**************************************************************************************************
+// entry.rs                                                                                        *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::{Error, Header};
+
+const FILE_TYPE_MASK: u32 = 0o170000;
+
+const PERMISSIONS_MASK: u32 = 0o7777;
+
+// Hard links share one copy of the data which is stored with the last entry of the link. The
+// other entries have no data.
+
+#[derive(Copy, Clone, Debug)]
+pub struct Entry<'a> {
+    header: Header,
+    name: &'a str,
+    data: &'a [u8],
+}
+
+impl<'a> Entry<'a> {
+    pub(crate) fn new(header: Header, name: &'a str, data: &'a [u8]) -> Self {
+        Self { header, name, data }
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn file_type(&self) -> FileType {
+        FileType::from_mode(self.header.mode)
+    }
+
+    pub fn permissions(&self) -> u32 {
+        self.header.mode & PERMISSIONS_MASK
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.file_type() == FileType::Regular
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.file_type() == FileType::Directory
+    }
+
+    // The target of a symbolic link is stored as its data.
+
+    pub fn link_target(&self) -> Option<&'a str> {
+        if self.file_type() != FileType::SymbolicLink {
+            return None;
+        }
+
+        core::str::from_utf8(self.data).ok()
+    }
+
+    // Only archives with the checksum magic have a checksum. Others always pass.
+
+    pub fn verify_checksum(&self) -> Result<(), Error> {
+        if !self.header.has_checksum {
+            return Ok(());
+        }
+
+        let sum = self
+            .data
+            .iter()
+            .fold(0u32, |sum, &byte| sum.wrapping_add(byte as u32));
+
+        if sum == self.header.check {
+            Ok(())
+        } else {
+            Err(Error::ChecksumMismatch)
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum FileType {
+    Regular,
+    Directory,
+    SymbolicLink,
+    CharacterDevice,
+    BlockDevice,
+    Fifo,
+    Socket,
+    Unknown,
+}
+
+impl FileType {
+    pub fn from_mode(mode: u32) -> Self {
+        match mode & FILE_TYPE_MASK {
+            0o100000 => FileType::Regular,
+            0o040000 => FileType::Directory,
+            0o120000 => FileType::SymbolicLink,
+            0o020000 => FileType::CharacterDevice,
+            0o060000 => FileType::BlockDevice,
+            0o010000 => FileType::Fifo,
+            0o140000 => FileType::Socket,
+            _ => FileType::Unknown,
+        }
+    }
+}
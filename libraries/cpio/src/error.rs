@@ -0,0 +1,37 @@
+//**************************************************************************************************
+// error.rs                                                                                        *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use core::fmt;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Error {
+    Truncated,
+    InvalidMagic,
+    InvalidField,
+    InvalidName,
+    MissingTrailer,
+    ChecksumMismatch,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Truncated => write!(f, "The archive ends in the middle of an entry."),
+            Error::InvalidMagic => {
+                write!(f, "An entry does not start with a newc cpio magic number.")
+            }
+            Error::InvalidField => write!(f, "A header field is not made of hexadecimal digits."),
+            Error::InvalidName => write!(
+                f,
+                "An entry name is empty, not null terminated, or not UTF-8."
+            ),
+            Error::MissingTrailer => write!(f, "The archive ends without a trailer entry."),
+            Error::ChecksumMismatch => {
+                write!(f, "The data of an entry does not match its checksum.")
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//**************************************************************************************************
+// header.rs                                                                                       *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::Error;
+
+pub const HEADER_SIZE: usize = 110;
+
+pub const MAGIC: &[u8; 6] = b"070701";
+
+// Same as the normal format except the check field holds a sum of the data bytes.
+
+pub const CHECKSUM_MAGIC: &[u8; 6] = b"070702";
+
+const FIELD_LEN: usize = 8;
+
+// Every field is 8 hexadecimal digits following the 6 byte magic.
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Header {
+    pub has_checksum: bool,
+    pub inode: u32,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub link_count: u32,
+    pub modification_time: u32,
+    pub file_size: u32,
+    pub device_major: u32,
+    pub device_minor: u32,
+    pub represented_device_major: u32,
+    pub represented_device_minor: u32,
+    pub name_size: u32,
+    pub check: u32,
+}
+
+impl Header {
+    pub fn read(source: &[u8]) -> Result<Self, Error> {
+        let source = source.get(..HEADER_SIZE).ok_or(Error::Truncated)?;
+
+        let has_checksum = match &source[..MAGIC.len()] {
+            magic if magic == MAGIC => false,
+            magic if magic == CHECKSUM_MAGIC => true,
+            _ => return Err(Error::InvalidMagic),
+        };
+
+        let field = |index: usize| {
+            let start = MAGIC.len() + index * FIELD_LEN;
+            parse_hex(&source[start..start + FIELD_LEN]).ok_or(Error::InvalidField)
+        };
+
+        Ok(Self {
+            has_checksum,
+            inode: field(0)?,
+            mode: field(1)?,
+            uid: field(2)?,
+            gid: field(3)?,
+            link_count: field(4)?,
+            modification_time: field(5)?,
+            file_size: field(6)?,
+            device_major: field(7)?,
+            device_minor: field(8)?,
+            represented_device_major: field(9)?,
+            represented_device_minor: field(10)?,
+            name_size: field(11)?,
+            check: field(12)?,
+        })
+    }
+}
+
+// Accepts upper and lower case digits. Signs and whitespace are rejected unlike from_str_radix.
+
+fn parse_hex(digits: &[u8]) -> Option<u32> {
+    digits.iter().try_fold(0u32, |value, &digit| {
+        let digit = (digit as char).to_digit(16)?;
+        Some((value << 4) | digit)
+    })
+}
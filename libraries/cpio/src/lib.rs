@@ -0,0 +1,184 @@
+//**************************************************************************************************
+// lib.rs                                                                                          *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+#![no_std]
+
+mod entry;
+mod error;
+mod header;
+
+pub use entry::*;
+pub use error::*;
+pub use header::*;
+
+use core::str;
+
+// Reader for cpio archives in the "new ASCII" (newc) format produced by "cpio -H newc". Entries
+// borrow their names and data from the archive so nothing is copied. Every header is checked
+// before it is used so truncated or malicious archives produce errors instead of panics.
+
+#[derive(Copy, Clone, Debug)]
+pub struct Archive<'a>(&'a [u8]);
+
+impl<'a> Archive<'a> {
+    pub const fn new(source: &'a [u8]) -> Self {
+        Archive(source)
+    }
+
+    pub fn source(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn entries(&self) -> Entries<'a> {
+        Entries {
+            source: self.0,
+            offset: 0,
+            finished: false,
+        }
+    }
+
+    // Finds the first entry with the path. Leading "/" and "./" are ignored on both sides.
+
+    pub fn find(&self, path: &str) -> Result<Option<Entry<'a>>, Error> {
+        let path = normalize_path(path);
+
+        for entry in self.entries() {
+            let entry = entry?;
+
+            if normalize_path(entry.name()) == path {
+                return Ok(Some(entry));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Checks every entry up to the trailer and returns the number of entries.
+
+    pub fn validate(&self) -> Result<usize, Error> {
+        let mut count = 0;
+
+        for entry in self.entries() {
+            entry?.verify_checksum()?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+// Iterates over the entries until the trailer. The iterator ends after the first error.
+
+#[derive(Clone, Debug)]
+pub struct Entries<'a> {
+    source: &'a [u8],
+    offset: usize,
+    finished: bool,
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = Result<Entry<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.read_entry() {
+            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(error) => {
+                self.finished = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+impl<'a> Entries<'a> {
+    fn read_entry(&mut self) -> Result<Option<Entry<'a>>, Error> {
+        let remaining = self
+            .source
+            .get(self.offset..)
+            .filter(|remaining| !remaining.is_empty())
+            .ok_or(Error::MissingTrailer)?;
+
+        let header = Header::read(remaining)?;
+
+        // The name includes its null terminator and is padded so the data starts 4 byte aligned.
+
+        let name_size = header.name_size as usize;
+
+        if name_size == 0 {
+            return Err(Error::InvalidName);
+        }
+
+        let name_start = self
+            .offset
+            .checked_add(HEADER_SIZE)
+            .ok_or(Error::Truncated)?;
+        let name_end = name_start.checked_add(name_size).ok_or(Error::Truncated)?;
+
+        let name_bytes = self
+            .source
+            .get(name_start..name_end)
+            .ok_or(Error::Truncated)?;
+
+        let name = match name_bytes.split_last() {
+            Some((0, name)) if !name.contains(&0) => {
+                str::from_utf8(name).map_err(|_| Error::InvalidName)?
+            }
+            _ => return Err(Error::InvalidName),
+        };
+
+        let data_start = align_up(name_end).ok_or(Error::Truncated)?;
+        let data_end = data_start
+            .checked_add(header.file_size as usize)
+            .ok_or(Error::Truncated)?;
+
+        let data = self
+            .source
+            .get(data_start..data_end)
+            .ok_or(Error::Truncated)?;
+
+        if name == TRAILER_NAME {
+            return Ok(None);
+        }
+
+        // The padding after the last entry's data may be missing at the end of the archive.
+
+        self.offset = align_up(data_end)
+            .ok_or(Error::Truncated)?
+            .min(self.source.len());
+
+        Ok(Some(Entry::new(header, name, data)))
+    }
+}
+
+// The name of the entry that marks the end of the archive.
+
+pub const TRAILER_NAME: &str = "TRAILER!!!";
+
+fn align_up(offset: usize) -> Option<usize> {
+    offset.checked_add(3).map(|offset| offset & !3)
+}
+
+fn normalize_path(path: &str) -> &str {
+    let mut path = path;
+
+    loop {
+        if let Some(rest) = path.strip_prefix("./") {
+            path = rest;
+        } else if let Some(rest) = path.strip_prefix('/') {
+            path = rest;
+        } else {
+            return path;
+        }
+    }
+}
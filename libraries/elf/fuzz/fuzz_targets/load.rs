@@ -0,0 +1,51 @@
+//**************************************************************************************************
+// load.rs                                                                                         *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+#![no_main]
+
+use elf::File;
+use libfuzzer_sys::fuzz_target;
+
+// Files that load more than this are skipped so the fuzzer does not run out of memory.
+
+const MAX_MEMORY_SIZE: usize = 16 * 1024 * 1024;
+
+// Run with "cargo fuzz run load" from the elf directory. Every function must return an error
+// instead of panicking for any input.
+
+fuzz_target!(|data: &[u8]| {
+    let file = File::new(data);
+
+    let _ = file.read_header();
+    let _ = file.find_build_id();
+    let _ = file.find_section_header_by_name(".symtab");
+
+    let is_valid = file.validate().is_ok();
+
+    let memory_segment = match file.load_memory_segment() {
+        Ok(memory_segment) => memory_segment,
+        Err(_) => return,
+    };
+
+    if memory_segment.len() > MAX_MEMORY_SIZE {
+        return;
+    }
+
+    // A destination that is too small must be rejected before anything is written past it.
+
+    if let Some(len) = memory_segment.len().checked_sub(1) {
+        let mut memory = vec![0; len];
+        assert!(file.load_to(&mut memory).is_err());
+    }
+
+    let mut memory = vec![0; memory_segment.len()];
+
+    assert_eq!(file.load_to(&mut memory).is_ok(), is_valid);
+
+    if is_valid {
+        let _ = file.relocate(&mut memory, 0x20_0000);
+    }
+});
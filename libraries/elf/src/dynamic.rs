@@ -0,0 +1,101 @@
+//**************************************************************************************************
+// dynamic.rs                                                                                      *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use super::error::Error;
+use super::identity::{Class, Data};
+use super::table::{Entry, Table};
+use core::convert::TryFrom;
+use io::cursor::Cursor;
+use io::{Endian, EndianRead};
+
+c_enum!(
+    pub enum DynamicTag : i64 {
+        NULL = 0,
+        NEEDED = 1,
+        PLT_RELOCATION_SIZE = 2,
+        PLT_GOT = 3,
+        HASH = 4,
+        STRING_TABLE = 5,
+        SYMBOL_TABLE = 6,
+        RELA = 7,
+        RELA_SIZE = 8,
+        RELA_ENTRY_SIZE = 9,
+        STRING_TABLE_SIZE = 10,
+        SYMBOL_ENTRY_SIZE = 11,
+        INIT = 12,
+        FINI = 13,
+        SONAME = 14,
+        RPATH = 15,
+        SYMBOLIC = 16,
+        REL = 17,
+        REL_SIZE = 18,
+        REL_ENTRY_SIZE = 19,
+        PLT_RELOCATION = 20,
+        DEBUG = 21,
+        TEXT_RELOCATION = 22,
+        JUMP_RELOCATION = 23,
+        BIND_NOW = 24,
+        FLAGS = 30,
+        RELA_COUNT = 0x6FFFFFF9,
+        REL_COUNT = 0x6FFFFFFA,
+        FLAGS_1 = 0x6FFFFFFB,
+    }
+);
+
+// An entry of the dynamic section. The value is either an integer or an address depending on the
+// tag.
+
+#[derive(Clone, Debug)]
+pub struct Dynamic {
+    pub tag: DynamicTag,
+    pub value: u64,
+}
+
+impl Entry for Dynamic {
+    fn read(source: &[u8], class: Class, data: Data) -> Result<Self, Error> {
+        let endian = Endian::try_from(data)?;
+        let mut cursor = Cursor::new(source);
+
+        match class {
+            Class::SIXTY_FOUR => Ok(Dynamic {
+                tag: DynamicTag::from(cursor.read_u64(endian)? as i64),
+                value: cursor.read_u64(endian)?,
+            }),
+            Class::THIRTY_TWO => Ok(Dynamic {
+                tag: DynamicTag::from(cursor.read_u32(endian)? as i32 as i64),
+                value: cursor.read_u32(endian)? as u64,
+            }),
+            _ => Err(Error::UnknownClass),
+        }
+    }
+
+    fn entry_size(class: Class) -> Result<usize, Error> {
+        match class {
+            Class::SIXTY_FOUR => Ok(16),
+            Class::THIRTY_TWO => Ok(8),
+            _ => Err(Error::UnknownClass),
+        }
+    }
+}
+
+// The dynamic section ends with a NULL entry. Anything after it is padding.
+
+pub type DynamicTable<'a> = Table<'a, Dynamic>;
+
+impl<'a> DynamicTable<'a> {
+    pub fn entries(&self) -> impl Iterator<Item = Dynamic> + 'a {
+        self.iter()
+            .take_while(|entry| entry.tag != DynamicTag::NULL)
+    }
+
+    // Returns the value of the first entry with the tag.
+
+    pub fn find(&self, tag: DynamicTag) -> Option<u64> {
+        self.entries()
+            .find(|entry| entry.tag == tag)
+            .map(|entry| entry.value)
+    }
+}
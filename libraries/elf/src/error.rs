@@ -15,6 +15,20 @@ pub enum Error {
     DestinationTooSmall,
     NoLoadProgramSegments,
     InvalidProgramSegmentSize,
+    InvalidDynamicSection,
+    UnsupportedRelocation,
+    InvalidEntrySize,
+    InvalidNote,
+    InvalidSectionType,
+    InvalidStringTable,
+    InvalidMagic,
+    UnsupportedVersion,
+    InvalidProgramHeaderTable,
+    SegmentOutsideOfFile,
+    SegmentAddressOverflow,
+    InvalidSegmentAlignment,
+    OverlappingSegments,
+    InvalidEntry,
 }
 
 impl fmt::Display for Error {
@@ -44,6 +58,49 @@ impl fmt::Display for Error {
                 f,
                 "A program segment has a file size larger than its memory size."
             ),
+            Error::InvalidDynamicSection => {
+                write!(f, "The dynamic section or a table it refers to is invalid.")
+            }
+            Error::UnsupportedRelocation => write!(
+                f,
+                "The ELF file contains a relocation that is not supported."
+            ),
+            Error::InvalidEntrySize => write!(
+                f,
+                "A table has entries smaller than the structure they contain."
+            ),
+            Error::InvalidNote => write!(f, "A note does not fit in its section."),
+            Error::InvalidSectionType => {
+                write!(f, "A section does not have the type needed to read it.")
+            }
+            Error::InvalidStringTable => {
+                write!(f, "A string table is missing or does not contain a name.")
+            }
+            Error::InvalidMagic => write!(f, "The source does not start with the ELF magic."),
+            Error::UnsupportedVersion => write!(f, "The ELF version is not supported."),
+            Error::InvalidProgramHeaderTable => write!(
+                f,
+                "The program header table is outside of the file or its entries are too small."
+            ),
+            Error::SegmentOutsideOfFile => {
+                write!(f, "A program segment is outside of the file.")
+            }
+            Error::SegmentAddressOverflow => write!(
+                f,
+                "A program segment ends past the highest address that can be loaded to."
+            ),
+            Error::InvalidSegmentAlignment => write!(
+                f,
+                "A program segment has an alignment that is invalid or does not match its offset."
+            ),
+            Error::OverlappingSegments => write!(
+                f,
+                "Loadable program segments overlap or are not sorted by address."
+            ),
+            Error::InvalidEntry => write!(
+                f,
+                "The entry point is not inside of an executable program segment."
+            ),
         }
     }
 }
@@ -9,20 +9,32 @@
 #[macro_use]
 extern crate enums;
 
+mod dynamic;
 mod error;
 mod header;
 mod identity;
+mod note;
 mod program;
+mod relocation;
 mod section;
+mod symbol;
+mod table;
 
+pub use dynamic::*;
 pub use error::*;
 pub use header::*;
 pub use identity::*;
+pub use note::*;
 pub use program::*;
+pub use relocation::*;
 pub use section::*;
+pub use symbol::*;
+pub use table::*;
 
 use core::cmp;
+use core::convert::TryFrom;
 use core::mem;
+use io::Endian;
 use memory;
 
 #[derive(Copy, Clone, Debug)]
@@ -51,61 +63,289 @@ impl<'a> File<'a> {
     pub fn read_program_header(&self, entry: u16) -> Result<ProgramHeader, Error> {
         let header = self.read_header()?;
 
-        if entry > header.program_header_entry_count {
+        if entry >= header.program_header_entry_count {
             return Err(Error::SourceTooSmall);
         }
 
         let identity_header = self.read_identity_header()?;
 
-        let entry_memory_offset = (entry * header.program_header_entry_size) as u64;
-        let source_start = (header.program_header_table_offset + entry_memory_offset) as usize;
+        let entry_memory_offset = entry as u64 * header.program_header_entry_size as u64;
+        let source_start = header
+            .program_header_table_offset
+            .checked_add(entry_memory_offset)
+            .ok_or(Error::SourceTooSmall)? as usize;
         let source = &self.0.get(source_start..).ok_or(Error::SourceTooSmall)?;
 
         ProgramHeader::read(source, identity_header.class, identity_header.data)
     }
 
+    pub fn find_program_header(
+        &self,
+        segment_type: ProgramSegmentType,
+    ) -> Result<Option<ProgramHeader>, Error> {
+        let header = self.read_header()?;
+
+        for entry in 0..header.program_header_entry_count {
+            let program_header = self.read_program_header(entry)?;
+
+            if program_header.segment_type == segment_type {
+                return Ok(Some(program_header));
+            }
+        }
+
+        Ok(None)
+    }
+
     pub fn read_section_header(&self, entry: u16) -> Result<SectionHeader, Error> {
         let header = self.read_header()?;
 
-        if entry > header.section_header_entry_count {
+        if entry >= header.section_header_entry_count {
             return Err(Error::SourceTooSmall);
         }
 
         let identity_header = self.read_identity_header()?;
 
-        let entry_memory_offset = (entry * header.section_header_entry_size) as u64;
-        let source_start = (header.section_header_table_offset + entry_memory_offset) as usize;
+        let entry_memory_offset = entry as u64 * header.section_header_entry_size as u64;
+        let source_start = header
+            .section_header_table_offset
+            .checked_add(entry_memory_offset)
+            .ok_or(Error::SourceTooSmall)? as usize;
         let source = &self.0.get(source_start..).ok_or(Error::SourceTooSmall)?;
 
         SectionHeader::read(source, identity_header.class, identity_header.data)
     }
 
-    pub fn load_memory_segment(&self) -> Result<memory::Segment, Error> {
-        let mut start_address = usize::MAX;
-        let mut end_address = usize::MIN;
-        let mut available_load_segment = false;
+    pub fn find_section_header(
+        &self,
+        segment_type: SectionSegmentType,
+    ) -> Result<Option<SectionHeader>, Error> {
+        let header = self.read_header()?;
+
+        for entry in 0..header.section_header_entry_count {
+            let section_header = self.read_section_header(entry)?;
+
+            if section_header.segment_type == segment_type {
+                return Ok(Some(section_header));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn read_section_data(&self, section_header: &SectionHeader) -> Result<&'a [u8], Error> {
+        let start = usize::try_from(section_header.offset).map_err(|_| Error::SourceTooSmall)?;
+        let end = usize::try_from(section_header.size)
+            .ok()
+            .and_then(|size| start.checked_add(size))
+            .ok_or(Error::SourceTooSmall)?;
+
+        self.0.get(start..end).ok_or(Error::SourceTooSmall)
+    }
+
+    // Section names are kept in the string table the header refers to.
+
+    pub fn read_section_names(&self) -> Result<Option<StringTable<'a>>, Error> {
+        let header = self.read_header()?;
+
+        if header.section_header_string_table_index == SECTION_INDEX_UNDEFINED {
+            return Ok(None);
+        }
+
+        let section_header = self.read_section_header(header.section_header_string_table_index)?;
+
+        self.read_string_table(&section_header).map(Some)
+    }
+
+    pub fn find_section_header_by_name(&self, name: &str) -> Result<Option<SectionHeader>, Error> {
+        let section_names = match self.read_section_names()? {
+            Some(section_names) => section_names,
+            None => return Ok(None),
+        };
+
+        let header = self.read_header()?;
+
+        for entry in 0..header.section_header_entry_count {
+            let section_header = self.read_section_header(entry)?;
+
+            if section_names.get(section_header.name) == Some(name) {
+                return Ok(Some(section_header));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn read_string_table(
+        &self,
+        section_header: &SectionHeader,
+    ) -> Result<StringTable<'a>, Error> {
+        if section_header.segment_type != SectionSegmentType::STRING_TABLE {
+            return Err(Error::InvalidSectionType);
+        }
 
+        self.read_section_data(section_header).map(StringTable::new)
+    }
+
+    // Returns a symbol table together with the string table that holds the names of its symbols.
+
+    pub fn read_symbol_table(
+        &self,
+        section_header: &SectionHeader,
+    ) -> Result<(SymbolTable<'a>, StringTable<'a>), Error> {
+        if section_header.segment_type != SectionSegmentType::SYMBOL_TABLE
+            && section_header.segment_type != SectionSegmentType::DYNAMIC_SYMBOL_TABLE
+        {
+            return Err(Error::InvalidSectionType);
+        }
+
+        let string_table_entry =
+            u16::try_from(section_header.link).map_err(|_| Error::InvalidStringTable)?;
+        let string_table_header = self.read_section_header(string_table_entry)?;
+
+        Ok((
+            self.read_table(section_header)?,
+            self.read_string_table(&string_table_header)?,
+        ))
+    }
+
+    pub fn read_rela_table(&self, section_header: &SectionHeader) -> Result<RelaTable<'a>, Error> {
+        if section_header.segment_type != SectionSegmentType::RELOCATIONS_WITH_ADDENDS {
+            return Err(Error::InvalidSectionType);
+        }
+
+        self.read_table(section_header)
+    }
+
+    pub fn read_rel_table(&self, section_header: &SectionHeader) -> Result<RelTable<'a>, Error> {
+        if section_header.segment_type != SectionSegmentType::RELOCATIONS {
+            return Err(Error::InvalidSectionType);
+        }
+
+        self.read_table(section_header)
+    }
+
+    pub fn read_notes(&self, section_header: &SectionHeader) -> Result<Notes<'a>, Error> {
+        if section_header.segment_type != SectionSegmentType::NOTE {
+            return Err(Error::InvalidSectionType);
+        }
+
+        let identity_header = self.read_identity_header()?;
+
+        Notes::new(
+            self.read_section_data(section_header)?,
+            identity_header.data,
+            section_header.address_align,
+        )
+    }
+
+    // The build ID is searched for in the note segments first since they are kept when a file is
+    // stripped of its section headers.
+
+    pub fn find_build_id(&self) -> Result<Option<&'a [u8]>, Error> {
+        let identity_header = self.read_identity_header()?;
         let header = self.read_header()?;
 
         for entry in 0..header.program_header_entry_count {
             let program_header = self.read_program_header(entry)?;
 
-            if program_header.segment_type != ProgramSegmentType::LOAD {
+            if program_header.segment_type != ProgramSegmentType::NOTE {
                 continue;
             }
 
-            let memory_size = program_header.memory_size as usize;
-            if memory_size == 0 {
+            let notes = Notes::new(
+                self.read_segment_data(&program_header)?,
+                identity_header.data,
+                program_header.alignment,
+            )?;
+
+            if let Some(build_id) = notes.find_gnu_build_id()? {
+                return Ok(Some(build_id));
+            }
+        }
+
+        for entry in 0..header.section_header_entry_count {
+            let section_header = self.read_section_header(entry)?;
+
+            if section_header.segment_type != SectionSegmentType::NOTE {
                 continue;
             }
 
-            let file_size = program_header.file_size as usize;
-            if memory_size < file_size {
-                return Err(Error::InvalidProgramSegmentSize);
+            if let Some(build_id) = self.read_notes(&section_header)?.find_gnu_build_id()? {
+                return Ok(Some(build_id));
             }
+        }
+
+        Ok(None)
+    }
 
-            let segment_start_address = program_header.virtual_address as usize;
-            let segment_end_address = segment_start_address + memory_size;
+    // Returns the file contents of a segment. Memory past the file size is not included.
+
+    pub fn read_segment_data(&self, program_header: &ProgramHeader) -> Result<&'a [u8], Error> {
+        let start = usize::try_from(program_header.offset).map_err(|_| Error::SourceTooSmall)?;
+        let end = usize::try_from(program_header.file_size)
+            .ok()
+            .and_then(|file_size| start.checked_add(file_size))
+            .ok_or(Error::SourceTooSmall)?;
+
+        self.0.get(start..end).ok_or(Error::SourceTooSmall)
+    }
+
+    pub fn read_dynamic_table(&self) -> Result<Option<DynamicTable<'a>>, Error> {
+        let identity_header = self.read_identity_header()?;
+
+        let program_header = match self.find_program_header(ProgramSegmentType::DYNAMIC)? {
+            Some(program_header) => program_header,
+            None => return Ok(None),
+        };
+
+        DynamicTable::new(
+            self.read_segment_data(&program_header)?,
+            identity_header.class,
+            identity_header.data,
+        )
+        .map(Some)
+    }
+
+    // Tables use the entry size of their section unless it is left as 0.
+
+    fn read_table<T: Entry>(&self, section_header: &SectionHeader) -> Result<Table<'a, T>, Error> {
+        let identity_header = self.read_identity_header()?;
+        let source = self.read_section_data(section_header)?;
+
+        match section_header.entry_size {
+            0 => Table::new(source, identity_header.class, identity_header.data),
+            entry_size => Table::with_entry_size(
+                source,
+                identity_header.class,
+                identity_header.data,
+                entry_size as usize,
+            ),
+        }
+    }
+
+    pub fn load_segments(&self) -> Result<LoadSegmentIter<'a>, Error> {
+        let header = self.read_header()?;
+
+        Ok(LoadSegmentIter::new(
+            *self,
+            header.program_header_entry_count,
+        ))
+    }
+
+    pub fn load_memory_segment(&self) -> Result<memory::Segment, Error> {
+        let mut start_address = usize::MAX;
+        let mut end_address = usize::MIN;
+        let mut available_load_segment = false;
+
+        for segment in self.load_segments()? {
+            let segment = segment?;
+
+            let segment_start_address = usize::try_from(segment.virtual_address)
+                .map_err(|_| Error::SegmentAddressOverflow)?;
+            let segment_end_address = segment
+                .end_address()
+                .and_then(|end_address| usize::try_from(end_address).ok())
+                .ok_or(Error::SegmentAddressOverflow)?;
 
             start_address = cmp::min(segment_start_address, start_address);
             end_address = cmp::max(segment_end_address, end_address);
@@ -120,12 +360,128 @@ impl<'a> File<'a> {
         Ok(memory::Segment::with_end(start_address, end_address))
     }
 
+    // Checks everything loading relies on so a file from an untrusted source can only be loaded
+    // if it fits in its destination. The file must be a current ELF file with a program header
+    // table inside of it. Loadable segments must be inside of the file, sorted by address without
+    // overlapping, and aligned as the System V ABI requires. An entry point other than 0 must be
+    // inside of an executable segment.
+
+    pub fn validate(&self) -> Result<(), Error> {
+        let identity_header = self.read_identity_header()?;
+
+        if !identity_header.is_valid() {
+            return Err(Error::InvalidMagic);
+        }
+
+        let header = self.read_header()?;
+
+        if u32::from(identity_header.version) != u32::from(Version::CURRENT)
+            || header.version != Version::CURRENT
+        {
+            return Err(Error::UnsupportedVersion);
+        }
+
+        if header.program_header_entry_count != 0 {
+            let table_size =
+                header.program_header_entry_count as u64 * header.program_header_entry_size as u64;
+            let table_end = header
+                .program_header_table_offset
+                .checked_add(table_size)
+                .ok_or(Error::InvalidProgramHeaderTable)?;
+
+            if (header.program_header_entry_size as usize)
+                < ProgramHeader::entry_size(identity_header.class)?
+                || table_end > self.0.len() as u64
+            {
+                return Err(Error::InvalidProgramHeaderTable);
+            }
+        }
+
+        let mut previous_end = None;
+        let mut entry_is_executable = false;
+
+        for entry in 0..header.program_header_entry_count {
+            let program_header = self.read_program_header(entry)?;
+
+            if program_header.segment_type != ProgramSegmentType::LOAD {
+                continue;
+            }
+
+            let (start, end) = self.validate_load_segment(&program_header)?;
+
+            if start == end {
+                continue;
+            }
+
+            if previous_end.map_or(false, |previous_end| start < previous_end) {
+                return Err(Error::OverlappingSegments);
+            }
+
+            previous_end = Some(end);
+
+            if program_header.is_executable() && start <= header.entry && header.entry < end {
+                entry_is_executable = true;
+            }
+        }
+
+        if previous_end.is_none() {
+            return Err(Error::NoLoadProgramSegments);
+        }
+
+        if header.entry != 0 && !entry_is_executable {
+            return Err(Error::InvalidEntry);
+        }
+
+        Ok(())
+    }
+
+    // Returns the range of virtual addresses the segment occupies.
+
+    fn validate_load_segment(&self, program_header: &ProgramHeader) -> Result<(u64, u64), Error> {
+        if program_header.file_size > program_header.memory_size {
+            return Err(Error::InvalidProgramSegmentSize);
+        }
+
+        let file_end = program_header
+            .offset
+            .checked_add(program_header.file_size)
+            .ok_or(Error::SegmentOutsideOfFile)?;
+
+        if file_end > self.0.len() as u64 {
+            return Err(Error::SegmentOutsideOfFile);
+        }
+
+        let end = program_header
+            .virtual_address
+            .checked_add(program_header.memory_size)
+            .filter(|&end| usize::try_from(end).is_ok())
+            .ok_or(Error::SegmentAddressOverflow)?;
+
+        // Alignments of 0 and 1 mean the segment has no alignment. Others must be powers of 2 and
+        // the segment must have the same offset from the alignment in the file and in memory.
+
+        let alignment = program_header.alignment;
+
+        if alignment > 1
+            && (!alignment.is_power_of_two()
+                || program_header.offset % alignment != program_header.virtual_address % alignment)
+        {
+            return Err(Error::InvalidSegmentAlignment);
+        }
+
+        Ok((program_header.virtual_address, end))
+    }
+
     pub unsafe fn load(&self) -> Result<(), Error> {
+        self.validate()?;
+
         let memory_range = self.load_memory_segment()?;
         self.load_internal(memory_range.as_mut_slice(), memory_range)
     }
 
     pub fn load_to(&self, memory: &mut [u8]) -> Result<(), Error> {
+        self.validate()?;
+
         let memory_range = self.load_memory_segment()?;
 
         if memory_range.len() > memory.len() {
@@ -135,35 +491,108 @@ impl<'a> File<'a> {
         self.load_internal(memory, memory_range)
     }
 
-    fn load_internal(&self, memory: &mut [u8], memory_usage: memory::Segment) -> Result<(), Error> {
+    // Applies the relocations of a position independent file after it was loaded with load_to.
+    // The slide is the difference between the address the file was loaded at and the address it
+    // was linked at. Only relative relocations are supported since they are the only ones a
+    // static position independent executable needs.
+
+    pub fn relocate(&self, memory: &mut [u8], slide: u64) -> Result<(), Error> {
+        let identity_header = self.read_identity_header()?;
+        let class = identity_header.class;
+        let data = identity_header.data;
+        let endian = Endian::try_from(data)?;
+
         let header = self.read_header()?;
 
-        for entry in 0..header.program_header_entry_count {
-            let program_header = self.read_program_header(entry)?;
+        let dynamic_table = match self.read_dynamic_table()? {
+            Some(dynamic_table) => dynamic_table,
+            None => return Ok(()),
+        };
 
-            if program_header.segment_type != ProgramSegmentType::LOAD {
-                continue;
-            }
+        if dynamic_table.find(DynamicTag::REL).is_some()
+            || dynamic_table.find(DynamicTag::JUMP_RELOCATION).is_some()
+        {
+            return Err(Error::UnsupportedRelocation);
+        }
+
+        let table_address = match dynamic_table.find(DynamicTag::RELA) {
+            Some(table_address) => table_address,
+            None => return Ok(()),
+        };
 
-            let file_size = program_header.file_size as usize;
-            let file_start = program_header.offset as usize;
-            let file_end = file_start + file_size;
+        let table_size = dynamic_table.find(DynamicTag::RELA_SIZE).unwrap_or(0) as usize;
+        let entry_size = match dynamic_table.find(DynamicTag::RELA_ENTRY_SIZE) {
+            Some(entry_size) => entry_size as usize,
+            None => Rela::entry_size(class)?,
+        };
 
-            let source = self
-                .0
-                .get(file_start..file_end)
-                .ok_or(Error::SourceTooSmall)?;
+        if entry_size < Rela::entry_size(class)? {
+            return Err(Error::InvalidDynamicSection);
+        }
+
+        if header.machine != Machine::X86_64 {
+            return Err(Error::UnsupportedRelocation);
+        }
 
-            let memory_size = program_header.memory_size as usize;
-            let destination_start =
-                (program_header.virtual_address as usize) - memory_usage.start();
-            let destination_end = destination_start + file_size;
+        // The table is read from the loaded memory since relocations refer to virtual addresses.
+
+        let memory_start = self.load_memory_segment()?.start() as u64;
+
+        let table_start = table_address
+            .checked_sub(memory_start)
+            .ok_or(Error::InvalidDynamicSection)? as usize;
+
+        for index in 0..table_size / entry_size {
+            let relocation = table_start
+                .checked_add(index * entry_size)
+                .and_then(|start| memory.get(start..))
+                .ok_or(Error::InvalidDynamicSection)
+                .and_then(|source| Rela::read(source, class, data))?;
+
+            match RelocationTypeX86_64::from(relocation.relocation_type()) {
+                RelocationTypeX86_64::NONE => {}
+                RelocationTypeX86_64::RELATIVE => {
+                    let value = (relocation.addend as u64).wrapping_add(slide);
+
+                    let bytes = match endian {
+                        Endian::Little => value.to_le_bytes(),
+                        Endian::Big => value.to_be_bytes(),
+                    };
+
+                    let target_start = relocation
+                        .offset
+                        .checked_sub(memory_start)
+                        .ok_or(Error::DestinationTooSmall)?;
+
+                    memory
+                        .get_mut(target_start as usize..)
+                        .and_then(|target| target.get_mut(..bytes.len()))
+                        .ok_or(Error::DestinationTooSmall)?
+                        .copy_from_slice(&bytes);
+                }
+                _ => return Err(Error::UnsupportedRelocation),
+            }
+        }
 
-            let destination = &mut memory[destination_start..destination_end];
-            destination.copy_from_slice(source);
+        Ok(())
+    }
 
-            let destination_extra_end = destination_end + (memory_size - file_size);
-            let destination_extra = &mut memory[destination_end..destination_extra_end];
+    fn load_internal(&self, memory: &mut [u8], memory_usage: memory::Segment) -> Result<(), Error> {
+        for segment in self.load_segments()? {
+            let segment = segment?;
+
+            let destination_start = usize::try_from(segment.virtual_address)
+                .ok()
+                .and_then(|address| address.checked_sub(memory_usage.start()))
+                .ok_or(Error::DestinationTooSmall)?;
+            let destination = usize::try_from(segment.memory_size)
+                .ok()
+                .and_then(|memory_size| destination_start.checked_add(memory_size))
+                .and_then(|destination_end| memory.get_mut(destination_start..destination_end))
+                .ok_or(Error::DestinationTooSmall)?;
+
+            let (destination, destination_extra) = destination.split_at_mut(segment.data.len());
+            destination.copy_from_slice(segment.data);
 
             for extra_byte in destination_extra {
                 *extra_byte = 0;
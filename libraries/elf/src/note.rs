@@ -0,0 +1,169 @@
+//**************************************************************************************************
+// note.rs                                                                                         *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use super::error::Error;
+use super::identity::Data;
+use core::convert::TryFrom;
+use io::cursor::Cursor;
+use io::{Endian, EndianRead};
+
+// Types of notes with the GNU name.
+
+c_enum!(
+    pub enum GnuNoteType : u32 {
+        ABI_TAG = 1,
+        HARDWARE_CAPABILITIES = 2,
+        BUILD_ID = 3,
+        GOLD_VERSION = 4,
+        PROPERTY = 5,
+    }
+);
+
+pub const GNU_NOTE_NAME: &[u8] = b"GNU";
+
+const NOTE_HEADER_SIZE: usize = 12;
+
+// The meaning of the type depends on the name of the note.
+
+#[derive(Clone, Debug)]
+pub struct Note<'a> {
+    pub note_type: u32,
+    // The name without its null terminator.
+    pub name: &'a [u8],
+    pub description: &'a [u8],
+}
+
+impl Note<'_> {
+    pub fn is_gnu(&self) -> bool {
+        self.name == GNU_NOTE_NAME
+    }
+
+    pub fn is_gnu_build_id(&self) -> bool {
+        self.is_gnu() && GnuNoteType::from(self.note_type) == GnuNoteType::BUILD_ID
+    }
+}
+
+// The notes of a note section or segment. Both 32 and 64-bit files use 4 byte fields but the
+// name and description are padded to the alignment of the section, which is 4 or 8.
+
+#[derive(Copy, Clone, Debug)]
+pub struct Notes<'a> {
+    source: &'a [u8],
+    endian: Endian,
+    alignment: usize,
+}
+
+impl<'a> Notes<'a> {
+    pub fn new(source: &'a [u8], data: Data, alignment: u64) -> Result<Self, Error> {
+        let alignment = match alignment {
+            8 => 8,
+            _ => 4,
+        };
+
+        Ok(Self {
+            source,
+            endian: Endian::try_from(data)?,
+            alignment,
+        })
+    }
+
+    pub fn iter(&self) -> NoteIter<'a> {
+        NoteIter {
+            notes: *self,
+            position: 0,
+        }
+    }
+
+    pub fn find_gnu_build_id(&self) -> Result<Option<&'a [u8]>, Error> {
+        for note in self.iter() {
+            let note = note?;
+
+            if note.is_gnu_build_id() {
+                return Ok(Some(note.description));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn read(&self, position: usize) -> Result<(Note<'a>, usize), Error> {
+        let source = self.source.get(position..).ok_or(Error::InvalidNote)?;
+        let mut cursor = Cursor::new(source);
+
+        let name_size = cursor.read_u32(self.endian)? as usize;
+        let description_size = cursor.read_u32(self.endian)? as usize;
+        let note_type = cursor.read_u32(self.endian)?;
+
+        let name_start = NOTE_HEADER_SIZE;
+        let name_end = name_start
+            .checked_add(name_size)
+            .ok_or(Error::InvalidNote)?;
+        let description_start = align_up(name_end, self.alignment).ok_or(Error::InvalidNote)?;
+        let description_end = description_start
+            .checked_add(description_size)
+            .ok_or(Error::InvalidNote)?;
+        let next = align_up(description_end, self.alignment).ok_or(Error::InvalidNote)?;
+
+        let name = source.get(name_start..name_end).ok_or(Error::InvalidNote)?;
+        let description = source
+            .get(description_start..description_end)
+            .ok_or(Error::InvalidNote)?;
+
+        // The padding after the last note may be left out.
+
+        let next = position.saturating_add(next).min(self.source.len());
+
+        let note = Note {
+            note_type,
+            name: name.strip_suffix(&[0][..]).unwrap_or(name),
+            description,
+        };
+
+        Ok((note, next))
+    }
+}
+
+impl<'a> IntoIterator for Notes<'a> {
+    type Item = Result<Note<'a>, Error>;
+    type IntoIter = NoteIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// Notes have different sizes so a malformed one ends the iteration after its error.
+
+#[derive(Clone, Debug)]
+pub struct NoteIter<'a> {
+    notes: Notes<'a>,
+    position: usize,
+}
+
+impl<'a> Iterator for NoteIter<'a> {
+    type Item = Result<Note<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.notes.source.len() {
+            return None;
+        }
+
+        match self.notes.read(self.position) {
+            Ok((note, next)) => {
+                self.position = next;
+                Some(Ok(note))
+            }
+            Err(error) => {
+                self.position = self.notes.source.len();
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+fn align_up(value: usize, alignment: usize) -> Option<usize> {
+    Some(value.checked_add(alignment - 1)? & !(alignment - 1))
+}
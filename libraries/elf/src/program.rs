@@ -6,6 +6,7 @@
 
 use super::error::Error;
 use super::identity::{Class, Data};
+use super::File;
 use core::convert::TryFrom;
 use io::cursor::Cursor;
 use io::{Endian, EndianRead};
@@ -22,6 +23,14 @@ c_enum!(
     }
 );
 
+// Permission bits of the flags field.
+
+pub const PROGRAM_FLAG_EXECUTE: u32 = 0x1;
+
+pub const PROGRAM_FLAG_WRITE: u32 = 0x2;
+
+pub const PROGRAM_FLAG_READ: u32 = 0x4;
+
 #[derive(Clone, Debug)]
 pub struct ProgramHeader {
     pub segment_type: ProgramSegmentType,
@@ -35,6 +44,18 @@ pub struct ProgramHeader {
 }
 
 impl ProgramHeader {
+    pub fn is_executable(&self) -> bool {
+        self.flags & PROGRAM_FLAG_EXECUTE != 0
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.flags & PROGRAM_FLAG_WRITE != 0
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.flags & PROGRAM_FLAG_READ != 0
+    }
+
     pub fn read(source: &[u8], class: Class, data: Data) -> Result<Self, Error> {
         let endian = Endian::try_from(data)?;
         let mut cursor = Cursor::new(source);
@@ -63,4 +84,106 @@ impl ProgramHeader {
             _ => Err(Error::UnknownClass),
         }
     }
+
+    pub fn entry_size(class: Class) -> Result<usize, Error> {
+        match class {
+            Class::SIXTY_FOUR => Ok(56),
+            Class::THIRTY_TWO => Ok(32),
+            _ => Err(Error::UnknownClass),
+        }
+    }
+}
+
+// A loadable segment and its contents in the file. Memory past the contents is filled with zeros
+// when it is loaded.
+
+#[derive(Clone, Debug)]
+pub struct LoadSegment<'a> {
+    pub virtual_address: u64,
+    pub memory_size: u64,
+    pub alignment: u64,
+    pub flags: u32,
+    pub data: &'a [u8],
+}
+
+impl LoadSegment<'_> {
+    pub fn is_executable(&self) -> bool {
+        self.flags & PROGRAM_FLAG_EXECUTE != 0
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.flags & PROGRAM_FLAG_WRITE != 0
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.flags & PROGRAM_FLAG_READ != 0
+    }
+
+    pub fn end_address(&self) -> Option<u64> {
+        self.virtual_address.checked_add(self.memory_size)
+    }
+}
+
+// Iterates over the loadable segments of a file in the order of the program header table.
+// Segments that take up no memory are skipped. The iteration ends after an error.
+
+#[derive(Clone, Debug)]
+pub struct LoadSegmentIter<'a> {
+    file: File<'a>,
+    entry: u16,
+    entry_count: u16,
+}
+
+impl<'a> LoadSegmentIter<'a> {
+    pub(crate) fn new(file: File<'a>, entry_count: u16) -> Self {
+        Self {
+            file,
+            entry: 0,
+            entry_count,
+        }
+    }
+
+    fn read(&self, program_header: &ProgramHeader) -> Result<LoadSegment<'a>, Error> {
+        if program_header.file_size > program_header.memory_size {
+            return Err(Error::InvalidProgramSegmentSize);
+        }
+
+        Ok(LoadSegment {
+            virtual_address: program_header.virtual_address,
+            memory_size: program_header.memory_size,
+            alignment: program_header.alignment,
+            flags: program_header.flags,
+            data: self.file.read_segment_data(program_header)?,
+        })
+    }
+}
+
+impl<'a> Iterator for LoadSegmentIter<'a> {
+    type Item = Result<LoadSegment<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.entry < self.entry_count {
+            let result = self.file.read_program_header(self.entry);
+            self.entry += 1;
+
+            let result = match result {
+                Ok(program_header)
+                    if program_header.segment_type != ProgramSegmentType::LOAD
+                        || program_header.memory_size == 0 =>
+                {
+                    continue
+                }
+                Ok(program_header) => self.read(&program_header),
+                Err(error) => Err(error),
+            };
+
+            if result.is_err() {
+                self.entry = self.entry_count;
+            }
+
+            return Some(result);
+        }
+
+        None
+    }
 }
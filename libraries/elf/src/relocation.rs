@@ -0,0 +1,132 @@
+//**************************************************************************************************
+// relocation.rs                                                                                   *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use super::error::Error;
+use super::identity::{Class, Data};
+use super::table::{Entry, Table};
+use core::convert::TryFrom;
+use io::cursor::Cursor;
+use io::{Endian, EndianRead};
+
+c_enum!(
+    pub enum RelocationTypeX86_64 : u32 {
+        NONE = 0,
+        SIXTY_FOUR = 1,
+        PC_32 = 2,
+        GOT_32 = 3,
+        PLT_32 = 4,
+        COPY = 5,
+        GLOBAL_DATA = 6,
+        JUMP_SLOT = 7,
+        RELATIVE = 8,
+    }
+);
+
+pub type RelaTable<'a> = Table<'a, Rela>;
+
+pub type RelTable<'a> = Table<'a, Rel>;
+
+// A relocation with an explicit addend.
+
+#[derive(Clone, Debug)]
+pub struct Rela {
+    pub offset: u64,
+    pub info: u64,
+    pub addend: i64,
+}
+
+impl Entry for Rela {
+    fn read(source: &[u8], class: Class, data: Data) -> Result<Self, Error> {
+        let endian = Endian::try_from(data)?;
+        let mut cursor = Cursor::new(source);
+
+        match class {
+            Class::SIXTY_FOUR => Ok(Rela {
+                offset: cursor.read_u64(endian)?,
+                // The symbol index is stored in the upper half for 64-bit files.
+                info: cursor.read_u64(endian)?,
+                addend: cursor.read_u64(endian)? as i64,
+            }),
+            Class::THIRTY_TWO => Ok(Rela {
+                offset: cursor.read_u32(endian)? as u64,
+                info: convert_info_32(cursor.read_u32(endian)?),
+                addend: cursor.read_u32(endian)? as i32 as i64,
+            }),
+            _ => Err(Error::UnknownClass),
+        }
+    }
+
+    fn entry_size(class: Class) -> Result<usize, Error> {
+        match class {
+            Class::SIXTY_FOUR => Ok(24),
+            Class::THIRTY_TWO => Ok(12),
+            _ => Err(Error::UnknownClass),
+        }
+    }
+}
+
+impl Rela {
+    pub fn relocation_type(&self) -> u32 {
+        self.info as u32
+    }
+
+    pub fn symbol_index(&self) -> u32 {
+        (self.info >> 32) as u32
+    }
+}
+
+// A relocation that takes its addend from the location being relocated.
+
+#[derive(Clone, Debug)]
+pub struct Rel {
+    pub offset: u64,
+    pub info: u64,
+}
+
+impl Entry for Rel {
+    fn read(source: &[u8], class: Class, data: Data) -> Result<Self, Error> {
+        let endian = Endian::try_from(data)?;
+        let mut cursor = Cursor::new(source);
+
+        match class {
+            Class::SIXTY_FOUR => Ok(Rel {
+                offset: cursor.read_u64(endian)?,
+                info: cursor.read_u64(endian)?,
+            }),
+            Class::THIRTY_TWO => Ok(Rel {
+                offset: cursor.read_u32(endian)? as u64,
+                info: convert_info_32(cursor.read_u32(endian)?),
+            }),
+            _ => Err(Error::UnknownClass),
+        }
+    }
+
+    fn entry_size(class: Class) -> Result<usize, Error> {
+        match class {
+            Class::SIXTY_FOUR => Ok(16),
+            Class::THIRTY_TWO => Ok(8),
+            _ => Err(Error::UnknownClass),
+        }
+    }
+}
+
+impl Rel {
+    pub fn relocation_type(&self) -> u32 {
+        self.info as u32
+    }
+
+    pub fn symbol_index(&self) -> u32 {
+        (self.info >> 32) as u32
+    }
+}
+
+// 32-bit files store the type in the low byte and the symbol index above it. They are moved to
+// the layout of 64-bit files so both can be handled the same way.
+
+fn convert_info_32(info: u32) -> u64 {
+    let info = info as u64;
+    ((info >> 8) << 32) | (info & 0xFF)
+}
@@ -23,6 +23,7 @@ c_enum!(
         NO_BITS = 8,
         RELOCATIONS = 9,
         RESERVED = 10,
+        DYNAMIC_SYMBOL_TABLE = 11,
     }
 );
 
@@ -0,0 +1,136 @@
+//**************************************************************************************************
+// symbol.rs                                                                                       *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use super::error::Error;
+use super::identity::{Class, Data};
+use super::table::{Entry, Table, TableIter};
+use core::convert::TryFrom;
+use core::str;
+use io::cursor::Cursor;
+use io::{Endian, EndianRead};
+
+c_enum!(
+    pub enum SymbolType : u8 {
+        NONE = 0,
+        OBJECT = 1,
+        FUNCTION = 2,
+        SECTION = 3,
+        FILE = 4,
+        COMMON = 5,
+        THREAD_LOCAL = 6,
+    }
+);
+
+c_enum!(
+    pub enum SymbolBinding : u8 {
+        LOCAL = 0,
+        GLOBAL = 1,
+        WEAK = 2,
+    }
+);
+
+pub const SECTION_INDEX_UNDEFINED: u16 = 0;
+
+#[derive(Clone, Debug)]
+pub struct Symbol {
+    pub name: u32,
+    pub info: u8,
+    pub other: u8,
+    pub section_index: u16,
+    pub value: u64,
+    pub size: u64,
+}
+
+impl Entry for Symbol {
+    fn read(source: &[u8], class: Class, data: Data) -> Result<Self, Error> {
+        let endian = Endian::try_from(data)?;
+        let mut cursor = Cursor::new(source);
+
+        match class {
+            Class::SIXTY_FOUR => Ok(Symbol {
+                name: cursor.read_u32(endian)?,
+                info: cursor.read_u8()?,
+                other: cursor.read_u8()?,
+                section_index: cursor.read_u16(endian)?,
+                value: cursor.read_u64(endian)?,
+                size: cursor.read_u64(endian)?,
+            }),
+            Class::THIRTY_TWO => Ok(Symbol {
+                name: cursor.read_u32(endian)?,
+                value: cursor.read_u32(endian)? as u64,
+                size: cursor.read_u32(endian)? as u64,
+                info: cursor.read_u8()?,
+                other: cursor.read_u8()?,
+                section_index: cursor.read_u16(endian)?,
+            }),
+            _ => Err(Error::UnknownClass),
+        }
+    }
+
+    fn entry_size(class: Class) -> Result<usize, Error> {
+        match class {
+            Class::SIXTY_FOUR => Ok(24),
+            Class::THIRTY_TWO => Ok(16),
+            _ => Err(Error::UnknownClass),
+        }
+    }
+}
+
+impl Symbol {
+    pub fn symbol_type(&self) -> SymbolType {
+        SymbolType::from(self.info & 0xF)
+    }
+
+    pub fn binding(&self) -> SymbolBinding {
+        SymbolBinding::from(self.info >> 4)
+    }
+
+    pub fn name<'a>(&self, strings: &StringTable<'a>) -> Option<&'a str> {
+        strings.get(self.name)
+    }
+
+    // Undefined symbols are provided by another file.
+
+    pub fn is_undefined(&self) -> bool {
+        self.section_index == SECTION_INDEX_UNDEFINED
+    }
+
+    pub fn contains_address(&self, address: u64) -> bool {
+        address >= self.value && address - self.value < self.size
+    }
+}
+
+pub type SymbolTable<'a> = Table<'a, Symbol>;
+
+pub type SymbolIter<'a> = TableIter<'a, Symbol>;
+
+impl<'a> SymbolTable<'a> {
+    // Names are not unique so the first symbol with the name is returned.
+
+    pub fn find(&self, name: &str, strings: &StringTable<'a>) -> Option<Symbol> {
+        self.iter()
+            .find(|symbol| symbol.name(strings) == Some(name))
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct StringTable<'a>(&'a [u8]);
+
+impl<'a> StringTable<'a> {
+    pub const fn new(source: &'a [u8]) -> Self {
+        StringTable(source)
+    }
+
+    pub fn source(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn get(&self, offset: u32) -> Option<&'a str> {
+        let source = self.0.get(offset as usize..)?;
+        let len = source.iter().position(|&byte| byte == 0)?;
+        str::from_utf8(&source[..len]).ok()
+    }
+}
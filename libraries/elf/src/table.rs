@@ -0,0 +1,128 @@
+//**************************************************************************************************
+// table.rs                                                                                        *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use super::error::Error;
+use super::identity::{Class, Data};
+use core::convert::TryFrom;
+use core::marker::PhantomData;
+use io::Endian;
+
+// A structure stored in an array of fixed size entries such as a symbol or relocation table.
+
+pub trait Entry: Sized {
+    fn read(source: &[u8], class: Class, data: Data) -> Result<Self, Error>;
+
+    fn entry_size(class: Class) -> Result<usize, Error>;
+}
+
+// A table of entries read directly from the source. Entries are only decoded when they are
+// accessed.
+
+#[derive(Debug)]
+pub struct Table<'a, T: Entry> {
+    source: &'a [u8],
+    class: Class,
+    data: Data,
+    entry_size: usize,
+    entry: PhantomData<T>,
+}
+
+impl<'a, T: Entry> Table<'a, T> {
+    pub fn new(source: &'a [u8], class: Class, data: Data) -> Result<Self, Error> {
+        Self::with_entry_size(source, class, data, T::entry_size(class)?)
+    }
+
+    // Files give the size of the entries of a table so they can be extended. It can't be smaller
+    // than the entries this library reads.
+
+    pub fn with_entry_size(
+        source: &'a [u8],
+        class: Class,
+        data: Data,
+        entry_size: usize,
+    ) -> Result<Self, Error> {
+        Endian::try_from(data)?;
+
+        if entry_size < T::entry_size(class)? {
+            return Err(Error::InvalidEntrySize);
+        }
+
+        Ok(Self {
+            source,
+            class,
+            data,
+            entry_size,
+            entry: PhantomData,
+        })
+    }
+
+    pub fn source(&self) -> &'a [u8] {
+        self.source
+    }
+
+    pub fn len(&self) -> usize {
+        self.source.len() / self.entry_size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Result<T, Error> {
+        let start = index
+            .checked_mul(self.entry_size)
+            .ok_or(Error::SourceTooSmall)?;
+        let source = self.source.get(start..).ok_or(Error::SourceTooSmall)?;
+        T::read(source, self.class, self.data)
+    }
+
+    pub fn iter(&self) -> TableIter<'a, T> {
+        TableIter {
+            table: *self,
+            index: 0,
+        }
+    }
+}
+
+// Deriving these would require the entry to implement them too.
+
+impl<T: Entry> Clone for Table<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Entry> Copy for Table<'_, T> {}
+
+impl<'a, T: Entry> IntoIterator for Table<'a, T> {
+    type Item = T;
+    type IntoIter = TableIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TableIter<'a, T: Entry> {
+    table: Table<'a, T>,
+    index: usize,
+}
+
+impl<'a, T: Entry> Iterator for TableIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.table.get(self.index).ok()?;
+        self.index += 1;
+        Some(entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.table.len().saturating_sub(self.index);
+        (remaining, Some(remaining))
+    }
+}
@@ -0,0 +1,83 @@
+//**************************************************************************************************
+// error.rs                                                                                        *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use core::fmt;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Error {
+    Truncated,
+    InvalidBlockSize,
+    NoProtectiveMbr,
+    InvalidSignature,
+    UnsupportedRevision,
+    InvalidHeaderSize,
+    HeaderChecksumMismatch,
+    WrongHeaderLocation,
+    InvalidEntrySize,
+    TooManyEntries,
+    InvalidEntryLocation,
+    InvalidUsableRange,
+    InvalidPartitionLocation,
+    EntriesChecksumMismatch,
+    InvalidName,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Truncated => write!(f, "The source ends in the middle of a structure."),
+            Error::InvalidBlockSize => write!(f, "The block size is too small for a GPT disk."),
+            Error::NoProtectiveMbr => write!(f, "The disk has no protective MBR."),
+            Error::InvalidSignature => {
+                write!(f, "The header does not start with the GPT signature.")
+            }
+            Error::UnsupportedRevision => write!(f, "The GPT revision is not supported."),
+            Error::InvalidHeaderSize => write!(f, "The header size is invalid."),
+            Error::HeaderChecksumMismatch => write!(f, "The header does not match its CRC32."),
+            Error::WrongHeaderLocation => {
+                write!(f, "The header is not at the block it says it is at.")
+            }
+            Error::InvalidEntrySize => write!(f, "The partition entry size is invalid."),
+            Error::TooManyEntries => write!(f, "The partition entries are too large."),
+            Error::InvalidEntryLocation => {
+                write!(f, "The partition entries are outside of the disk.")
+            }
+            Error::InvalidUsableRange => {
+                write!(f, "The usable blocks are not a valid range of the disk.")
+            }
+            Error::InvalidPartitionLocation => {
+                write!(f, "A partition is outside of the usable blocks.")
+            }
+            Error::EntriesChecksumMismatch => {
+                write!(f, "The partition entries do not match their CRC32.")
+            }
+            Error::InvalidName => write!(f, "The partition name is not valid UCS-2."),
+        }
+    }
+}
+
+// Errors of reading a partition table from a disk. The disk's own errors are kept as they are.
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ReadError<E> {
+    Disk(E),
+    Table(Error),
+}
+
+impl<E> From<Error> for ReadError<E> {
+    fn from(error: Error) -> Self {
+        ReadError::Table(error)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ReadError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Disk(error) => write!(f, "Failed to read the disk. {}", error),
+            ReadError::Table(error) => write!(f, "The partition table is invalid. {}", error),
+        }
+    }
+}
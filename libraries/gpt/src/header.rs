@@ -0,0 +1,178 @@
+//**************************************************************************************************
+// header.rs                                                                                       *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::crc32::{crc32, Crc32};
+use crate::error::Error;
+use crate::partition::{Partition, Partitions, MIN_ENTRY_SIZE};
+use core::convert::TryInto;
+use uefi::Guid;
+
+const SIGNATURE: [u8; 8] = *b"EFI PART";
+
+const MIN_HEADER_SIZE: usize = 92;
+
+const HEADER_CRC32_OFFSET: usize = 16;
+
+// Only the major version has to match. Minor versions are compatible.
+
+const REVISION_MAJOR: u32 = 1;
+
+// The specification reserves 16 KiB for the entries. Larger tables are allowed up to this so a
+// damaged header can't make the table allocate most of memory.
+
+const MAX_ENTRIES_LEN: usize = 1024 * 1024;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Header {
+    pub revision: u32,
+    pub header_size: u32,
+    pub header_crc32: u32,
+    pub my_lba: u64,
+    pub alternate_lba: u64,
+    pub first_usable_lba: u64,
+    pub last_usable_lba: u64,
+    pub disk_guid: Guid,
+    pub partition_entry_lba: u64,
+    pub number_of_partition_entries: u32,
+    pub size_of_partition_entry: u32,
+    pub partition_entry_array_crc32: u32,
+}
+
+impl Header {
+    // Reads the header from the start of its block. The source must be the whole block since the
+    // header's CRC32 can cover more than the fields defined so far.
+
+    pub fn read(source: &[u8]) -> Result<Self, Error> {
+        if source.len() < MIN_HEADER_SIZE {
+            return Err(Error::Truncated);
+        }
+
+        if source[..SIGNATURE.len()] != SIGNATURE {
+            return Err(Error::InvalidSignature);
+        }
+
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(source[offset..offset + 4].try_into().unwrap());
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(source[offset..offset + 8].try_into().unwrap());
+
+        let revision = u32_at(8);
+
+        if revision >> 16 != REVISION_MAJOR {
+            return Err(Error::UnsupportedRevision);
+        }
+
+        let header_size = u32_at(12);
+
+        if (header_size as usize) < MIN_HEADER_SIZE || header_size as usize > source.len() {
+            return Err(Error::InvalidHeaderSize);
+        }
+
+        let header_crc32 = u32_at(HEADER_CRC32_OFFSET);
+
+        // The CRC32 is calculated with its own field set to 0.
+
+        let header = &source[..header_size as usize];
+
+        let mut crc = Crc32::new();
+        crc.update(&header[..HEADER_CRC32_OFFSET]);
+        crc.update(&[0; 4]);
+        crc.update(&header[HEADER_CRC32_OFFSET + 4..]);
+
+        let calculated_crc32 = crc.finish();
+
+        if calculated_crc32 != header_crc32 {
+            return Err(Error::HeaderChecksumMismatch);
+        }
+
+        let header = Self {
+            revision,
+            header_size,
+            header_crc32,
+            my_lba: u64_at(24),
+            alternate_lba: u64_at(32),
+            first_usable_lba: u64_at(40),
+            last_usable_lba: u64_at(48),
+            disk_guid: Guid::from_bytes(source[56..72].try_into().unwrap()),
+            partition_entry_lba: u64_at(72),
+            number_of_partition_entries: u32_at(80),
+            size_of_partition_entry: u32_at(84),
+            partition_entry_array_crc32: u32_at(88),
+        };
+
+        // Entries are 128 bytes multiplied by a power of two.
+
+        let entry_size = header.size_of_partition_entry as usize;
+
+        if entry_size < MIN_ENTRY_SIZE
+            || entry_size % MIN_ENTRY_SIZE != 0
+            || !(entry_size / MIN_ENTRY_SIZE).is_power_of_two()
+        {
+            return Err(Error::InvalidEntrySize);
+        }
+
+        let entries_len = (header.number_of_partition_entries as usize)
+            .checked_mul(entry_size)
+            .ok_or(Error::TooManyEntries)?;
+
+        if entries_len > MAX_ENTRIES_LEN {
+            return Err(Error::TooManyEntries);
+        }
+
+        if header.first_usable_lba > header.last_usable_lba {
+            return Err(Error::InvalidUsableRange);
+        }
+
+        Ok(header)
+    }
+
+    pub fn is_primary(&self) -> bool {
+        self.my_lba == 1
+    }
+
+    // Can't overflow for a header that was read since the length is limited.
+
+    pub fn entries_len(&self) -> usize {
+        self.number_of_partition_entries as usize * self.size_of_partition_entry as usize
+    }
+
+    // The number of blocks the entries take up rounded up to whole blocks.
+
+    pub fn entries_block_count(&self, block_size: usize) -> u64 {
+        ((self.entries_len() + block_size - 1) / block_size) as u64
+    }
+
+    // True if the partition is entirely within the usable blocks.
+
+    pub fn is_usable(&self, partition: &Partition) -> bool {
+        partition.starting_lba >= self.first_usable_lba
+            && partition.starting_lba <= partition.ending_lba
+            && partition.ending_lba <= self.last_usable_lba
+    }
+
+    // Checks the entries against their CRC32 and that every used one is within the usable blocks,
+    // then returns the used ones. The source starts at the partition entry LBA and may be longer
+    // than the entries.
+
+    pub fn partitions<'a>(&self, source: &'a [u8]) -> Result<Partitions<'a>, Error> {
+        let entries = source.get(..self.entries_len()).ok_or(Error::Truncated)?;
+
+        if crc32(entries) != self.partition_entry_array_crc32 {
+            return Err(Error::EntriesChecksumMismatch);
+        }
+
+        let partitions = Partitions::new(entries, self.size_of_partition_entry as usize);
+
+        if !partitions
+            .clone()
+            .all(|partition| self.is_usable(&partition))
+        {
+            return Err(Error::InvalidPartitionLocation);
+        }
+
+        Ok(partitions)
+    }
+}
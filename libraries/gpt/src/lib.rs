@@ -0,0 +1,24 @@
+//**************************************************************************************************
+// lib.rs                                                                                          *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+#![no_std]
+
+extern crate alloc;
+
+mod crc32;
+mod error;
+mod header;
+mod mbr;
+mod partition;
+mod table;
+mod uefi_disk;
+
+pub use crc32::*;
+pub use error::*;
+pub use header::*;
+pub use mbr::*;
+pub use partition::*;
+pub use table::*;
@@ -0,0 +1,91 @@
+//**************************************************************************************************
+// mbr.rs                                                                                          *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::error::Error;
+use core::convert::TryInto;
+
+const RECORDS_OFFSET: usize = 446;
+
+const RECORD_SIZE: usize = 16;
+
+const RECORD_COUNT: usize = 4;
+
+const SIGNATURE_OFFSET: usize = 510;
+
+const SIGNATURE: [u8; 2] = [0x55, 0xAA];
+
+// The MBR is always 512 bytes at the start of the first block whatever the block size is.
+
+pub const MBR_SIZE: usize = 512;
+
+// A GPT disk starts with an MBR that covers the whole disk with one partition of this type so
+// tools that only know MBR don't see it as empty.
+
+pub const PROTECTIVE_OS_TYPE: u8 = 0xEE;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MbrPartitionRecord {
+    pub boot_indicator: u8,
+    pub starting_chs: [u8; 3],
+    pub os_type: u8,
+    pub ending_chs: [u8; 3],
+    pub starting_lba: u32,
+    pub size_in_lba: u32,
+}
+
+impl MbrPartitionRecord {
+    fn read(source: &[u8]) -> Self {
+        Self {
+            boot_indicator: source[0],
+            starting_chs: [source[1], source[2], source[3]],
+            os_type: source[4],
+            ending_chs: [source[5], source[6], source[7]],
+            starting_lba: u32::from_le_bytes(source[8..12].try_into().unwrap()),
+            size_in_lba: u32::from_le_bytes(source[12..16].try_into().unwrap()),
+        }
+    }
+
+    pub fn is_used(&self) -> bool {
+        self.os_type != 0
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Mbr {
+    records: [MbrPartitionRecord; RECORD_COUNT],
+}
+
+impl Mbr {
+    pub fn read(source: &[u8]) -> Result<Self, Error> {
+        let source = source.get(..MBR_SIZE).ok_or(Error::Truncated)?;
+
+        if source[SIGNATURE_OFFSET..] != SIGNATURE {
+            return Err(Error::NoProtectiveMbr);
+        }
+
+        let record = |index: usize| {
+            let offset = RECORDS_OFFSET + index * RECORD_SIZE;
+            MbrPartitionRecord::read(&source[offset..offset + RECORD_SIZE])
+        };
+
+        Ok(Self {
+            records: [record(0), record(1), record(2), record(3)],
+        })
+    }
+
+    pub fn records(&self) -> &[MbrPartitionRecord] {
+        &self.records
+    }
+
+    // Hybrid MBRs that also describe some GPT partitions are accepted as long as one record
+    // protects the GPT.
+
+    pub fn is_protective(&self) -> bool {
+        self.records
+            .iter()
+            .any(|record| record.os_type == PROTECTIVE_OS_TYPE && record.starting_lba == 1)
+    }
+}
@@ -0,0 +1,162 @@
+//**************************************************************************************************
+// partition.rs                                                                                    *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::error::Error;
+use alloc::string::String;
+use core::convert::TryInto;
+use core::iter::FusedIterator;
+use memory::flags;
+use ucs2::FromUcs2Buffer;
+use uefi::Guid;
+
+pub(crate) const MIN_ENTRY_SIZE: usize = 128;
+
+const NAME_OFFSET: usize = 56;
+
+const NAME_LEN: usize = 36;
+
+pub const EFI_SYSTEM_PARTITION: Guid = Guid {
+    data_1: 0xC12A7328,
+    data_2: 0xF81F,
+    data_3: 0x11D2,
+    data_4: [0xBA, 0x4B, 0x00, 0xA0, 0xC9, 0x3E, 0xC9, 0x3B],
+};
+
+pub const LEGACY_MBR_PARTITION: Guid = Guid {
+    data_1: 0x024DEE41,
+    data_2: 0x33E7,
+    data_3: 0x11D3,
+    data_4: [0x9D, 0x69, 0x00, 0x08, 0xC7, 0x81, 0xF3, 0x9F],
+};
+
+pub const BASIC_DATA_PARTITION: Guid = Guid {
+    data_1: 0xEBD0A0A2,
+    data_2: 0xB9E5,
+    data_3: 0x4433,
+    data_4: [0x87, 0xC0, 0x68, 0xB6, 0xB7, 0x26, 0x99, 0xC7],
+};
+
+pub const LINUX_FILESYSTEM_PARTITION: Guid = Guid {
+    data_1: 0x0FC63DAF,
+    data_2: 0x8483,
+    data_3: 0x4772,
+    data_4: [0x8E, 0x79, 0x3D, 0x69, 0xD8, 0x47, 0x7D, 0xE4],
+};
+
+// Bits 48 to 63 are defined by each partition type.
+
+flags!(
+    pub struct PartitionAttributes : u64 {
+        REQUIRED_PARTITION = 0x1;
+        NO_BLOCK_IO_PROTOCOL = 0x2;
+        LEGACY_BIOS_BOOTABLE = 0x4;
+    }
+);
+
+impl PartitionAttributes {
+    pub fn type_specific(self) -> u16 {
+        (u64::from(self) >> 48) as u16
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Partition {
+    // The 1 based position of the entry in the table as used by UEFI device paths.
+    pub number: u32,
+    pub type_guid: Guid,
+    pub unique_guid: Guid,
+    pub starting_lba: u64,
+    pub ending_lba: u64,
+    pub attributes: PartitionAttributes,
+    name: [u16; NAME_LEN],
+}
+
+impl Partition {
+    fn read(number: u32, source: &[u8]) -> Self {
+        let mut name = [0; NAME_LEN];
+
+        for (index, character) in name.iter_mut().enumerate() {
+            let offset = NAME_OFFSET + index * 2;
+            *character = u16::from_le_bytes([source[offset], source[offset + 1]]);
+        }
+
+        Self {
+            number,
+            type_guid: Guid::from_bytes(source[0..16].try_into().unwrap()),
+            unique_guid: Guid::from_bytes(source[16..32].try_into().unwrap()),
+            starting_lba: u64::from_le_bytes(source[32..40].try_into().unwrap()),
+            ending_lba: u64::from_le_bytes(source[40..48].try_into().unwrap()),
+            attributes: u64::from_le_bytes(source[48..56].try_into().unwrap()).into(),
+            name,
+        }
+    }
+
+    // The ending LBA is inclusive. None if the ending LBA is before the starting one or the
+    // partition covers every block a u64 can address.
+
+    pub fn block_count(&self) -> Option<u64> {
+        self.ending_lba
+            .checked_sub(self.starting_lba)?
+            .checked_add(1)
+    }
+
+    // The name ends at the first null character or fills the whole field.
+
+    pub fn name(&self) -> Result<String, Error> {
+        let len = self
+            .name
+            .iter()
+            .position(|&character| character == 0)
+            .unwrap_or(NAME_LEN);
+
+        String::from_usc2(&self.name[..len]).map_err(|_| Error::InvalidName)
+    }
+
+    pub fn name_ucs2(&self) -> &[u16] {
+        &self.name
+    }
+}
+
+// Goes through the entries of a partition table. Entries with the null type GUID are unused
+// and are skipped.
+
+#[derive(Clone, Debug)]
+pub struct Partitions<'a> {
+    source: &'a [u8],
+    entry_size: usize,
+    index: usize,
+}
+
+impl<'a> Partitions<'a> {
+    pub(crate) fn new(source: &'a [u8], entry_size: usize) -> Self {
+        Self {
+            source,
+            entry_size,
+            index: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Partitions<'a> {
+    type Item = Partition;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let offset = self.index * self.entry_size;
+            let entry = self.source.get(offset..offset + self.entry_size)?;
+
+            self.index += 1;
+
+            let partition = Partition::read(self.index as u32, entry);
+
+            if !partition.type_guid.is_null() {
+                return Some(partition);
+            }
+        }
+    }
+}
+
+impl<'a> FusedIterator for Partitions<'a> {}
@@ -0,0 +1,161 @@
+//**************************************************************************************************
+// table.rs                                                                                        *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::error::{Error, ReadError};
+use crate::header::Header;
+use crate::mbr::{Mbr, MBR_SIZE};
+use crate::partition::{Partition, Partitions};
+use alloc::vec;
+use alloc::vec::Vec;
+use uefi::Guid;
+
+// Access to the blocks of a whole disk. Implemented by anything the table can be read from such
+// as UEFI Disk I/O in the loader or a storage driver in the kernel.
+
+pub trait Disk {
+    type Error;
+
+    fn block_size(&self) -> usize;
+
+    fn last_block(&self) -> u64;
+
+    // The buffer is always a whole number of blocks.
+
+    fn read_blocks(&mut self, lba: u64, buffer: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct PartitionTable {
+    mbr: Mbr,
+    header: Header,
+    entries: Vec<u8>,
+    is_backup_used: bool,
+}
+
+impl PartitionTable {
+    // The backup table at the end of the disk is used if the primary one is damaged. The error of
+    // the primary table is returned if both are.
+
+    pub fn read<D: Disk>(disk: &mut D) -> Result<Self, ReadError<D::Error>> {
+        let block_size = disk.block_size();
+
+        if block_size < MBR_SIZE {
+            return Err(Error::InvalidBlockSize.into());
+        }
+
+        let mut block = vec![0; block_size];
+
+        disk.read_blocks(0, &mut block).map_err(ReadError::Disk)?;
+
+        let mbr = Mbr::read(&block)?;
+
+        if !mbr.is_protective() {
+            return Err(Error::NoProtectiveMbr.into());
+        }
+
+        let last_block = disk.last_block();
+
+        let primary_error = match Self::read_table(disk, 1, last_block) {
+            Ok((header, entries)) => {
+                return Ok(Self {
+                    mbr,
+                    header,
+                    entries,
+                    is_backup_used: false,
+                })
+            }
+            Err(error) => error,
+        };
+
+        match Self::read_table(disk, last_block, last_block) {
+            Ok((header, entries)) => Ok(Self {
+                mbr,
+                header,
+                entries,
+                is_backup_used: true,
+            }),
+            Err(ReadError::Table(_)) => Err(primary_error),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn read_table<D: Disk>(
+        disk: &mut D,
+        lba: u64,
+        last_block: u64,
+    ) -> Result<(Header, Vec<u8>), ReadError<D::Error>> {
+        let block_size = disk.block_size();
+
+        let mut block = vec![0; block_size];
+
+        disk.read_blocks(lba, &mut block).map_err(ReadError::Disk)?;
+
+        let header = Header::read(&block)?;
+
+        if header.my_lba != lba {
+            return Err(Error::WrongHeaderLocation.into());
+        }
+
+        if header.last_usable_lba > last_block {
+            return Err(Error::InvalidUsableRange.into());
+        }
+
+        let entries_block_count = header.entries_block_count(block_size);
+
+        let entries_end = header
+            .partition_entry_lba
+            .checked_add(entries_block_count)
+            .ok_or(Error::InvalidEntryLocation)?;
+
+        if header.partition_entry_lba < 2 || entries_end > last_block + 1 {
+            return Err(Error::InvalidEntryLocation.into());
+        }
+
+        let mut entries = vec![0; entries_block_count as usize * block_size];
+
+        disk.read_blocks(header.partition_entry_lba, &mut entries)
+            .map_err(ReadError::Disk)?;
+
+        header.partitions(&entries)?;
+
+        Ok((header, entries))
+    }
+
+    pub fn mbr(&self) -> &Mbr {
+        &self.mbr
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    pub fn disk_guid(&self) -> Guid {
+        self.header.disk_guid
+    }
+
+    // True if the primary table was damaged and the backup one was read instead.
+
+    pub fn is_backup_used(&self) -> bool {
+        self.is_backup_used
+    }
+
+    pub fn partitions(&self) -> Partitions {
+        Partitions::new(
+            &self.entries[..self.header.entries_len()],
+            self.header.size_of_partition_entry as usize,
+        )
+    }
+
+    pub fn find_by_unique_guid(&self, guid: Guid) -> Option<Partition> {
+        self.partitions()
+            .find(|partition| partition.unique_guid == guid)
+    }
+
+    pub fn partitions_of_type(&self, type_guid: Guid) -> impl Iterator<Item = Partition> + '_ {
+        self.partitions()
+            .filter(move |partition| partition.type_guid == type_guid)
+    }
+}
@@ -0,0 +1,30 @@
+//**************************************************************************************************
+// uefi_disk.rs                                                                                    *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::table::Disk;
+use uefi::io::block;
+
+// Disk I/O is used instead of Block I/O since it has no alignment requirements for the buffers.
+
+impl Disk for block::Disk {
+    type Error = uefi::Error;
+
+    fn block_size(&self) -> usize {
+        self.media().block_size() as usize
+    }
+
+    fn last_block(&self) -> u64 {
+        self.media().last_block()
+    }
+
+    fn read_blocks(&mut self, lba: u64, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let offset = lba
+            .checked_mul(self.block_size() as u64)
+            .ok_or(uefi::Error::InvalidArgument("lba"))?;
+
+        self.read(offset, buffer)
+    }
+}
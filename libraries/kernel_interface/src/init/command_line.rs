@@ -0,0 +1,79 @@
+//**************************************************************************************************
+// command_line.rs                                                                                 *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use core::fmt;
+use core::str;
+
+pub const COMMAND_LINE_CAPACITY: usize = 1024;
+
+// UTF-8 command line for the kernel. It is stored inline so the kernel can keep using it after
+// boot loader memory is reclaimed.
+
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct CommandLine {
+    pub data: [u8; COMMAND_LINE_CAPACITY],
+    pub len: usize,
+}
+
+impl CommandLine {
+    pub const fn new() -> Self {
+        Self {
+            data: [0; COMMAND_LINE_CAPACITY],
+            len: 0,
+        }
+    }
+
+    // Fails without changing the command line if the text is longer than the capacity.
+
+    pub fn set(&mut self, text: &str) -> Result<(), CommandLineTooLong> {
+        if text.len() > COMMAND_LINE_CAPACITY {
+            return Err(CommandLineTooLong);
+        }
+
+        self.data[..text.len()].copy_from_slice(text.as_bytes());
+        self.data[text.len()..].fill(0);
+        self.len = text.len();
+
+        Ok(())
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.data
+            .get(..self.len)
+            .and_then(|data| str::from_utf8(data).ok())
+            .unwrap_or("")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_str().is_empty()
+    }
+}
+
+impl Default for CommandLine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for CommandLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CommandLine").field(&self.as_str()).finish()
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct CommandLineTooLong;
+
+impl fmt::Display for CommandLineTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The command line is longer than {} bytes.",
+            COMMAND_LINE_CAPACITY
+        )
+    }
+}
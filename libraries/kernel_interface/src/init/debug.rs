@@ -12,6 +12,8 @@ pub struct DebugConfig {
     pub enabled: bool,
     pub port_number: Port,
     pub baud_divisor: BaudDivisor,
+    pub gdb_enabled: bool,
+    pub gdb_port_number: Port,
 }
 
 impl DebugConfig {
@@ -20,8 +22,17 @@ impl DebugConfig {
             enabled: cfg!(debug_assertions),
             port_number: Port::COM_1,
             baud_divisor: BaudDivisor::RATE_9600,
+            gdb_enabled: false,
+            gdb_port_number: Port::COM_2,
         }
     }
+
+    // GDB gets the debug port to itself when both use the same port so output does not corrupt
+    // its packets.
+
+    pub fn is_gdb_dedicated(&self) -> bool {
+        self.gdb_enabled && self.port_number == self.gdb_port_number
+    }
 }
 
 impl Default for DebugConfig {
@@ -0,0 +1,62 @@
+//**************************************************************************************************
+// framebuffer.rs                                                                                  *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use enums::c_enum;
+use memory::Address64;
+
+// Linear framebuffer set up by the boot loader. Every pixel is 4 bytes and each row is stride
+// pixels long which can be more than the width. The address is null if there is no framebuffer.
+
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct FramebufferInfo {
+    pub address: Address64,
+    pub size: usize,
+    pub width: u32,
+    pub height: u32,
+    pub stride: u32,
+    pub pixel_format: PixelFormat,
+    pub red_mask: u32,
+    pub green_mask: u32,
+    pub blue_mask: u32,
+}
+
+impl FramebufferInfo {
+    pub const fn new() -> Self {
+        Self {
+            address: Address64::null(),
+            size: 0,
+            width: 0,
+            height: 0,
+            stride: 0,
+            pixel_format: PixelFormat::RGB,
+            red_mask: 0,
+            green_mask: 0,
+            blue_mask: 0,
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        !self.address.is_null()
+    }
+}
+
+impl Default for FramebufferInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+c_enum!(
+    pub enum PixelFormat : u32 {
+        // Red in the first byte, then green and blue.
+        RGB = 0,
+        // Blue in the first byte, then green and red.
+        BGR = 1,
+        // Channels are described by the masks.
+        BIT_MASK = 2,
+    }
+);
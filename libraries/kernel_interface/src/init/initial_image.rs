@@ -0,0 +1,37 @@
+//**************************************************************************************************
+// initial_image.rs                                                                                *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use memory::Address64;
+
+// Physical location of the initial image loaded by the boot loader. It is stored in memory of the
+// initial image memory type so it is kept until the kernel releases it. The address is null if no
+// initial image was loaded.
+
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct InitialImageInfo {
+    pub address: Address64,
+    pub len: usize,
+}
+
+impl InitialImageInfo {
+    pub const fn new() -> Self {
+        Self {
+            address: Address64::null(),
+            len: 0,
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        !self.address.is_null() && self.len != 0
+    }
+}
+
+impl Default for InitialImageInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
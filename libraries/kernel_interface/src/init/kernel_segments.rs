@@ -0,0 +1,88 @@
+//**************************************************************************************************
+// kernel_segments.rs                                                                              *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use core::fmt;
+use memory::Address64;
+
+pub const KERNEL_SEGMENTS_CAPACITY: usize = 8;
+
+// Virtual location and permissions of a loadable segment of the kernel with the slide applied.
+
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct KernelSegment {
+    pub start: Address64,
+    pub len: usize,
+    pub writable: bool,
+    pub executable: bool,
+}
+
+impl KernelSegment {
+    pub const fn new() -> Self {
+        Self {
+            start: Address64::null(),
+            len: 0,
+            writable: false,
+            executable: false,
+        }
+    }
+}
+
+impl Default for KernelSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The segments the boot loader protected the kernel with in the order of their addresses so the
+// kernel can protect its own mapping the same way.
+
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct KernelSegments {
+    pub data: [KernelSegment; KERNEL_SEGMENTS_CAPACITY],
+    pub len: usize,
+}
+
+impl KernelSegments {
+    pub const fn new() -> Self {
+        Self {
+            data: [KernelSegment::new(); KERNEL_SEGMENTS_CAPACITY],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, segment: KernelSegment) -> Result<(), TooManyKernelSegments> {
+        let slot = self.data.get_mut(self.len).ok_or(TooManyKernelSegments)?;
+        *slot = segment;
+        self.len += 1;
+
+        Ok(())
+    }
+
+    pub fn as_slice(&self) -> &[KernelSegment] {
+        self.data.get(..self.len).unwrap_or(&[])
+    }
+}
+
+impl Default for KernelSegments {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct TooManyKernelSegments;
+
+impl fmt::Display for TooManyKernelSegments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The kernel has more than {} loadable segments.",
+            KERNEL_SEGMENTS_CAPACITY
+        )
+    }
+}
@@ -153,6 +153,10 @@ pub const KERNEL_UEFI_MEMORY_TYPE: UefiMemoryType = UefiMemoryType::new(0x800000
 
 pub const KERNEL_STACK_UEFI_MEMORY_TYPE: UefiMemoryType = UefiMemoryType::new(0x80000001);
 
+pub const KERNEL_SYMBOLS_UEFI_MEMORY_TYPE: UefiMemoryType = UefiMemoryType::new(0x80000002);
+
+pub const INITIAL_IMAGE_UEFI_MEMORY_TYPE: UefiMemoryType = UefiMemoryType::new(0x80000003);
+
 c_enum!(
     pub enum MemoryType : u32 {
         // Usable section.
@@ -169,6 +173,8 @@ c_enum!(
         MEMORY_MAPPED_IO = 6 | MEMORY_UNUSABLE_BIT,
         ACPI_NVS = 7 | MEMORY_UNUSABLE_BIT,
         ACPI_RECLAIM = 8 | MEMORY_UNUSABLE_BIT,
+        KERNEL_SYMBOLS = 9 | MEMORY_UNUSABLE_BIT,
+        INITIAL_IMAGE = 10 | MEMORY_UNUSABLE_BIT,
     }
 );
 
@@ -199,6 +205,8 @@ impl From<UefiMemoryType> for MemoryType {
             UefiMemoryType::PERSISTENT_MEMORY => MemoryType::PERSISTENT,
             KERNEL_UEFI_MEMORY_TYPE => MemoryType::KERNEL,
             KERNEL_STACK_UEFI_MEMORY_TYPE => MemoryType::KERNEL_STACK,
+            KERNEL_SYMBOLS_UEFI_MEMORY_TYPE => MemoryType::KERNEL_SYMBOLS,
+            INITIAL_IMAGE_UEFI_MEMORY_TYPE => MemoryType::INITIAL_IMAGE,
             _ => MemoryType::RESERVED,
         }
     }
@@ -4,18 +4,37 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+mod command_line;
 mod debug;
+mod framebuffer;
+mod initial_image;
+mod kernel_segments;
 mod memory_map;
+mod symbols;
 mod system;
+mod uefi_runtime;
 
+pub use command_line::*;
 pub use debug::*;
+pub use framebuffer::*;
+pub use initial_image::*;
+pub use kernel_segments::*;
 pub use memory_map::*;
+pub use symbols::*;
 pub use system::*;
+pub use uefi_runtime::*;
 
 pub type EntryFunction = unsafe extern "sysv64" fn(args: *const Args);
 
 pub const KERNEL_VIRTUAL_START: u64 = 0xffffffff80000000;
 
+// The kernel is position independent and the boot loader moves it up from KERNEL_VIRTUAL_START by
+// a random slide. The slide is a multiple of the alignment below the limit.
+
+pub const KERNEL_SLIDE_ALIGNMENT: u64 = 0x200000;
+
+pub const KERNEL_SLIDE_LIMIT: u64 = 0x40000000;
+
 pub const STACK_PAGES: u64 = 5;
 
 pub const STACK_SIZE: u64 = STACK_PAGES * 4096;
@@ -31,10 +50,17 @@ pub struct Args {
     pub system_info: SystemInfo,
     pub memory_map: MemoryMap,
     pub debug_config: DebugConfig,
+    pub symbols: SymbolInfo,
+    pub framebuffer: FramebufferInfo,
+    pub command_line: CommandLine,
+    pub initial_image: InitialImageInfo,
+    // Offset of the kernel image from the address it was linked at.
+    pub kernel_slide: u64,
+    pub kernel_segments: KernelSegments,
 }
 
 impl Args {
-    pub const CURRENT_VERSION: u32 = 1;
+    pub const CURRENT_VERSION: u32 = 10;
 
     pub const fn new() -> Self {
         Args {
@@ -42,6 +68,12 @@ impl Args {
             system_info: SystemInfo::new(),
             memory_map: MemoryMap::new(),
             debug_config: DebugConfig::new(),
+            symbols: SymbolInfo::new(),
+            framebuffer: FramebufferInfo::new(),
+            command_line: CommandLine::new(),
+            initial_image: InitialImageInfo::new(),
+            kernel_slide: 0,
+            kernel_segments: KernelSegments::new(),
         }
     }
 
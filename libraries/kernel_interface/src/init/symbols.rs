@@ -0,0 +1,41 @@
+//**************************************************************************************************
+// symbols.rs                                                                                      *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use memory::Address64;
+
+// Physical location of the kernel's ELF symbol table and its linked string table. Both are copied
+// by the boot loader into memory of the kernel symbols memory type and are null if the kernel
+// binary was stripped.
+
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SymbolInfo {
+    pub symbol_table: Address64,
+    pub symbol_table_len: usize,
+    pub string_table: Address64,
+    pub string_table_len: usize,
+}
+
+impl SymbolInfo {
+    pub const fn new() -> Self {
+        Self {
+            symbol_table: Address64::null(),
+            symbol_table_len: 0,
+            string_table: Address64::null(),
+            string_table_len: 0,
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        !self.symbol_table.is_null() && !self.string_table.is_null()
+    }
+}
+
+impl Default for SymbolInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
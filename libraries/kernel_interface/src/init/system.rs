@@ -4,6 +4,7 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+use super::UefiRuntimeMap;
 pub use acpi::{Interface as AcpiInterface, RsdpIter as AcpiRootEntryIter};
 use acpi::{RootEntry, Rsdt, Xsdt};
 use memory::{Address32, Address64};
@@ -14,7 +15,12 @@ use uefi::ffi::runtime;
 pub struct SystemInfo {
     pub rsdt: Address32,
     pub xsdt: Address64,
+    // Physical address of the UEFI runtime services table. It is null if the system was not
+    // booted by UEFI.
     pub uefi_runtime: Address64,
+    pub uefi_runtime_map: UefiRuntimeMap,
+    // Physical address of the SMBIOS entry point. The 64 bit SMBIOS 3 entry point is preferred.
+    pub smbios: Address64,
 }
 
 impl SystemInfo {
@@ -23,6 +29,8 @@ impl SystemInfo {
             rsdt: Address32::null(),
             xsdt: Address64::null(),
             uefi_runtime: Address64::null(),
+            uefi_runtime_map: UefiRuntimeMap::new(),
+            smbios: Address64::null(),
         }
     }
 
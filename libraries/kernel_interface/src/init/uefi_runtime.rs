@@ -0,0 +1,62 @@
+//**************************************************************************************************
+// uefi_runtime.rs                                                                                 *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use alloc::vec::Vec;
+use core::mem;
+use core::ptr;
+use core::slice;
+pub use uefi::memory::MemoryDescriptor as UefiMemoryDescriptor;
+
+// The UEFI memory map entries with the runtime attribute. They keep the UEFI layout so the kernel
+// can fill in the virtual addresses it maps them to and pass them to SetVirtualAddressMap.
+
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct UefiRuntimeMap {
+    pub ptr: *mut UefiMemoryDescriptor,
+    pub len: usize,
+    pub capacity: usize,
+}
+
+impl UefiRuntimeMap {
+    pub const fn new() -> Self {
+        Self {
+            ptr: ptr::null_mut(),
+            len: 0,
+            capacity: 0,
+        }
+    }
+
+    pub fn from_vec(mut vector: Vec<UefiMemoryDescriptor>) -> Self {
+        let map = Self {
+            ptr: vector.as_mut_ptr(),
+            len: vector.len(),
+            capacity: vector.capacity(),
+        };
+        mem::forget(vector);
+        map
+    }
+
+    pub unsafe fn into_vec(self) -> Vec<UefiMemoryDescriptor> {
+        Vec::from_raw_parts(self.ptr, self.len, self.capacity)
+    }
+
+    pub unsafe fn as_slice<'a>(self) -> &'a [UefiMemoryDescriptor] {
+        slice::from_raw_parts(self.ptr, self.len)
+    }
+
+    pub unsafe fn as_mut_slice<'a>(self) -> &'a mut [UefiMemoryDescriptor] {
+        slice::from_raw_parts_mut(self.ptr, self.len)
+    }
+}
+
+unsafe impl Send for UefiRuntimeMap {}
+
+impl Default for UefiRuntimeMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
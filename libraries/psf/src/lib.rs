@@ -0,0 +1,172 @@
+//**************************************************************************************************
+// lib.rs                                                                                          *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+#![no_std]
+
+use core::convert::TryInto;
+use core::fmt;
+use core::str;
+
+// The console font built into the loader and kernel. It is generated by scripts/make_font.py.
+
+pub static BUILTIN_FONT: &[u8] = include_bytes!("builtin.psf");
+
+const PSF2_MAGIC: u32 = 0x864AB572;
+
+const HEADER_SIZE: usize = 32;
+
+const HAS_UNICODE_TABLE: u32 = 0x1;
+
+// Entries in the unicode table end with this byte. Sequences of multiple characters start with
+// 0xFE and are not used for lookups.
+
+const UNICODE_ENTRY_END: u8 = 0xFF;
+
+const UNICODE_SEQUENCE_START: u8 = 0xFE;
+
+// A PC Screen Font version 2 bitmap font. Each glyph is a bitmap of height rows where every row
+// is padded to a whole byte and the most significant bit is the leftmost pixel.
+
+#[derive(Copy, Clone, Debug)]
+pub struct Font<'a> {
+    glyphs: &'a [u8],
+    unicode_table: Option<&'a [u8]>,
+    glyph_count: usize,
+    glyph_size: usize,
+    width: usize,
+    height: usize,
+}
+
+impl<'a> Font<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, Error> {
+        if data.len() < HEADER_SIZE {
+            return Err(Error::Truncated);
+        }
+
+        let field = |index: usize| {
+            let offset = index * 4;
+            u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+        };
+
+        if field(0) != PSF2_MAGIC {
+            return Err(Error::InvalidMagic);
+        }
+
+        if field(1) != 0 {
+            return Err(Error::UnsupportedVersion);
+        }
+
+        let header_size = field(2) as usize;
+        let flags = field(3);
+        let glyph_count = field(4) as usize;
+        let glyph_size = field(5) as usize;
+        let height = field(6) as usize;
+        let width = field(7) as usize;
+
+        if header_size < HEADER_SIZE
+            || width == 0
+            || height == 0
+            || glyph_count == 0
+            || glyph_size != height * ((width + 7) / 8)
+        {
+            return Err(Error::InvalidHeader);
+        }
+
+        let glyphs_end = glyph_count
+            .checked_mul(glyph_size)
+            .and_then(|len| len.checked_add(header_size))
+            .ok_or(Error::InvalidHeader)?;
+
+        if data.len() < glyphs_end {
+            return Err(Error::Truncated);
+        }
+
+        let unicode_table = if flags & HAS_UNICODE_TABLE != 0 {
+            Some(&data[glyphs_end..])
+        } else {
+            None
+        };
+
+        Ok(Self {
+            glyphs: &data[header_size..glyphs_end],
+            unicode_table,
+            glyph_count,
+            glyph_size,
+            width,
+            height,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn bytes_per_row(&self) -> usize {
+        (self.width + 7) / 8
+    }
+
+    pub fn glyph(&self, character: char) -> Option<&'a [u8]> {
+        let index = self.glyph_index(character)?;
+        let start = index * self.glyph_size;
+
+        Some(&self.glyphs[start..start + self.glyph_size])
+    }
+
+    // Fonts without a unicode table map characters directly to glyph indices.
+
+    fn glyph_index(&self, character: char) -> Option<usize> {
+        let table = match self.unicode_table {
+            Some(table) => table,
+            None => {
+                let index = character as usize;
+                return if index < self.glyph_count {
+                    Some(index)
+                } else {
+                    None
+                };
+            }
+        };
+
+        // Neither marker byte can appear in UTF-8 so entries can be split on them directly.
+
+        table
+            .split(|&byte| byte == UNICODE_ENTRY_END)
+            .take(self.glyph_count)
+            .position(|entry| {
+                let characters = entry
+                    .split(|&byte| byte == UNICODE_SEQUENCE_START)
+                    .next()
+                    .unwrap_or(&[]);
+
+                str::from_utf8(characters).map_or(false, |characters| {
+                    characters.chars().any(|c| c == character)
+                })
+            })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    Truncated,
+    InvalidMagic,
+    UnsupportedVersion,
+    InvalidHeader,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Truncated => write!(f, "The font data is truncated."),
+            Error::InvalidMagic => write!(f, "The font is not a PSF2 font."),
+            Error::UnsupportedVersion => write!(f, "The PSF2 version is not supported."),
+            Error::InvalidHeader => write!(f, "The font header is invalid."),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//**************************************************************************************************
+// bios.rs                                                                                         *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::{Error, Structure, StructureType};
+
+// Type 0. Describes the platform firmware. On UEFI systems the "BIOS" is the UEFI firmware.
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct BiosInformation<'a> {
+    pub vendor: Option<&'a str>,
+    pub version: Option<&'a str>,
+    pub release_date: Option<&'a str>,
+    pub rom_size: Option<u64>,
+    pub characteristics: Option<u64>,
+    pub release: Option<(u8, u8)>,
+    pub embedded_controller_release: Option<(u8, u8)>,
+}
+
+impl<'a> BiosInformation<'a> {
+    pub fn read(structure: &Structure<'a>) -> Result<Self, Error> {
+        structure.expect_type(StructureType::BIOS_INFORMATION)?;
+
+        Ok(Self {
+            vendor: structure.string_at(0x04),
+            version: structure.string_at(0x05),
+            release_date: structure.string_at(0x08),
+            rom_size: Self::read_rom_size(structure),
+            characteristics: structure.u64(0x0A),
+            release: Self::read_release(structure, 0x14),
+            embedded_controller_release: Self::read_release(structure, 0x16),
+        })
+    }
+
+    // The size is given in 64 KiB units minus one. Larger ROMs use the extended size which is in
+    // MiB or GiB depending on its top two bits.
+
+    fn read_rom_size(structure: &Structure) -> Option<u64> {
+        const KIB: u64 = 1024;
+        const MIB: u64 = 1024 * KIB;
+        const GIB: u64 = 1024 * MIB;
+
+        match structure.u8(0x09)? {
+            0xFF => {
+                let extended_size = structure.u16(0x18)?;
+                let size = u64::from(extended_size & 0x3FFF);
+
+                match extended_size >> 14 {
+                    0 => Some(size * MIB),
+                    1 => Some(size * GIB),
+                    _ => None,
+                }
+            }
+            size => Some((u64::from(size) + 1) * 64 * KIB),
+        }
+    }
+
+    // A release of 0xFF.0xFF means the release is not known.
+
+    fn read_release(structure: &Structure, offset: usize) -> Option<(u8, u8)> {
+        match (structure.u8(offset)?, structure.u8(offset + 1)?) {
+            (0xFF, 0xFF) => None,
+            release => Some(release),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//**************************************************************************************************
+// entry_point.rs                                                                                  *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::Error;
+use core::convert::TryInto;
+
+pub const ANCHOR_2: &[u8; 4] = b"_SM_";
+
+pub const ANCHOR_3: &[u8; 5] = b"_SM3_";
+
+const INTERMEDIATE_ANCHOR: &[u8; 5] = b"_DMI_";
+
+const ENTRY_POINT_2_LEN: usize = 0x1F;
+
+const ENTRY_POINT_3_LEN: usize = 0x18;
+
+// The 32 bit entry point is found through the SMBIOS configuration table and the 64 bit entry
+// point through the SMBIOS 3 one. Either locates the structure table.
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum EntryPoint {
+    Two(EntryPoint2),
+    Three(EntryPoint3),
+}
+
+impl EntryPoint {
+    // Enough bytes to read either entry point.
+
+    pub const MAX_LEN: usize = ENTRY_POINT_2_LEN;
+
+    pub fn read(source: &[u8]) -> Result<Self, Error> {
+        if source.starts_with(ANCHOR_3) {
+            EntryPoint3::read(source).map(EntryPoint::Three)
+        } else if source.starts_with(ANCHOR_2) {
+            EntryPoint2::read(source).map(EntryPoint::Two)
+        } else {
+            Err(Error::InvalidAnchor)
+        }
+    }
+
+    pub fn version(&self) -> Version {
+        match self {
+            EntryPoint::Two(entry_point) => entry_point.version,
+            EntryPoint::Three(entry_point) => entry_point.version,
+        }
+    }
+
+    pub fn table_address(&self) -> u64 {
+        match self {
+            EntryPoint::Two(entry_point) => u64::from(entry_point.table_address),
+            EntryPoint::Three(entry_point) => entry_point.table_address,
+        }
+    }
+
+    // The 64 bit entry point only gives the maximum size. The table ends at its end-of-table
+    // structure which may come earlier.
+
+    pub fn table_len(&self) -> usize {
+        match self {
+            EntryPoint::Two(entry_point) => entry_point.table_len as usize,
+            EntryPoint::Three(entry_point) => entry_point.table_max_len as usize,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl Version {
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct EntryPoint2 {
+    pub version: Version,
+    pub max_structure_len: u16,
+    pub table_len: u16,
+    pub table_address: u32,
+    pub structure_count: u16,
+}
+
+impl EntryPoint2 {
+    pub fn read(source: &[u8]) -> Result<Self, Error> {
+        let len = *source.get(5).ok_or(Error::Truncated)? as usize;
+
+        if len < ENTRY_POINT_2_LEN {
+            return Err(Error::InvalidLength);
+        }
+
+        let source = source.get(..len).ok_or(Error::Truncated)?;
+
+        if &source[..ANCHOR_2.len()] != ANCHOR_2 || &source[0x10..0x15] != INTERMEDIATE_ANCHOR {
+            return Err(Error::InvalidAnchor);
+        }
+
+        // The intermediate entry point from the legacy DMI standard has its own checksum.
+
+        if checksum(source) != 0 || checksum(&source[0x10..ENTRY_POINT_2_LEN]) != 0 {
+            return Err(Error::ChecksumMismatch);
+        }
+
+        Ok(Self {
+            version: Version::new(source[6], source[7]),
+            max_structure_len: read_u16(source, 0x08),
+            table_len: read_u16(source, 0x16),
+            table_address: u32::from_le_bytes(source[0x18..0x1C].try_into().unwrap()),
+            structure_count: read_u16(source, 0x1C),
+        })
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct EntryPoint3 {
+    pub version: Version,
+    pub document_revision: u8,
+    pub entry_point_revision: u8,
+    pub table_max_len: u32,
+    pub table_address: u64,
+}
+
+impl EntryPoint3 {
+    pub fn read(source: &[u8]) -> Result<Self, Error> {
+        let len = *source.get(6).ok_or(Error::Truncated)? as usize;
+
+        if len < ENTRY_POINT_3_LEN {
+            return Err(Error::InvalidLength);
+        }
+
+        let source = source.get(..len).ok_or(Error::Truncated)?;
+
+        if &source[..ANCHOR_3.len()] != ANCHOR_3 {
+            return Err(Error::InvalidAnchor);
+        }
+
+        if checksum(source) != 0 {
+            return Err(Error::ChecksumMismatch);
+        }
+
+        Ok(Self {
+            version: Version::new(source[7], source[8]),
+            document_revision: source[9],
+            entry_point_revision: source[10],
+            table_max_len: u32::from_le_bytes(source[0x0C..0x10].try_into().unwrap()),
+            table_address: u64::from_le_bytes(source[0x10..0x18].try_into().unwrap()),
+        })
+    }
+}
+
+fn read_u16(source: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([source[offset], source[offset + 1]])
+}
+
+// Every byte of a checksummed area adds up to zero.
+
+fn checksum(source: &[u8]) -> u8 {
+    source.iter().fold(0, |sum, &byte| sum.wrapping_add(byte))
+}
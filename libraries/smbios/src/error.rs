@@ -0,0 +1,34 @@
+//**************************************************************************************************
+// error.rs                                                                                        *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use core::fmt;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Error {
+    Truncated,
+    InvalidAnchor,
+    InvalidLength,
+    ChecksumMismatch,
+    UnterminatedStrings,
+    WrongType,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Truncated => write!(f, "The source ends in the middle of a structure."),
+            Error::InvalidAnchor => {
+                write!(f, "The entry point does not start with an SMBIOS anchor.")
+            }
+            Error::InvalidLength => write!(f, "A length field is too small for its structure."),
+            Error::ChecksumMismatch => write!(f, "The entry point does not match its checksum."),
+            Error::UnterminatedStrings => {
+                write!(f, "The string set of a structure is not terminated.")
+            }
+            Error::WrongType => write!(f, "The structure is not of the requested type."),
+        }
+    }
+}
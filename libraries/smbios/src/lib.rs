@@ -0,0 +1,90 @@
+//**************************************************************************************************
+// lib.rs                                                                                          *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+#![no_std]
+
+mod bios;
+mod entry_point;
+mod error;
+mod memory_device;
+mod processor;
+mod structure;
+mod system;
+
+pub use bios::*;
+pub use entry_point::*;
+pub use error::*;
+pub use memory_device::*;
+pub use processor::*;
+pub use structure::*;
+pub use system::*;
+
+// Reader for SMBIOS structure tables. Structures borrow their formatted areas and strings from the
+// table so nothing is copied. Every length is checked before it is used so a broken table ends
+// iteration with an error instead of a panic.
+
+#[derive(Copy, Clone, Debug)]
+pub struct Table<'a>(&'a [u8]);
+
+impl<'a> Table<'a> {
+    pub const fn new(source: &'a [u8]) -> Self {
+        Table(source)
+    }
+
+    pub fn source(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn structures(&self) -> Structures<'a> {
+        Structures::new(self.0)
+    }
+
+    pub fn structures_of_type(
+        &self,
+        structure_type: StructureType,
+    ) -> impl Iterator<Item = Result<Structure<'a>, Error>> {
+        self.structures().filter(move |structure| match structure {
+            Ok(structure) => structure.structure_type == structure_type,
+            Err(_) => true,
+        })
+    }
+
+    pub fn bios_information(&self) -> Result<Option<BiosInformation<'a>>, Error> {
+        self.first_of_type(StructureType::BIOS_INFORMATION)?
+            .map(|structure| BiosInformation::read(&structure))
+            .transpose()
+    }
+
+    pub fn system_information(&self) -> Result<Option<SystemInformation<'a>>, Error> {
+        self.first_of_type(StructureType::SYSTEM_INFORMATION)?
+            .map(|structure| SystemInformation::read(&structure))
+            .transpose()
+    }
+
+    pub fn processors(&self) -> impl Iterator<Item = Result<ProcessorInformation<'a>, Error>> {
+        self.structures_of_type(StructureType::PROCESSOR_INFORMATION)
+            .map(|structure| ProcessorInformation::read(&structure?))
+    }
+
+    pub fn memory_devices(&self) -> impl Iterator<Item = Result<MemoryDevice<'a>, Error>> {
+        self.structures_of_type(StructureType::MEMORY_DEVICE)
+            .map(|structure| MemoryDevice::read(&structure?))
+    }
+
+    fn first_of_type(&self, structure_type: StructureType) -> Result<Option<Structure<'a>>, Error> {
+        self.structures_of_type(structure_type).next().transpose()
+    }
+}
+
+// Many fields use zero for an unknown value.
+
+fn non_zero(value: u16) -> Option<u16> {
+    if value == 0 {
+        None
+    } else {
+        Some(value)
+    }
+}
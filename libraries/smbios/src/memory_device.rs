@@ -0,0 +1,100 @@
+//**************************************************************************************************
+// memory_device.rs                                                                                *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::{non_zero, Error, Structure, StructureType};
+use enums::c_enum;
+
+c_enum!(
+    pub enum MemoryDeviceType : u8 {
+        OTHER = 0x01,
+        UNKNOWN = 0x02,
+        DRAM = 0x03,
+        EDRAM = 0x04,
+        VRAM = 0x05,
+        SRAM = 0x06,
+        RAM = 0x07,
+        ROM = 0x08,
+        FLASH = 0x09,
+        EEPROM = 0x0A,
+        FEPROM = 0x0B,
+        EPROM = 0x0C,
+        CDRAM = 0x0D,
+        THREE_DRAM = 0x0E,
+        SDRAM = 0x0F,
+        SGRAM = 0x10,
+        RDRAM = 0x11,
+        DDR = 0x12,
+        DDR2 = 0x13,
+        DDR2_FB_DIMM = 0x14,
+        DDR3 = 0x18,
+        FBD2 = 0x19,
+        DDR4 = 0x1A,
+        LPDDR = 0x1B,
+        LPDDR2 = 0x1C,
+        LPDDR3 = 0x1D,
+        LPDDR4 = 0x1E,
+        LOGICAL_NON_VOLATILE = 0x1F,
+        HBM = 0x20,
+        HBM2 = 0x21,
+        DDR5 = 0x22,
+        LPDDR5 = 0x23,
+    }
+);
+
+// Type 17. Describes one memory slot such as a DIMM socket. Empty slots are listed with a size of
+// zero.
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct MemoryDevice<'a> {
+    pub device_locator: Option<&'a str>,
+    pub bank_locator: Option<&'a str>,
+    pub size: Option<u64>,
+    pub memory_type: MemoryDeviceType,
+    pub speed_mts: Option<u16>,
+    pub configured_speed_mts: Option<u16>,
+    pub manufacturer: Option<&'a str>,
+    pub serial_number: Option<&'a str>,
+    pub part_number: Option<&'a str>,
+}
+
+impl<'a> MemoryDevice<'a> {
+    pub fn read(structure: &Structure<'a>) -> Result<Self, Error> {
+        structure.expect_type(StructureType::MEMORY_DEVICE)?;
+
+        Ok(Self {
+            device_locator: structure.string_at(0x10),
+            bank_locator: structure.string_at(0x11),
+            size: Self::read_size(structure),
+            memory_type: structure
+                .u8(0x12)
+                .map_or(MemoryDeviceType::UNKNOWN, MemoryDeviceType::new),
+            speed_mts: structure.u16(0x15).and_then(non_zero),
+            configured_speed_mts: structure.u16(0x20).and_then(non_zero),
+            manufacturer: structure.string_at(0x17),
+            serial_number: structure.string_at(0x18),
+            part_number: structure.string_at(0x1A),
+        })
+    }
+
+    pub fn is_installed(&self) -> bool {
+        self.size != Some(0)
+    }
+
+    // The size in bytes. The top bit selects KiB instead of MiB. Sizes of 32 GiB or more are
+    // stored in the extended size in MiB. None means the size is unknown.
+
+    fn read_size(structure: &Structure) -> Option<u64> {
+        const KIB: u64 = 1024;
+        const MIB: u64 = 1024 * KIB;
+
+        match structure.u16(0x0C)? {
+            0xFFFF => None,
+            0x7FFF => Some(u64::from(structure.u32(0x1C)? & 0x7FFF_FFFF) * MIB),
+            size if size & 0x8000 != 0 => Some(u64::from(size & 0x7FFF) * KIB),
+            size => Some(u64::from(size) * MIB),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//**************************************************************************************************
+// processor.rs                                                                                    *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::{non_zero, Error, Structure, StructureType};
+
+// Type 4. Describes one processor socket. Empty sockets are listed as well.
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct ProcessorInformation<'a> {
+    pub socket_designation: Option<&'a str>,
+    pub manufacturer: Option<&'a str>,
+    pub version: Option<&'a str>,
+    pub is_populated: bool,
+    pub max_speed_mhz: Option<u16>,
+    pub current_speed_mhz: Option<u16>,
+    pub core_count: Option<u16>,
+    pub enabled_core_count: Option<u16>,
+    pub thread_count: Option<u16>,
+    pub serial_number: Option<&'a str>,
+    pub part_number: Option<&'a str>,
+}
+
+impl<'a> ProcessorInformation<'a> {
+    pub fn read(structure: &Structure<'a>) -> Result<Self, Error> {
+        structure.expect_type(StructureType::PROCESSOR_INFORMATION)?;
+
+        Ok(Self {
+            socket_designation: structure.string_at(0x04),
+            manufacturer: structure.string_at(0x07),
+            version: structure.string_at(0x10),
+            is_populated: structure
+                .u8(0x18)
+                .map_or(false, |status| status & 0x40 != 0),
+            max_speed_mhz: structure.u16(0x14).and_then(non_zero),
+            current_speed_mhz: structure.u16(0x16).and_then(non_zero),
+            core_count: Self::read_count(structure, 0x23, 0x2A),
+            enabled_core_count: Self::read_count(structure, 0x24, 0x2C),
+            thread_count: Self::read_count(structure, 0x25, 0x2E),
+            serial_number: structure.string_at(0x20),
+            part_number: structure.string_at(0x22),
+        })
+    }
+
+    // Counts above 254 are stored in a second 16 bit field that was added in version 3.0.
+
+    fn read_count(structure: &Structure, offset: usize, offset_2: usize) -> Option<u16> {
+        match structure.u8(offset)? {
+            0 => None,
+            0xFF => structure.u16(offset_2).and_then(non_zero),
+            count => Some(u16::from(count)),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//**************************************************************************************************
+// structure.rs                                                                                    *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::Error;
+use core::str;
+use enums::c_enum;
+
+pub const HEADER_LEN: usize = 4;
+
+c_enum!(
+    pub enum StructureType : u8 {
+        BIOS_INFORMATION = 0,
+        SYSTEM_INFORMATION = 1,
+        BASEBOARD_INFORMATION = 2,
+        SYSTEM_ENCLOSURE = 3,
+        PROCESSOR_INFORMATION = 4,
+        CACHE_INFORMATION = 7,
+        SYSTEM_SLOTS = 9,
+        PHYSICAL_MEMORY_ARRAY = 16,
+        MEMORY_DEVICE = 17,
+        MEMORY_ARRAY_MAPPED_ADDRESS = 19,
+        SYSTEM_BOOT_INFORMATION = 32,
+        INACTIVE = 126,
+        END_OF_TABLE = 127,
+    }
+);
+
+// A structure is a formatted area starting with the header followed by a set of strings. Fields
+// of the formatted area refer to strings by their number starting from 1. Structures of older
+// versions are shorter so fields past the formatted area read as None.
+
+#[derive(Copy, Clone, Debug)]
+pub struct Structure<'a> {
+    pub structure_type: StructureType,
+    pub handle: u16,
+    formatted: &'a [u8],
+    strings: &'a [u8],
+}
+
+impl<'a> Structure<'a> {
+    pub fn formatted(&self) -> &'a [u8] {
+        self.formatted
+    }
+
+    pub fn u8(&self, offset: usize) -> Option<u8> {
+        self.formatted.get(offset).copied()
+    }
+
+    pub fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes = self.formatted.get(offset..offset + 2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes = self.formatted.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn u64(&self, offset: usize) -> Option<u64> {
+        Some(u64::from(self.u32(offset)?) | (u64::from(self.u32(offset + 4)?) << 32))
+    }
+
+    // Returns the string with the number. Number 0 means there is no string. Strings that are
+    // not UTF-8 are treated as missing.
+
+    pub fn string(&self, number: u8) -> Option<&'a str> {
+        let index = usize::from(number).checked_sub(1)?;
+
+        let bytes = self
+            .strings
+            .split(|&byte| byte == 0)
+            .take_while(|string| !string.is_empty())
+            .nth(index)?;
+
+        str::from_utf8(bytes).ok()
+    }
+
+    // Returns the string whose number is stored at the offset.
+
+    pub fn string_at(&self, offset: usize) -> Option<&'a str> {
+        self.string(self.u8(offset)?)
+    }
+
+    pub fn strings(&self) -> impl Iterator<Item = &'a [u8]> {
+        self.strings
+            .split(|&byte| byte == 0)
+            .take_while(|string| !string.is_empty())
+    }
+
+    pub(crate) fn expect_type(&self, structure_type: StructureType) -> Result<(), Error> {
+        if self.structure_type == structure_type {
+            Ok(())
+        } else {
+            Err(Error::WrongType)
+        }
+    }
+}
+
+// Iterates over the structures of a table until the end-of-table structure or the end of the
+// table. The iterator ends after the first error.
+
+#[derive(Clone, Debug)]
+pub struct Structures<'a> {
+    source: &'a [u8],
+    offset: usize,
+    finished: bool,
+}
+
+impl<'a> Structures<'a> {
+    pub const fn new(source: &'a [u8]) -> Self {
+        Self {
+            source,
+            offset: 0,
+            finished: false,
+        }
+    }
+
+    fn read_structure(&mut self) -> Result<Option<Structure<'a>>, Error> {
+        let remaining = match self.source.get(self.offset..) {
+            Some(remaining) if remaining.len() >= HEADER_LEN => remaining,
+            _ => return Ok(None),
+        };
+
+        let structure_type = StructureType::new(remaining[0]);
+        let len = remaining[1] as usize;
+        let handle = u16::from_le_bytes([remaining[2], remaining[3]]);
+
+        if len < HEADER_LEN {
+            return Err(Error::InvalidLength);
+        }
+
+        let formatted = remaining.get(..len).ok_or(Error::Truncated)?;
+
+        // The string set ends with two null bytes. A structure without strings still has both.
+
+        let strings_end = remaining[len..]
+            .windows(2)
+            .position(|bytes| bytes == [0, 0])
+            .ok_or(Error::UnterminatedStrings)?;
+
+        let strings = &remaining[len..len + strings_end + 1];
+
+        self.offset += len + strings_end + 2;
+
+        if structure_type == StructureType::END_OF_TABLE {
+            self.finished = true;
+        }
+
+        Ok(Some(Structure {
+            structure_type,
+            handle,
+            formatted,
+            strings,
+        }))
+    }
+}
+
+impl<'a> Iterator for Structures<'a> {
+    type Item = Result<Structure<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.read_structure() {
+            Ok(Some(structure)) => Some(Ok(structure)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(error) => {
+                self.finished = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
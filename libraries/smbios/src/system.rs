@@ -0,0 +1,49 @@
+//**************************************************************************************************
+// system.rs                                                                                       *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::{Error, Structure, StructureType};
+use core::convert::TryInto;
+
+// Type 1. Identifies the machine as a whole.
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SystemInformation<'a> {
+    pub manufacturer: Option<&'a str>,
+    pub product_name: Option<&'a str>,
+    pub version: Option<&'a str>,
+    pub serial_number: Option<&'a str>,
+    pub uuid: Option<[u8; 16]>,
+    pub sku_number: Option<&'a str>,
+    pub family: Option<&'a str>,
+}
+
+impl<'a> SystemInformation<'a> {
+    pub fn read(structure: &Structure<'a>) -> Result<Self, Error> {
+        structure.expect_type(StructureType::SYSTEM_INFORMATION)?;
+
+        Ok(Self {
+            manufacturer: structure.string_at(0x04),
+            product_name: structure.string_at(0x05),
+            version: structure.string_at(0x06),
+            serial_number: structure.string_at(0x07),
+            uuid: Self::read_uuid(structure),
+            sku_number: structure.string_at(0x19),
+            family: structure.string_at(0x1A),
+        })
+    }
+
+    // All zeros means there is no UUID and all ones means it is not set yet.
+
+    fn read_uuid(structure: &Structure) -> Option<[u8; 16]> {
+        let uuid: [u8; 16] = structure.formatted().get(0x08..0x18)?.try_into().ok()?;
+
+        if uuid.iter().all(|&byte| byte == 0) || uuid.iter().all(|&byte| byte == 0xFF) {
+            None
+        } else {
+            Some(uuid)
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//**************************************************************************************************
+// mod.rs                                                                                          *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+mod node;
+mod text;
+
+pub use node::*;
+
+use crate::error::Error;
+use crate::ffi::device_path::{self, EndSubType, NodeType, HEADER_SIZE};
+use crate::protocol;
+use crate::Handle;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+use core::ptr;
+
+// An owned device path. The bytes always end with an end entire node and every node in them has a
+// valid length so the nodes can be read without checking again.
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct DevicePath {
+    bytes: Vec<u8>,
+}
+
+impl DevicePath {
+    // An empty path that only has the end node.
+
+    pub fn new() -> Self {
+        Self {
+            bytes: Node::END_ENTIRE.to_vec(),
+        }
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        let len = validated_len(&bytes)?;
+
+        if len != bytes.len() {
+            return Err(Error::InvalidDevicePath);
+        }
+
+        Ok(Self { bytes })
+    }
+
+    // Copies the device path at the pointer. The path is read up to its end entire node so the
+    // length must be valid.
+
+    pub unsafe fn from_ptr(path: *const device_path::Protocol) -> Result<Self, Error> {
+        if path.is_null() {
+            return Err(Error::InvalidArgument("path"));
+        }
+
+        let start = path as *const u8;
+        let mut len = 0;
+
+        loop {
+            let mut header = [0; HEADER_SIZE];
+            ptr::copy_nonoverlapping(start.add(len), header.as_mut_ptr(), HEADER_SIZE);
+
+            let node_len = u16::from_le_bytes([header[2], header[3]]) as usize;
+
+            if node_len < HEADER_SIZE {
+                return Err(Error::InvalidDevicePath);
+            }
+
+            len += node_len;
+
+            if is_end_entire(header[0], header[1]) {
+                break;
+            }
+        }
+
+        let mut bytes = Vec::with_capacity(len);
+        ptr::copy_nonoverlapping(start, bytes.as_mut_ptr(), len);
+        bytes.set_len(len);
+
+        Self::from_bytes(bytes)
+    }
+
+    // The device path of the device the handle is for.
+
+    pub fn of_handle(handle: Handle) -> Result<Self, Error> {
+        let interface = protocol::Interface::open(device_path::Protocol::GUID, handle)?;
+
+        unsafe { Self::from_ptr(interface.get::<device_path::Protocol>()) }
+    }
+
+    pub fn from_nodes<'a>(nodes: impl IntoIterator<Item = &'a Node>) -> Result<Self, Error> {
+        let mut path = Self::new();
+
+        for node in nodes {
+            path.push(node)?;
+        }
+
+        Ok(path)
+    }
+
+    // A path with a single file path node. These are relative to a device.
+
+    pub fn file(path: &str) -> Result<Self, Error> {
+        let mut device_path = Self::new();
+        device_path.push(&Node::FilePath(path.into()))?;
+        Ok(device_path)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn as_ptr(&self) -> *const device_path::Protocol {
+        self.bytes.as_ptr() as *const device_path::Protocol
+    }
+
+    // The firmware takes mutable pointers even where it doesn't change the path.
+
+    pub fn as_mut_ptr(&mut self) -> *mut device_path::Protocol {
+        self.bytes.as_mut_ptr() as *mut device_path::Protocol
+    }
+
+    // The length in bytes including the end node.
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.len() == HEADER_SIZE
+    }
+
+    pub fn raw_nodes(&self) -> RawNodes {
+        RawNodes {
+            source: &self.bytes[..self.bytes.len() - HEADER_SIZE],
+        }
+    }
+
+    // End instance nodes are returned between the instances of a multi-instance path.
+
+    pub fn nodes(&self) -> impl Iterator<Item = Node> + '_ {
+        self.raw_nodes().map(|node| node.decode())
+    }
+
+    pub fn push(&mut self, node: &Node) -> Result<(), Error> {
+        let encoded = node.encode()?;
+        let end = self.bytes.len() - HEADER_SIZE;
+
+        self.bytes.splice(end..end, encoded);
+
+        Ok(())
+    }
+
+    // Appends every node of the other path to this one.
+
+    pub fn append(&mut self, other: &DevicePath) {
+        let end = self.bytes.len() - HEADER_SIZE;
+        let other_nodes = &other.bytes[..other.bytes.len() - HEADER_SIZE];
+
+        self.bytes.splice(end..end, other_nodes.iter().copied());
+    }
+
+    // The path without its last node. Used to get the device of a file path.
+
+    pub fn parent(&self) -> Option<DevicePath> {
+        let last = self.raw_nodes().last()?;
+        let end = self.bytes.len() - HEADER_SIZE - last.len();
+
+        let mut bytes = Vec::with_capacity(end + HEADER_SIZE);
+        bytes.extend_from_slice(&self.bytes[..end]);
+        bytes.extend_from_slice(&Node::END_ENTIRE);
+
+        Some(Self { bytes })
+    }
+
+    // Splits the path at the first media file path node. Returns the device part and the file
+    // path if there is one.
+
+    pub fn split_file_path(&self) -> (DevicePath, Option<String>) {
+        let mut device = Self::new();
+
+        for node in self.raw_nodes() {
+            if let Node::FilePath(path) = node.decode() {
+                return (device, Some(path));
+            }
+
+            let end = device.bytes.len() - HEADER_SIZE;
+            device
+                .bytes
+                .splice(end..end, node.as_bytes().iter().copied());
+        }
+
+        (device, None)
+    }
+}
+
+impl Default for DevicePath {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// A node that has not been decoded. The data does not include the header.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RawNode<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> RawNode<'a> {
+    pub fn node_type(&self) -> NodeType {
+        NodeType::new(self.bytes[0])
+    }
+
+    pub fn sub_type(&self) -> u8 {
+        self.bytes[1]
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        &self.bytes[HEADER_SIZE..]
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data().is_empty()
+    }
+
+    pub fn decode(&self) -> Node {
+        Node::decode(self.node_type(), self.sub_type(), self.data())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RawNodes<'a> {
+    source: &'a [u8],
+}
+
+impl<'a> Iterator for RawNodes<'a> {
+    type Item = RawNode<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.source.is_empty() {
+            return None;
+        }
+
+        // Lengths were checked when the path was created.
+
+        let len = u16::from_le_bytes([self.source[2], self.source[3]]) as usize;
+        let (bytes, rest) = self.source.split_at(len);
+
+        self.source = rest;
+
+        Some(RawNode { bytes })
+    }
+}
+
+impl<'a> FusedIterator for RawNodes<'a> {}
+
+// Returns the length of the path up to and including its end entire node.
+
+fn validated_len(bytes: &[u8]) -> Result<usize, Error> {
+    let mut offset = 0;
+
+    loop {
+        let header = bytes
+            .get(offset..offset + HEADER_SIZE)
+            .ok_or(Error::InvalidDevicePath)?;
+
+        let len = u16::from_le_bytes([header[2], header[3]]) as usize;
+
+        if len < HEADER_SIZE || offset + len > bytes.len() {
+            return Err(Error::InvalidDevicePath);
+        }
+
+        offset += len;
+
+        if is_end_entire(header[0], header[1]) {
+            return Ok(offset);
+        }
+    }
+}
+
+fn is_end_entire(node_type: u8, sub_type: u8) -> bool {
+    NodeType::new(node_type) == NodeType::END && EndSubType::new(sub_type) == EndSubType::ENTIRE
+}
@@ -0,0 +1,438 @@
+//**************************************************************************************************
+// node.rs                                                                                         *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::error::Error;
+use crate::ffi::device_path::{
+    AcpiSubType, EndSubType, HardwareSubType, MediaSubType, MessagingSubType, NodeType, HEADER_SIZE,
+};
+use crate::Guid;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::mem;
+use ucs2::FromUcs2Buffer;
+
+const GUID_SIZE: usize = mem::size_of::<Guid>();
+
+// A decoded device path node. Nodes that are not known or don't have the length of their type
+// are kept as unknown nodes so a path can always be decoded and encoded again without changes.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
+    Pci {
+        function: u8,
+        device: u8,
+    },
+    MemoryMapped {
+        memory_type: u32,
+        start: u64,
+        end: u64,
+    },
+    Controller(u32),
+    // Vendor nodes exist for the hardware, messaging and media types.
+    Vendor {
+        node_type: NodeType,
+        guid: Guid,
+        data: Vec<u8>,
+    },
+    Acpi {
+        hid: u32,
+        uid: u32,
+    },
+    Atapi {
+        is_secondary: bool,
+        is_slave: bool,
+        lun: u16,
+    },
+    Scsi {
+        target: u16,
+        lun: u16,
+    },
+    Usb {
+        parent_port: u8,
+        interface: u8,
+    },
+    Sata {
+        hba_port: u16,
+        port_multiplier_port: u16,
+        lun: u16,
+    },
+    Nvme {
+        namespace_id: u32,
+        eui64: [u8; 8],
+    },
+    HardDrive {
+        partition_number: u32,
+        start: u64,
+        size: u64,
+        signature: PartitionSignature,
+    },
+    CdRom {
+        boot_entry: u32,
+        start: u64,
+        size: u64,
+    },
+    FilePath(String),
+    MediaProtocol(Guid),
+    FirmwareFile(Guid),
+    FirmwareVolume(Guid),
+    RelativeOffsetRange {
+        start: u64,
+        end: u64,
+    },
+    EndInstance,
+    Unknown {
+        node_type: NodeType,
+        sub_type: u8,
+        data: Vec<u8>,
+    },
+}
+
+// Identifies the partition of a hard drive node. MBR partitions use the disk's 32 bit signature
+// and GPT partitions their unique GUID.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PartitionSignature {
+    None,
+    Mbr(u32),
+    Gpt(Guid),
+}
+
+impl PartitionSignature {
+    const MBR_FORMAT: u8 = 0x01;
+    const GPT_FORMAT: u8 = 0x02;
+
+    const NO_SIGNATURE: u8 = 0x00;
+    const MBR_SIGNATURE: u8 = 0x01;
+    const GUID_SIGNATURE: u8 = 0x02;
+}
+
+impl Node {
+    pub const END_ENTIRE: [u8; HEADER_SIZE] = [0x7F, 0xFF, HEADER_SIZE as u8, 0];
+
+    pub const END_INSTANCE: [u8; HEADER_SIZE] = [0x7F, 0x01, HEADER_SIZE as u8, 0];
+
+    // The EISA ID of PCI root bridges. They are written as PciRoot in text.
+
+    pub const PCI_ROOT_HID: u32 = 0x0A0341D0;
+
+    pub const PCIE_ROOT_HID: u32 = 0x0A0841D0;
+
+    pub fn node_type(&self) -> NodeType {
+        match self {
+            Node::Pci { .. } | Node::MemoryMapped { .. } | Node::Controller(_) => {
+                NodeType::HARDWARE
+            }
+            Node::Vendor { node_type, .. } => *node_type,
+            Node::Acpi { .. } => NodeType::ACPI,
+            Node::Atapi { .. }
+            | Node::Scsi { .. }
+            | Node::Usb { .. }
+            | Node::Sata { .. }
+            | Node::Nvme { .. } => NodeType::MESSAGING,
+            Node::HardDrive { .. }
+            | Node::CdRom { .. }
+            | Node::FilePath(_)
+            | Node::MediaProtocol(_)
+            | Node::FirmwareFile(_)
+            | Node::FirmwareVolume(_)
+            | Node::RelativeOffsetRange { .. } => NodeType::MEDIA,
+            Node::EndInstance => NodeType::END,
+            Node::Unknown { node_type, .. } => *node_type,
+        }
+    }
+
+    pub fn sub_type(&self) -> u8 {
+        match self {
+            Node::Pci { .. } => HardwareSubType::PCI.into(),
+            Node::MemoryMapped { .. } => HardwareSubType::MEMORY_MAPPED.into(),
+            Node::Controller(_) => HardwareSubType::CONTROLLER.into(),
+            Node::Vendor { node_type, .. } => match *node_type {
+                NodeType::MESSAGING => MessagingSubType::VENDOR.into(),
+                NodeType::MEDIA => MediaSubType::VENDOR.into(),
+                _ => HardwareSubType::VENDOR.into(),
+            },
+            Node::Acpi { .. } => AcpiSubType::ACPI.into(),
+            Node::Atapi { .. } => MessagingSubType::ATAPI.into(),
+            Node::Scsi { .. } => MessagingSubType::SCSI.into(),
+            Node::Usb { .. } => MessagingSubType::USB.into(),
+            Node::Sata { .. } => MessagingSubType::SATA.into(),
+            Node::Nvme { .. } => MessagingSubType::NVME_NAMESPACE.into(),
+            Node::HardDrive { .. } => MediaSubType::HARD_DRIVE.into(),
+            Node::CdRom { .. } => MediaSubType::CD_ROM.into(),
+            Node::FilePath(_) => MediaSubType::FILE_PATH.into(),
+            Node::MediaProtocol(_) => MediaSubType::MEDIA_PROTOCOL.into(),
+            Node::FirmwareFile(_) => MediaSubType::PIWG_FIRMWARE_FILE.into(),
+            Node::FirmwareVolume(_) => MediaSubType::PIWG_FIRMWARE_VOLUME.into(),
+            Node::RelativeOffsetRange { .. } => MediaSubType::RELATIVE_OFFSET_RANGE.into(),
+            Node::EndInstance => EndSubType::INSTANCE.into(),
+            Node::Unknown { sub_type, .. } => *sub_type,
+        }
+    }
+
+    pub fn decode(node_type: NodeType, sub_type: u8, data: &[u8]) -> Node {
+        Self::decode_known(node_type, sub_type, data).unwrap_or_else(|| Node::Unknown {
+            node_type,
+            sub_type,
+            data: data.to_vec(),
+        })
+    }
+
+    fn decode_known(node_type: NodeType, sub_type: u8, data: &[u8]) -> Option<Node> {
+        let u16_at =
+            |offset: usize| u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap());
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        let guid_at =
+            |offset: usize| Guid::from_bytes(data[offset..offset + GUID_SIZE].try_into().unwrap());
+
+        let vendor = || {
+            if data.len() < GUID_SIZE {
+                return None;
+            }
+
+            Some(Node::Vendor {
+                node_type,
+                guid: guid_at(0),
+                data: data[GUID_SIZE..].to_vec(),
+            })
+        };
+
+        let node = match (node_type, sub_type, data.len()) {
+            (NodeType::HARDWARE, sub_type, len) => match (HardwareSubType::new(sub_type), len) {
+                (HardwareSubType::PCI, 2) => Node::Pci {
+                    function: data[0],
+                    device: data[1],
+                },
+                (HardwareSubType::MEMORY_MAPPED, 20) => Node::MemoryMapped {
+                    memory_type: u32_at(0),
+                    start: u64_at(4),
+                    end: u64_at(12),
+                },
+                (HardwareSubType::VENDOR, _) => return vendor(),
+                (HardwareSubType::CONTROLLER, 4) => Node::Controller(u32_at(0)),
+                _ => return None,
+            },
+            (NodeType::ACPI, sub_type, 8) if AcpiSubType::new(sub_type) == AcpiSubType::ACPI => {
+                Node::Acpi {
+                    hid: u32_at(0),
+                    uid: u32_at(4),
+                }
+            }
+            (NodeType::MESSAGING, sub_type, len) => match (MessagingSubType::new(sub_type), len) {
+                (MessagingSubType::ATAPI, 4) => Node::Atapi {
+                    is_secondary: data[0] != 0,
+                    is_slave: data[1] != 0,
+                    lun: u16_at(2),
+                },
+                (MessagingSubType::SCSI, 4) => Node::Scsi {
+                    target: u16_at(0),
+                    lun: u16_at(2),
+                },
+                (MessagingSubType::USB, 2) => Node::Usb {
+                    parent_port: data[0],
+                    interface: data[1],
+                },
+                (MessagingSubType::VENDOR, _) => return vendor(),
+                (MessagingSubType::SATA, 6) => Node::Sata {
+                    hba_port: u16_at(0),
+                    port_multiplier_port: u16_at(2),
+                    lun: u16_at(4),
+                },
+                (MessagingSubType::NVME_NAMESPACE, 12) => Node::Nvme {
+                    namespace_id: u32_at(0),
+                    eui64: data[4..12].try_into().unwrap(),
+                },
+                _ => return None,
+            },
+            (NodeType::MEDIA, sub_type, len) => match (MediaSubType::new(sub_type), len) {
+                (MediaSubType::HARD_DRIVE, 38) => {
+                    let signature = match (data[36], data[37]) {
+                        (PartitionSignature::MBR_FORMAT, PartitionSignature::MBR_SIGNATURE) => {
+                            PartitionSignature::Mbr(u32_at(20))
+                        }
+                        (PartitionSignature::GPT_FORMAT, PartitionSignature::GUID_SIGNATURE) => {
+                            PartitionSignature::Gpt(guid_at(20))
+                        }
+                        (_, PartitionSignature::NO_SIGNATURE) => PartitionSignature::None,
+                        _ => return None,
+                    };
+
+                    Node::HardDrive {
+                        partition_number: u32_at(0),
+                        start: u64_at(4),
+                        size: u64_at(12),
+                        signature,
+                    }
+                }
+                (MediaSubType::CD_ROM, 20) => Node::CdRom {
+                    boot_entry: u32_at(0),
+                    start: u64_at(4),
+                    size: u64_at(12),
+                },
+                (MediaSubType::VENDOR, _) => return vendor(),
+                (MediaSubType::FILE_PATH, len) if len % 2 == 0 => {
+                    let units = data
+                        .chunks_exact(2)
+                        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+                        .take_while(|&unit| unit != 0)
+                        .collect::<Vec<_>>();
+
+                    Node::FilePath(String::from_usc2(&units).ok()?)
+                }
+                (MediaSubType::MEDIA_PROTOCOL, GUID_SIZE) => Node::MediaProtocol(guid_at(0)),
+                (MediaSubType::PIWG_FIRMWARE_FILE, GUID_SIZE) => Node::FirmwareFile(guid_at(0)),
+                (MediaSubType::PIWG_FIRMWARE_VOLUME, GUID_SIZE) => Node::FirmwareVolume(guid_at(0)),
+                (MediaSubType::RELATIVE_OFFSET_RANGE, 20) => Node::RelativeOffsetRange {
+                    start: u64_at(4),
+                    end: u64_at(12),
+                },
+                _ => return None,
+            },
+            (NodeType::END, sub_type, 0) if EndSubType::new(sub_type) == EndSubType::INSTANCE => {
+                Node::EndInstance
+            }
+            _ => return None,
+        };
+
+        Some(node)
+    }
+
+    // Encodes the node with its header.
+
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::new();
+
+        match self {
+            Node::Pci { function, device } => data.extend_from_slice(&[*function, *device]),
+            Node::MemoryMapped {
+                memory_type,
+                start,
+                end,
+            } => {
+                data.extend_from_slice(&memory_type.to_le_bytes());
+                data.extend_from_slice(&start.to_le_bytes());
+                data.extend_from_slice(&end.to_le_bytes());
+            }
+            Node::Controller(controller) => data.extend_from_slice(&controller.to_le_bytes()),
+            Node::Vendor {
+                guid, data: bytes, ..
+            } => {
+                data.extend_from_slice(&guid.to_bytes());
+                data.extend_from_slice(bytes);
+            }
+            Node::Acpi { hid, uid } => {
+                data.extend_from_slice(&hid.to_le_bytes());
+                data.extend_from_slice(&uid.to_le_bytes());
+            }
+            Node::Atapi {
+                is_secondary,
+                is_slave,
+                lun,
+            } => {
+                data.extend_from_slice(&[*is_secondary as u8, *is_slave as u8]);
+                data.extend_from_slice(&lun.to_le_bytes());
+            }
+            Node::Scsi { target, lun } => {
+                data.extend_from_slice(&target.to_le_bytes());
+                data.extend_from_slice(&lun.to_le_bytes());
+            }
+            Node::Usb {
+                parent_port,
+                interface,
+            } => data.extend_from_slice(&[*parent_port, *interface]),
+            Node::Sata {
+                hba_port,
+                port_multiplier_port,
+                lun,
+            } => {
+                data.extend_from_slice(&hba_port.to_le_bytes());
+                data.extend_from_slice(&port_multiplier_port.to_le_bytes());
+                data.extend_from_slice(&lun.to_le_bytes());
+            }
+            Node::Nvme {
+                namespace_id,
+                eui64,
+            } => {
+                data.extend_from_slice(&namespace_id.to_le_bytes());
+                data.extend_from_slice(eui64);
+            }
+            Node::HardDrive {
+                partition_number,
+                start,
+                size,
+                signature,
+            } => {
+                data.extend_from_slice(&partition_number.to_le_bytes());
+                data.extend_from_slice(&start.to_le_bytes());
+                data.extend_from_slice(&size.to_le_bytes());
+
+                let (signature_bytes, format, signature_type) = match signature {
+                    PartitionSignature::None => ([0; 16], 0, PartitionSignature::NO_SIGNATURE),
+                    PartitionSignature::Mbr(signature) => {
+                        let mut bytes = [0; 16];
+                        bytes[..4].copy_from_slice(&signature.to_le_bytes());
+
+                        (
+                            bytes,
+                            PartitionSignature::MBR_FORMAT,
+                            PartitionSignature::MBR_SIGNATURE,
+                        )
+                    }
+                    PartitionSignature::Gpt(guid) => (
+                        guid.to_bytes(),
+                        PartitionSignature::GPT_FORMAT,
+                        PartitionSignature::GUID_SIGNATURE,
+                    ),
+                };
+
+                data.extend_from_slice(&signature_bytes);
+                data.extend_from_slice(&[format, signature_type]);
+            }
+            Node::CdRom {
+                boot_entry,
+                start,
+                size,
+            } => {
+                data.extend_from_slice(&boot_entry.to_le_bytes());
+                data.extend_from_slice(&start.to_le_bytes());
+                data.extend_from_slice(&size.to_le_bytes());
+            }
+            Node::FilePath(path) => {
+                let encoded = ucs2::encode_string_with_null(path)
+                    .map_err(|_| Error::InvalidArgument("path"))?;
+
+                for unit in encoded.iter() {
+                    data.extend_from_slice(&unit.to_le_bytes());
+                }
+            }
+            Node::MediaProtocol(guid) | Node::FirmwareFile(guid) | Node::FirmwareVolume(guid) => {
+                data.extend_from_slice(&guid.to_bytes())
+            }
+            Node::RelativeOffsetRange { start, end } => {
+                data.extend_from_slice(&[0; 4]);
+                data.extend_from_slice(&start.to_le_bytes());
+                data.extend_from_slice(&end.to_le_bytes());
+            }
+            Node::EndInstance => {}
+            Node::Unknown { data: bytes, .. } => data.extend_from_slice(bytes),
+        }
+
+        let len: u16 = (HEADER_SIZE + data.len())
+            .try_into()
+            .map_err(|_| Error::InvalidArgument("node"))?;
+
+        let mut bytes = Vec::with_capacity(len as usize);
+        bytes.push(self.node_type().into());
+        bytes.push(self.sub_type());
+        bytes.extend_from_slice(&len.to_le_bytes());
+        bytes.extend_from_slice(&data);
+
+        Ok(bytes)
+    }
+}
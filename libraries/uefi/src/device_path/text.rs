@@ -0,0 +1,440 @@
+//**************************************************************************************************
+// text.rs                                                                                         *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+// The text form of device paths uses the same names as the UEFI specification and the shell so
+// paths printed by the loader can be compared with the ones shown by the firmware.
+
+use super::{DevicePath, Node, PartitionSignature};
+use crate::error::Error;
+use crate::ffi::device_path::NodeType;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::str::FromStr;
+
+impl fmt::Display for DevicePath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut is_first = true;
+
+        for node in self.nodes() {
+            if node == Node::EndInstance {
+                write!(f, ",")?;
+                is_first = true;
+                continue;
+            }
+
+            if !is_first {
+                write!(f, "/")?;
+            }
+
+            write!(f, "{}", node)?;
+            is_first = false;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for DevicePath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DevicePath(\"{}\")", self)
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Node::Pci { function, device } => write!(f, "Pci(0x{:X},0x{:X})", device, function),
+            Node::MemoryMapped {
+                memory_type,
+                start,
+                end,
+            } => write!(
+                f,
+                "MemoryMapped(0x{:X},0x{:X},0x{:X})",
+                memory_type, start, end
+            ),
+            Node::Controller(controller) => write!(f, "Ctrl(0x{:X})", controller),
+            Node::Vendor {
+                node_type,
+                guid,
+                data,
+            } => {
+                let name = match *node_type {
+                    NodeType::MESSAGING => "VenMsg",
+                    NodeType::MEDIA => "VenMedia",
+                    _ => "VenHw",
+                };
+
+                write!(f, "{}({}", name, guid)?;
+
+                if !data.is_empty() {
+                    write!(f, ",")?;
+                    write_hex(f, data)?;
+                }
+
+                write!(f, ")")
+            }
+            Node::Acpi { hid, uid } => match *hid {
+                Node::PCI_ROOT_HID => write!(f, "PciRoot(0x{:X})", uid),
+                Node::PCIE_ROOT_HID => write!(f, "PcieRoot(0x{:X})", uid),
+                _ => {
+                    write!(f, "Acpi(")?;
+                    write_eisa_id(f, *hid)?;
+                    write!(f, ",0x{:X})", uid)
+                }
+            },
+            Node::Atapi {
+                is_secondary,
+                is_slave,
+                lun,
+            } => write!(
+                f,
+                "Ata({},{},0x{:X})",
+                if *is_secondary {
+                    "Secondary"
+                } else {
+                    "Primary"
+                },
+                if *is_slave { "Slave" } else { "Master" },
+                lun
+            ),
+            Node::Scsi { target, lun } => write!(f, "Scsi(0x{:X},0x{:X})", target, lun),
+            Node::Usb {
+                parent_port,
+                interface,
+            } => write!(f, "USB(0x{:X},0x{:X})", parent_port, interface),
+            Node::Sata {
+                hba_port,
+                port_multiplier_port,
+                lun,
+            } => write!(
+                f,
+                "Sata(0x{:X},0x{:X},0x{:X})",
+                hba_port, port_multiplier_port, lun
+            ),
+            Node::Nvme {
+                namespace_id,
+                eui64,
+            } => {
+                write!(f, "NVMe(0x{:X},", namespace_id)?;
+
+                // The identifier is stored with its least significant byte first but written
+                // the other way round.
+
+                for (index, byte) in eui64.iter().rev().enumerate() {
+                    if index != 0 {
+                        write!(f, "-")?;
+                    }
+                    write!(f, "{:02X}", byte)?;
+                }
+
+                write!(f, ")")
+            }
+            Node::HardDrive {
+                partition_number,
+                start,
+                size,
+                signature,
+            } => {
+                write!(f, "HD({},", partition_number)?;
+
+                match signature {
+                    PartitionSignature::None => write!(f, "0,0")?,
+                    PartitionSignature::Mbr(signature) => write!(f, "MBR,0x{:08X}", signature)?,
+                    PartitionSignature::Gpt(guid) => write!(f, "GPT,{}", guid)?,
+                }
+
+                write!(f, ",0x{:X},0x{:X})", start, size)
+            }
+            Node::CdRom {
+                boot_entry,
+                start,
+                size,
+            } => write!(f, "CDROM(0x{:X},0x{:X},0x{:X})", boot_entry, start, size),
+            Node::FilePath(path) => write!(f, "{}", path),
+            Node::MediaProtocol(guid) => write!(f, "Media({})", guid),
+            Node::FirmwareFile(guid) => write!(f, "FvFile({})", guid),
+            Node::FirmwareVolume(guid) => write!(f, "Fv({})", guid),
+            Node::RelativeOffsetRange { start, end } => {
+                write!(f, "Offset(0x{:X},0x{:X})", start, end)
+            }
+            Node::EndInstance => write!(f, ","),
+            Node::Unknown {
+                node_type,
+                sub_type,
+                data,
+            } => {
+                write!(f, "Path({},{},", u8::from(*node_type), sub_type)?;
+                write_hex(f, data)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl FromStr for DevicePath {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut path = DevicePath::new();
+
+        for (index, instance) in split_top_level(text, ',').into_iter().enumerate() {
+            if index != 0 {
+                path.push(&Node::EndInstance)?;
+            }
+
+            for node in split_top_level(instance, '/') {
+                if !node.is_empty() {
+                    path.push(&node.parse()?)?;
+                }
+            }
+        }
+
+        Ok(path)
+    }
+}
+
+impl FromStr for Node {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+
+        // Anything that doesn't look like a node is a file path. The firmware does the same so
+        // paths like \EFI\BOOT\BOOTX64.EFI can be written without a node name.
+
+        let open = match text.find('(') {
+            Some(open) if text.ends_with(')') => open,
+            _ => return Ok(Node::FilePath(String::from(text))),
+        };
+
+        let name = &text[..open];
+        let arguments = split_top_level(&text[open + 1..text.len() - 1], ',')
+            .into_iter()
+            .map(str::trim)
+            .collect::<Vec<_>>();
+
+        let argument = |index: usize| arguments.get(index).copied().unwrap_or("");
+        let optional_data = |index: usize| match arguments.get(index) {
+            Some(text) if !text.is_empty() => parse_hex(text),
+            _ => Ok(Vec::new()),
+        };
+
+        let vendor = |node_type| {
+            Ok(Node::Vendor {
+                node_type,
+                guid: argument(0).parse()?,
+                data: optional_data(1)?,
+            })
+        };
+
+        match name {
+            "Pci" => Ok(Node::Pci {
+                device: parse_number(argument(0))?,
+                function: parse_number(argument(1))?,
+            }),
+            "MemoryMapped" => Ok(Node::MemoryMapped {
+                memory_type: parse_number(argument(0))?,
+                start: parse_number(argument(1))?,
+                end: parse_number(argument(2))?,
+            }),
+            "Ctrl" => Ok(Node::Controller(parse_number(argument(0))?)),
+            "VenHw" => vendor(NodeType::HARDWARE),
+            "VenMsg" => vendor(NodeType::MESSAGING),
+            "VenMedia" => vendor(NodeType::MEDIA),
+            "PciRoot" => Ok(Node::Acpi {
+                hid: Node::PCI_ROOT_HID,
+                uid: parse_number(argument(0))?,
+            }),
+            "PcieRoot" => Ok(Node::Acpi {
+                hid: Node::PCIE_ROOT_HID,
+                uid: parse_number(argument(0))?,
+            }),
+            "Acpi" => Ok(Node::Acpi {
+                hid: parse_eisa_id(argument(0))?,
+                uid: parse_number(argument(1))?,
+            }),
+            "Ata" => Ok(Node::Atapi {
+                is_secondary: parse_choice(argument(0), "Primary", "Secondary")?,
+                is_slave: parse_choice(argument(1), "Master", "Slave")?,
+                lun: parse_number(argument(2))?,
+            }),
+            "Scsi" => Ok(Node::Scsi {
+                target: parse_number(argument(0))?,
+                lun: parse_number(argument(1))?,
+            }),
+            "USB" => Ok(Node::Usb {
+                parent_port: parse_number(argument(0))?,
+                interface: parse_number(argument(1))?,
+            }),
+            "Sata" => Ok(Node::Sata {
+                hba_port: parse_number(argument(0))?,
+                port_multiplier_port: parse_number(argument(1))?,
+                lun: parse_number(argument(2))?,
+            }),
+            "NVMe" => {
+                let mut eui64 = [0; 8];
+                let bytes = argument(1)
+                    .split('-')
+                    .map(|byte| u8::from_str_radix(byte, 16))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| Error::InvalidDevicePath)?;
+
+                if bytes.len() != eui64.len() {
+                    return Err(Error::InvalidDevicePath);
+                }
+
+                for (target, byte) in eui64.iter_mut().zip(bytes.iter().rev()) {
+                    *target = *byte;
+                }
+
+                Ok(Node::Nvme {
+                    namespace_id: parse_number(argument(0))?,
+                    eui64,
+                })
+            }
+            "HD" => {
+                let signature = match argument(1) {
+                    "MBR" => PartitionSignature::Mbr(parse_number(argument(2))?),
+                    "GPT" => PartitionSignature::Gpt(argument(2).parse()?),
+                    "0" => PartitionSignature::None,
+                    _ => return Err(Error::InvalidDevicePath),
+                };
+
+                Ok(Node::HardDrive {
+                    partition_number: parse_number(argument(0))?,
+                    start: parse_number(argument(3))?,
+                    size: parse_number(argument(4))?,
+                    signature,
+                })
+            }
+            "CDROM" => Ok(Node::CdRom {
+                boot_entry: parse_number(argument(0))?,
+                start: parse_number(argument(1))?,
+                size: parse_number(argument(2))?,
+            }),
+            "Media" => Ok(Node::MediaProtocol(argument(0).parse()?)),
+            "FvFile" => Ok(Node::FirmwareFile(argument(0).parse()?)),
+            "Fv" => Ok(Node::FirmwareVolume(argument(0).parse()?)),
+            "Offset" => Ok(Node::RelativeOffsetRange {
+                start: parse_number(argument(0))?,
+                end: parse_number(argument(1))?,
+            }),
+            "Path" => {
+                // Known nodes written this way are decoded so they compare equal to the parsed
+                // form of their own names.
+
+                let node_type = NodeType::new(parse_number(argument(0))?);
+                let sub_type = parse_number(argument(1))?;
+
+                Ok(Node::decode(node_type, sub_type, &optional_data(2)?))
+            }
+            _ => Err(Error::InvalidDevicePath),
+        }
+    }
+}
+
+// Splits at separators that are not inside parentheses.
+
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (index, character) in text.char_indices() {
+        match character {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ if character == separator && depth == 0 => {
+                parts.push(&text[start..index]);
+                start = index + character.len_utf8();
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&text[start..]);
+    parts
+}
+
+// Numbers are hexadecimal with a 0x prefix and decimal otherwise.
+
+fn parse_number<T: TryFrom<u64>>(text: &str) -> Result<T, Error> {
+    let value = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => text.parse(),
+    }
+    .map_err(|_| Error::InvalidDevicePath)?;
+
+    T::try_from(value).map_err(|_| Error::InvalidDevicePath)
+}
+
+fn parse_choice(text: &str, first: &str, second: &str) -> Result<bool, Error> {
+    if text == first {
+        Ok(false)
+    } else if text == second {
+        Ok(true)
+    } else {
+        Ok(parse_number::<u8>(text)? != 0)
+    }
+}
+
+fn parse_hex(text: &str) -> Result<Vec<u8>, Error> {
+    if text.len() % 2 != 0 {
+        return Err(Error::InvalidDevicePath);
+    }
+
+    (0..text.len())
+        .step_by(2)
+        .map(|index| {
+            text.get(index..index + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or(Error::InvalidDevicePath)
+        })
+        .collect()
+}
+
+fn write_hex(f: &mut fmt::Formatter, data: &[u8]) -> fmt::Result {
+    for byte in data {
+        write!(f, "{:02X}", byte)?;
+    }
+    Ok(())
+}
+
+// ACPI hardware IDs are compressed EISA IDs. The three letter vendor is packed into the low 16 bits
+// and the product number is the high 16 bits.
+
+fn write_eisa_id(f: &mut fmt::Formatter, id: u32) -> fmt::Result {
+    let vendor = id as u16;
+    let letter = |shift: u16| (b'A' - 1 + ((vendor >> shift) & 0x1F) as u8) as char;
+
+    write!(
+        f,
+        "{}{}{}{:04X}",
+        letter(10),
+        letter(5),
+        letter(0),
+        id >> 16
+    )
+}
+
+fn parse_eisa_id(text: &str) -> Result<u32, Error> {
+    let bytes = text.as_bytes();
+
+    if bytes.len() != 7 || !bytes[..3].iter().all(u8::is_ascii_uppercase) {
+        return Err(Error::InvalidDevicePath);
+    }
+
+    let vendor = bytes[..3].iter().fold(0u32, |vendor, letter| {
+        (vendor << 5) | (letter - b'A' + 1) as u32
+    });
+
+    let product = u32::from_str_radix(&text[3..], 16).map_err(|_| Error::InvalidDevicePath)?;
+
+    Ok(vendor | (product << 16))
+}
@@ -30,6 +30,12 @@ pub enum Error {
     FileOnlyOperation,
     DeleteFailed,
     UnexpectedEnd,
+    VariableNonExistent(String),
+    VariableStorageFull,
+    InvalidDevicePath,
+    InvalidImage,
+    SecurityViolation,
+    ImageFailed(super::ffi::Status, String),
 }
 
 impl fmt::Display for Error {
@@ -68,6 +74,22 @@ impl fmt::Display for Error {
             }
             Error::DeleteFailed => write!(f, "Failed to delete the file or directory."),
             Error::UnexpectedEnd => write!(f, "The end of the source was reached unexpectedly."),
+            Error::VariableNonExistent(name) => {
+                write!(f, "The variable \"{}\" does not exist.", name)
+            }
+            Error::VariableStorageFull => write!(f, "The variable storage is full."),
+            Error::InvalidDevicePath => write!(f, "The device path is malformed."),
+            Error::InvalidImage => write!(f, "The image is not a valid image for this system."),
+            Error::SecurityViolation => write!(f, "The image failed the security check."),
+            Error::ImageFailed(status, exit_data) => {
+                write!(f, "The image exited with the status \"{:?}\".", status)?;
+
+                if !exit_data.is_empty() {
+                    write!(f, " {}", exit_data)?;
+                }
+
+                Ok(())
+            }
         }
     }
 }
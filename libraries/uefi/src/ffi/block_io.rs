@@ -0,0 +1,63 @@
+//**************************************************************************************************
+// block_io.rs                                                                                     *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use super::primitives::{Guid, Status};
+use core::ffi::c_void;
+
+pub type Lba = u64;
+
+#[repr(C)]
+pub struct Protocol {
+    pub revision: u64,
+    pub media: *mut Media,
+    pub reset: extern "efiapi" fn(this: *mut Protocol, extended_verification: bool) -> Status,
+    pub read_blocks: extern "efiapi" fn(
+        this: *mut Protocol,
+        media_id: u32,
+        lba: Lba,
+        buffer_size: usize,
+        buffer: *mut c_void,
+    ) -> Status,
+    pub write_blocks: extern "efiapi" fn(
+        this: *mut Protocol,
+        media_id: u32,
+        lba: Lba,
+        buffer_size: usize,
+        buffer: *const c_void,
+    ) -> Status,
+    pub flush_blocks: extern "efiapi" fn(this: *mut Protocol) -> Status,
+}
+
+impl Protocol {
+    pub const GUID: Guid = Guid {
+        data_1: 0x964e5b21,
+        data_2: 0x6459,
+        data_3: 0x11d2,
+        data_4: [0x8e, 0x39, 0x00, 0xa0, 0xc9, 0x69, 0x72, 0x3b],
+    };
+    pub const REVISION: u64 = 0x00010000;
+    pub const REVISION_2: u64 = 0x00020001;
+    pub const REVISION_3: u64 = 0x0002001f;
+}
+
+// The fields after last_block only exist from revision 2 and the last one from revision 3.
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Media {
+    pub media_id: u32,
+    pub removable_media: bool,
+    pub media_present: bool,
+    pub logical_partition: bool,
+    pub read_only: bool,
+    pub write_caching: bool,
+    pub block_size: u32,
+    pub io_align: u32,
+    pub last_block: Lba,
+    pub lowest_aligned_lba: Lba,
+    pub logical_blocks_per_physical_block: u32,
+    pub optimal_transfer_length_granularity: u32,
+}
@@ -4,6 +4,7 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+use super::device_path::Protocol as DevicePathProtocol;
 use super::primitives::{
     Event, Guid, Handle, PhysicalAddress, Status, TableHeader, VirtualAddress,
 };
@@ -218,13 +219,6 @@ pub struct OpenProtocolInformationEntry {
     pub open_count: u32,
 }
 
-#[repr(C)]
-pub struct DevicePathProtocol {
-    pub node_type: u8,
-    pub sub_node_type: u8,
-    pub length: [u8; 2],
-}
-
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum LocateSearchType {
@@ -254,6 +248,7 @@ pub struct EventNotify {
 }
 
 #[repr(C)]
+#[derive(Clone, Copy, Debug)]
 pub struct MemoryDescriptor {
     pub region_type: MemoryType,
     pub physical_start: PhysicalAddress,
@@ -279,7 +274,7 @@ flags!(
         NV = 0x0000000000008000;
         MORE_RELIABLE = 0x0000000000010000;
         RO = 0x0000000000020000;
-        RUNTIME = 8000000000000000;
+        RUNTIME = 0x8000000000000000;
     }
 );
 
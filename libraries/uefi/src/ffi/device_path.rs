@@ -7,7 +7,8 @@
 use crate::Guid;
 use enums::c_enum;
 
-//TODO Better handling of subtypes and additional fields.
+// The header of every node. A device path is a sequence of nodes that ends with an end node.
+// The length includes the header and nodes are not aligned.
 
 #[repr(C)]
 #[derive(Copy, Clone)]
@@ -40,6 +41,80 @@ c_enum!(
         MESSAGING = 0x03,
         MEDIA = 0x04,
         BIOS_BOOT_SPECIFICATION = 0x05,
-        END_OF_HARDWARE = 6,
+        END = 0x7F,
     }
 );
+
+c_enum!(
+    pub enum HardwareSubType : u8 {
+        PCI = 0x01,
+        PC_CARD = 0x02,
+        MEMORY_MAPPED = 0x03,
+        VENDOR = 0x04,
+        CONTROLLER = 0x05,
+        BMC = 0x06,
+    }
+);
+
+c_enum!(
+    pub enum AcpiSubType : u8 {
+        ACPI = 0x01,
+        EXPANDED_ACPI = 0x02,
+        ADR = 0x03,
+    }
+);
+
+c_enum!(
+    pub enum MessagingSubType : u8 {
+        ATAPI = 0x01,
+        SCSI = 0x02,
+        FIBRE_CHANNEL = 0x03,
+        IEEE_1394 = 0x04,
+        USB = 0x05,
+        I2O = 0x06,
+        INFINIBAND = 0x09,
+        VENDOR = 0x0A,
+        MAC_ADDRESS = 0x0B,
+        IPV4 = 0x0C,
+        IPV6 = 0x0D,
+        UART = 0x0E,
+        USB_CLASS = 0x0F,
+        USB_WWID = 0x10,
+        DEVICE_LOGICAL_UNIT = 0x11,
+        SATA = 0x12,
+        ISCSI = 0x13,
+        VLAN = 0x14,
+        FIBRE_CHANNEL_EX = 0x15,
+        SAS_EX = 0x16,
+        NVME_NAMESPACE = 0x17,
+        URI = 0x18,
+        UFS = 0x19,
+        SD = 0x1A,
+        BLUETOOTH = 0x1B,
+        WIFI = 0x1C,
+        EMMC = 0x1D,
+    }
+);
+
+c_enum!(
+    pub enum MediaSubType : u8 {
+        HARD_DRIVE = 0x01,
+        CD_ROM = 0x02,
+        VENDOR = 0x03,
+        FILE_PATH = 0x04,
+        MEDIA_PROTOCOL = 0x05,
+        PIWG_FIRMWARE_FILE = 0x06,
+        PIWG_FIRMWARE_VOLUME = 0x07,
+        RELATIVE_OFFSET_RANGE = 0x08,
+        RAM_DISK = 0x09,
+    }
+);
+
+c_enum!(
+    pub enum EndSubType : u8 {
+        INSTANCE = 0x01,
+        ENTIRE = 0xFF,
+    }
+);
+
+pub const HEADER_SIZE: usize = 4;
@@ -0,0 +1,37 @@
+//**************************************************************************************************
+// disk_io.rs                                                                                      *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use super::primitives::{Guid, Status};
+use core::ffi::c_void;
+
+#[repr(C)]
+pub struct Protocol {
+    pub revision: u64,
+    pub read_disk: extern "efiapi" fn(
+        this: *mut Protocol,
+        media_id: u32,
+        offset: u64,
+        buffer_size: usize,
+        buffer: *mut c_void,
+    ) -> Status,
+    pub write_disk: extern "efiapi" fn(
+        this: *mut Protocol,
+        media_id: u32,
+        offset: u64,
+        buffer_size: usize,
+        buffer: *const c_void,
+    ) -> Status,
+}
+
+impl Protocol {
+    pub const GUID: Guid = Guid {
+        data_1: 0xce345171,
+        data_2: 0xba0b,
+        data_3: 0x11d2,
+        data_4: [0x8e, 0x4f, 0x00, 0xa0, 0xc9, 0x69, 0x72, 0x3b],
+    };
+    pub const REVISION: u64 = 0x00010000;
+}
@@ -4,13 +4,16 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+pub mod block_io;
 pub mod boot;
 pub mod configuration;
 pub mod device_path;
+pub mod disk_io;
 pub mod file;
 pub mod graphics_output;
 pub mod loaded_image;
 mod primitives;
+pub mod rng;
 pub mod runtime;
 pub mod simple_file_system;
 pub mod simple_text_input;
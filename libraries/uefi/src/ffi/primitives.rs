@@ -4,8 +4,12 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+use crate::error::Error;
+use alloc::vec::Vec;
 use core::ffi::c_void;
+use core::fmt;
 use core::mem;
+use core::str::FromStr;
 use enums::c_enum;
 
 const ERROR_BIT: usize = 1 << ((mem::size_of::<usize>() * 8) - 1);
@@ -67,6 +71,102 @@ pub struct Guid {
     pub data_4: [u8; 8],
 }
 
+// GUIDs are stored with the first three fields little endian wherever UEFI puts them on disk or
+// in memory. They are written in the registry format with those fields as big endian numbers.
+
+impl Guid {
+    pub const NULL: Guid = Guid {
+        data_1: 0,
+        data_2: 0,
+        data_3: 0,
+        data_4: [0; 8],
+    };
+
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        let mut data_4 = [0; 8];
+        data_4.copy_from_slice(&bytes[8..]);
+
+        Guid {
+            data_1: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            data_2: u16::from_le_bytes([bytes[4], bytes[5]]),
+            data_3: u16::from_le_bytes([bytes[6], bytes[7]]),
+            data_4,
+        }
+    }
+
+    pub fn to_bytes(self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+
+        bytes[..4].copy_from_slice(&self.data_1.to_le_bytes());
+        bytes[4..6].copy_from_slice(&self.data_2.to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.data_3.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.data_4);
+
+        bytes
+    }
+
+    pub fn is_null(self) -> bool {
+        self == Self::NULL
+    }
+}
+
+// Parses the registry format with or without braces.
+
+impl FromStr for Guid {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .unwrap_or(s);
+
+        let groups = s.split('-').collect::<Vec<_>>();
+
+        if groups.len() != 5
+            || groups.iter().zip(&[8, 4, 4, 4, 12]).any(|(group, &len)| {
+                group.len() != len || !group.chars().all(|c| c.is_ascii_hexdigit())
+            })
+        {
+            return Err(Error::InvalidArgument("guid"));
+        }
+
+        // Every group is known to be valid hexadecimal at this point.
+
+        let parse_u64 = |group: &str| u64::from_str_radix(group, 16).unwrap();
+
+        let clock = parse_u64(groups[3]).to_be_bytes();
+        let node = parse_u64(groups[4]).to_be_bytes();
+
+        let mut data_4 = [0; 8];
+        data_4[..2].copy_from_slice(&clock[6..]);
+        data_4[2..].copy_from_slice(&node[2..]);
+
+        Ok(Guid {
+            data_1: parse_u64(groups[0]) as u32,
+            data_2: parse_u64(groups[1]) as u16,
+            data_3: parse_u64(groups[2]) as u16,
+            data_4,
+        })
+    }
+}
+
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-",
+            self.data_1, self.data_2, self.data_3, self.data_4[0], self.data_4[1]
+        )?;
+
+        for byte in &self.data_4[2..] {
+            write!(f, "{:02X}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
 pub type Handle = *mut c_void;
 pub type Event = *mut c_void;
 
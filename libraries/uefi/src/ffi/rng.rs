@@ -0,0 +1,33 @@
+//**************************************************************************************************
+// rng.rs                                                                                          *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::{Guid, Status};
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Protocol {
+    pub get_info: extern "efiapi" fn(
+        this: *mut Protocol,
+        algorithm_list_size: *mut usize,
+        algorithm_list: *mut Guid,
+    ) -> Status,
+    // A null algorithm selects the default one of the implementation.
+    pub get_rng: extern "efiapi" fn(
+        this: *mut Protocol,
+        algorithm: *mut Guid,
+        value_len: usize,
+        value: *mut u8,
+    ) -> Status,
+}
+
+impl Protocol {
+    pub const GUID: Guid = Guid {
+        data_1: 0x3152BCA5,
+        data_2: 0xEADE,
+        data_3: 0x433D,
+        data_4: [0x86, 0x2E, 0xC0, 0x1C, 0xDC, 0x29, 0x1F, 0x44],
+    };
+}
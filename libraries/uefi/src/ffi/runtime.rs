@@ -4,6 +4,7 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+use super::boot::MemoryDescriptor;
 use super::primitives::{Guid, PhysicalAddress, Status, TableHeader, Time};
 use super::system;
 use core::ffi::c_void;
@@ -18,15 +19,40 @@ pub struct Services {
     pub get_wakeup_time:
         extern "efiapi" fn(enabled: *mut bool, pending: *mut bool, time: *mut Time) -> Status,
     pub set_wakeup_time: extern "efiapi" fn(enabled: bool, time: *mut Time) -> Status,
+    pub set_virtual_address_map: extern "efiapi" fn(
+        memory_map_size: usize,
+        descriptor_size: usize,
+        descriptor_version: u32,
+        virtual_map: *mut MemoryDescriptor,
+    ) -> Status,
     pub convert_pointer:
         extern "efiapi" fn(debug_disposition: usize, address: *mut *mut c_void) -> Status,
+    pub get_variable: extern "efiapi" fn(
+        variable_name: *const u16,
+        vendor_guid: *const Guid,
+        attributes: *mut VariableAttributes,
+        data_size: *mut usize,
+        data: *mut c_void,
+    ) -> Status,
+    pub get_next_variable_name: extern "efiapi" fn(
+        variable_name_size: *mut usize,
+        variable_name: *mut u16,
+        vendor_guid: *mut Guid,
+    ) -> Status,
+    pub set_variable: extern "efiapi" fn(
+        variable_name: *const u16,
+        vendor_guid: *const Guid,
+        attributes: VariableAttributes,
+        data_size: usize,
+        data: *const c_void,
+    ) -> Status,
+    pub get_next_high_monotonic_count: extern "efiapi" fn(high_count: *mut u32) -> Status,
     pub reset_system: extern "efiapi" fn(
         reset_type: ResetType,
         status: Status,
         data_size: usize,
         reset_data: *mut c_void,
     ),
-    pub get_next_high_monotonic_count: extern "efiapi" fn(high_count: *mut u32) -> Status,
     pub update_capsule: extern "efiapi" fn(
         capsule_header_array: *mut *mut CapsuleHeader,
         capsule_count: usize,
@@ -39,7 +65,7 @@ pub struct Services {
         reset_type: *mut ResetType,
     ) -> Status,
     pub query_variable_info: extern "efiapi" fn(
-        attributes: u32,
+        attributes: VariableAttributes,
         maximum_variable_storage_size: *mut u64,
         remaining_variable_storage_size: *mut u64,
         maximum_variable_size: *mut u64,
@@ -49,10 +75,19 @@ pub struct Services {
 impl Services {
     pub const SIGNATURE: u64 = 0x56524553544e5552;
     pub const REVISION: u32 = system::Table::LATEST_REVISION;
+
+    // Vendor of the architecturally defined variables such as BootOrder and BootCurrent.
+
+    pub const GLOBAL_VARIABLE_GUID: Guid = Guid {
+        data_1: 0x8be4df61,
+        data_2: 0x93ca,
+        data_3: 0x11d2,
+        data_4: [0xaa, 0x0d, 0x00, 0xe0, 0x98, 0x03, 0x2b, 0x8c],
+    };
 }
 
 #[repr(C)]
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum ResetType {
     Cold,
     Warm,
@@ -82,3 +117,16 @@ flags!(
         INITIATE_RESET = 0x00040000;
     }
 );
+
+flags!(
+    pub struct VariableAttributes : u32 {
+        NON_VOLATILE = 0x00000001;
+        BOOTSERVICE_ACCESS = 0x00000002;
+        RUNTIME_ACCESS = 0x00000004;
+        HARDWARE_ERROR_RECORD = 0x00000008;
+        AUTHENTICATED_WRITE_ACCESS = 0x00000010;
+        TIME_BASED_AUTHENTICATED_WRITE_ACCESS = 0x00000020;
+        APPEND_WRITE = 0x00000040;
+        ENHANCED_AUTHENTICATED_ACCESS = 0x00000080;
+    }
+);
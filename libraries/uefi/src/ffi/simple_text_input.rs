@@ -5,6 +5,7 @@
 //**************************************************************************************************
 
 use super::primitives::{Event, Guid, Status};
+use enums::c_enum;
 
 #[repr(C)]
 pub struct Protocol {
@@ -25,6 +26,33 @@ impl Protocol {
 #[repr(C)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct InputKey {
-    pub scan_code: u16,
+    pub scan_code: ScanCode,
     pub unicode_char: u16,
 }
+
+c_enum!(
+    pub enum ScanCode : u16 {
+        NULL = 0x00,
+        UP = 0x01,
+        DOWN = 0x02,
+        RIGHT = 0x03,
+        LEFT = 0x04,
+        HOME = 0x05,
+        END = 0x06,
+        INSERT = 0x07,
+        DELETE = 0x08,
+        PAGE_UP = 0x09,
+        PAGE_DOWN = 0x0A,
+        F1 = 0x0B,
+        F2 = 0x0C,
+        F3 = 0x0D,
+        F4 = 0x0E,
+        F5 = 0x0F,
+        F6 = 0x10,
+        F7 = 0x11,
+        F8 = 0x12,
+        F9 = 0x13,
+        F10 = 0x14,
+        ESCAPE = 0x17,
+    }
+);
@@ -4,11 +4,15 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+pub use super::ffi::graphics_output::BltPixel;
+
 use super::error::Error;
 use super::ffi::graphics_output;
+use super::ffi::graphics_output::BltOperation;
 use super::ffi::{PhysicalAddress, Status};
 use super::protocol;
 use core::iter::FusedIterator;
+use core::mem;
 use core::ptr;
 
 #[derive(Debug)]
@@ -277,6 +281,128 @@ impl Output {
             Some(gop_mode.frame_buffer_base)
         }
     }
+
+    pub fn framebuffer_size(&self) -> usize {
+        unsafe {
+            let gop = &*self.0.get::<graphics_output::Protocol>();
+            let gop_mode = &*gop.mode;
+
+            gop_mode.frame_buffer_size
+        }
+    }
+
+    pub fn pixels_per_scan_line(&self) -> u32 {
+        unsafe {
+            let gop = &*self.0.get::<graphics_output::Protocol>();
+            let gop_mode = &*gop.mode;
+            let gop_mode_info = &*gop_mode.info;
+
+            gop_mode_info.pixels_per_scan_line
+        }
+    }
+
+    pub fn pixel_format(&self) -> graphics_output::PixelFormat {
+        unsafe {
+            let gop = &*self.0.get::<graphics_output::Protocol>();
+            let gop_mode = &*gop.mode;
+            let gop_mode_info = &*gop_mode.info;
+
+            gop_mode_info.pixel_format
+        }
+    }
+
+    pub fn pixel_bit_mask(&self) -> graphics_output::PixelBitMask {
+        unsafe {
+            let gop = &*self.0.get::<graphics_output::Protocol>();
+            let gop_mode = &*gop.mode;
+            let gop_mode_info = &*gop_mode.info;
+
+            gop_mode_info.pixel_information
+        }
+    }
+
+    // Fills a rectangle with one pixel. Blt works in every mode including ones without a
+    // framebuffer.
+
+    pub fn fill(
+        &mut self,
+        pixel: BltPixel,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<(), Error> {
+        let mut pixel = pixel;
+
+        self.blt(
+            &mut pixel,
+            BltOperation::VideoFill,
+            (x, y),
+            width,
+            height,
+            0,
+        )
+    }
+
+    // Copies a rectangle of width by height pixels from the buffer to the screen. The rows of the
+    // buffer are next to each other.
+
+    pub fn draw(
+        &mut self,
+        buffer: &[BltPixel],
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<(), Error> {
+        if buffer.len() < width * height {
+            return Err(Error::InvalidArgument("buffer"));
+        }
+
+        self.blt(
+            buffer.as_ptr() as *mut BltPixel,
+            BltOperation::BufferToVideo,
+            (x, y),
+            width,
+            height,
+            width * mem::size_of::<BltPixel>(),
+        )
+    }
+
+    fn blt(
+        &mut self,
+        buffer: *mut BltPixel,
+        operation: BltOperation,
+        destination: (usize, usize),
+        width: usize,
+        height: usize,
+        delta: usize,
+    ) -> Result<(), Error> {
+        unsafe {
+            let interface = self.0.get::<graphics_output::Protocol>();
+            let gop = &*interface;
+
+            let status = (gop.blt)(
+                interface,
+                buffer,
+                operation,
+                0,
+                0,
+                destination.0,
+                destination.1,
+                width,
+                height,
+                delta,
+            );
+
+            match status {
+                Status::SUCCESS => Ok(()),
+                Status::INVALID_PARAMETER => Err(Error::InvalidArgument("rectangle")),
+                Status::DEVICE_ERROR => Err(Error::DeviceError),
+                _ => Err(Error::UnexpectedStatus(status)),
+            }
+        }
+    }
 }
 
 impl<'a> IntoIterator for &'a Output {
@@ -0,0 +1,266 @@
+//**************************************************************************************************
+// image.rs                                                                                        *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::device_path::DevicePath;
+use crate::error::Error;
+use crate::ffi::{boot, loaded_image, Status};
+use crate::{protocol, system, Handle};
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use core::ffi::c_void;
+use core::{char, mem, ptr};
+
+// Returns the load options of the current image as text. Boot managers and the shell pass the
+// command line this way as a null terminated UCS-2 string. Options that are not valid UCS-2 are
+// decoded with replacement characters.
+
+pub fn load_options() -> Result<String, Error> {
+    unsafe {
+        let interface = protocol::Interface::open(loaded_image::Protocol::GUID, system::handle()?)?;
+        let loaded_image_protocol = &*interface.get::<loaded_image::Protocol>();
+
+        if loaded_image_protocol.load_options.is_null() {
+            return Ok(String::new());
+        }
+
+        Ok(decode_lossy(
+            loaded_image_protocol.load_options as *const u16,
+            loaded_image_protocol.load_options_size as usize,
+        ))
+    }
+}
+
+// The handle of the device the current image was loaded from.
+
+pub fn device_handle() -> Result<Handle, Error> {
+    unsafe {
+        let interface = protocol::Interface::open(loaded_image::Protocol::GUID, system::handle()?)?;
+        Ok((*interface.get::<loaded_image::Protocol>()).device_handle)
+    }
+}
+
+// The path of the current image's file relative to its device. This is usually a single file path
+// node.
+
+pub fn file_path() -> Result<DevicePath, Error> {
+    unsafe {
+        let interface = protocol::Interface::open(loaded_image::Protocol::GUID, system::handle()?)?;
+        DevicePath::from_ptr((*interface.get::<loaded_image::Protocol>()).file_path)
+    }
+}
+
+// The full path of the current image including its device.
+
+pub fn device_path() -> Result<DevicePath, Error> {
+    let mut path = DevicePath::of_handle(device_handle()?)?;
+    path.append(&file_path()?);
+    Ok(path)
+}
+
+// The full path of a file on the device the current image was loaded from. The path uses
+// backslashes like other UEFI file paths.
+
+pub fn file_device_path(path: &str) -> Result<DevicePath, Error> {
+    let mut device_path = DevicePath::of_handle(device_handle()?)?;
+    device_path.append(&DevicePath::file(path)?);
+    Ok(device_path)
+}
+
+// An image loaded by the current one. Images that were not started are unloaded when dropped.
+
+#[derive(Debug)]
+pub struct Image {
+    handle: Handle,
+    // Kept alive until the image was started since the firmware only stores the pointer.
+    load_options: Option<Box<[u16]>>,
+}
+
+impl Image {
+    // Loads the image at the path. The path must include the device, like the ones from
+    // file_device_path.
+
+    pub fn load(path: &DevicePath) -> Result<Self, Error> {
+        let mut path_copy = path.clone();
+
+        unsafe {
+            Self::load_raw(path_copy.as_mut_ptr(), ptr::null_mut(), 0).map_err(
+                |error| match error {
+                    Error::PathNonExistent(_) => Error::PathNonExistent(path.to_string()),
+                    error => error,
+                },
+            )
+        }
+    }
+
+    // Loads an image that was already read into memory. The path is only used to describe where
+    // the image came from.
+
+    pub fn load_from_buffer(buffer: &[u8], path: Option<&DevicePath>) -> Result<Self, Error> {
+        let mut path_copy = path.cloned();
+
+        let path_ptr = path_copy
+            .as_mut()
+            .map_or(ptr::null_mut(), |path| path.as_mut_ptr());
+
+        unsafe { Self::load_raw(path_ptr, buffer.as_ptr() as *mut c_void, buffer.len()) }
+    }
+
+    unsafe fn load_raw(
+        path: *mut crate::ffi::device_path::Protocol,
+        buffer: *mut c_void,
+        buffer_size: usize,
+    ) -> Result<Self, Error> {
+        let boot_services = &*boot_services()?;
+        let mut handle = ptr::null_mut();
+
+        let status = (boot_services.load_image)(
+            false,
+            system::handle()?,
+            path,
+            buffer,
+            buffer_size,
+            &mut handle,
+        );
+
+        match status {
+            Status::SUCCESS => Ok(Self {
+                handle,
+                load_options: None,
+            }),
+            Status::NOT_FOUND => Err(Error::PathNonExistent(String::new())),
+            Status::INVALID_PARAMETER => Err(Error::InvalidArgument("path")),
+            Status::UNSUPPORTED | Status::LOAD_ERROR => Err(Error::InvalidImage),
+            Status::OUT_OF_RESOURCES => Err(Error::OutOfMemory),
+            Status::DEVICE_ERROR => Err(Error::DeviceError),
+            Status::ACCESS_DENIED => Err(Error::OperationDenied),
+            Status::SECURITY_VIOLATION => {
+                // The image is loaded but can't be started in this case.
+
+                if !handle.is_null() {
+                    (boot_services.unload_image)(handle);
+                }
+
+                Err(Error::SecurityViolation)
+            }
+            _ => Err(Error::UnexpectedStatus(status)),
+        }
+    }
+
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    // Sets the options the image reads with load_options. Applications usually expect their own
+    // path as the first word like a command line.
+
+    pub fn set_load_options(&mut self, options: &str) -> Result<(), Error> {
+        let options = ucs2::encode_string_with_null(options)
+            .map_err(|_| Error::InvalidArgument("options"))?;
+
+        unsafe {
+            let interface = protocol::Interface::open(loaded_image::Protocol::GUID, self.handle)?;
+            let loaded_image_protocol = &mut *interface.get::<loaded_image::Protocol>();
+
+            loaded_image_protocol.load_options = options.as_ptr() as *mut c_void;
+            loaded_image_protocol.load_options_size = (options.len() * 2) as u32;
+        }
+
+        self.load_options = Some(options);
+
+        Ok(())
+    }
+
+    // Runs the image until it exits. Applications are unloaded by the firmware when they exit so
+    // the image can't be used afterwards.
+
+    pub fn start(mut self) -> Result<(), Error> {
+        let handle = self.handle;
+        let load_options = self.load_options.take();
+
+        mem::forget(self);
+
+        unsafe {
+            let boot_services = &*boot_services()?;
+
+            let mut exit_data_size = 0;
+            let mut exit_data = ptr::null_mut();
+
+            let status = (boot_services.start_image)(handle, &mut exit_data_size, &mut exit_data);
+
+            drop(load_options);
+
+            // The image can return a description of the error. It is allocated from pool memory
+            // and has to be freed by the caller.
+
+            let description = if exit_data.is_null() {
+                String::new()
+            } else {
+                let description = decode_lossy(exit_data, exit_data_size);
+                (boot_services.free_pool)(exit_data as *mut c_void);
+                description
+            };
+
+            match status {
+                Status::SUCCESS => Ok(()),
+                Status::INVALID_PARAMETER => Err(Error::InvalidArgument("image")),
+                Status::SECURITY_VIOLATION => Err(Error::SecurityViolation),
+                _ => Err(Error::ImageFailed(status, description)),
+            }
+        }
+    }
+
+    pub fn unload(self) -> Result<(), Error> {
+        let handle = self.handle;
+
+        mem::forget(self);
+
+        unsafe {
+            let boot_services = &*boot_services()?;
+
+            match (boot_services.unload_image)(handle) {
+                Status::SUCCESS => Ok(()),
+                Status::INVALID_PARAMETER => Err(Error::InvalidArgument("image")),
+                Status::UNSUPPORTED => Err(Error::NotSupported),
+                status => Err(Error::UnexpectedStatus(status)),
+            }
+        }
+    }
+}
+
+impl Drop for Image {
+    fn drop(&mut self) {
+        unsafe {
+            if let Ok(boot_services) = boot_services() {
+                ((*boot_services).unload_image)(self.handle);
+            }
+        }
+    }
+}
+
+fn boot_services() -> Result<*mut boot::Services, Error> {
+    unsafe {
+        let system_table = &*system::table()?;
+
+        if system_table.boot_services.is_null() {
+            return Err(Error::BootServicesUnavailable);
+        }
+
+        Ok(system_table.boot_services)
+    }
+}
+
+// Decodes a null terminated UCS-2 string of at most the size in bytes. The strings passed between
+// images are not guaranteed to be aligned or valid.
+
+unsafe fn decode_lossy(string: *const u16, size: usize) -> String {
+    let units = (0..size / 2)
+        .map(|index| ptr::read_unaligned(string.add(index)))
+        .take_while(|&unit| unit != 0);
+
+    char::decode_utf16(units)
+        .map(|result| result.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
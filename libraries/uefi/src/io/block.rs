@@ -0,0 +1,409 @@
+//**************************************************************************************************
+// block.rs                                                                                        *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+pub use crate::ffi::block_io::Lba;
+
+use crate::error::Error;
+use crate::ffi::{block_io, disk_io, loaded_image, Status};
+use crate::{protocol, Handle};
+use core::ffi::c_void;
+use core::iter::FusedIterator;
+
+// Firmware creates a block device for every disk and another one for every partition it finds on
+// them. The blocks of a partition's device start at the beginning of the partition.
+
+#[derive(Debug)]
+pub struct BlockDeviceBuffer(protocol::HandleBuffer);
+
+impl BlockDeviceBuffer {
+    pub fn locate() -> Result<Self, Error> {
+        let handle_buffer = protocol::HandleBuffer::locate(block_io::Protocol::GUID)?;
+        Ok(BlockDeviceBuffer(handle_buffer))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn open(&self, index: usize) -> Result<BlockDevice, Error> {
+        let protocol = self.0.open(index)?;
+        Ok(BlockDevice(protocol))
+    }
+
+    pub fn iter(&self) -> BlockDeviceIterator {
+        BlockDeviceIterator(self.0.iter())
+    }
+}
+
+impl<'a> IntoIterator for &'a BlockDeviceBuffer {
+    type Item = Result<BlockDevice, Error>;
+    type IntoIter = BlockDeviceIterator<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct BlockDeviceIterator<'a>(protocol::InterfaceIterator<'a>);
+
+impl<'a> Iterator for BlockDeviceIterator<'a> {
+    type Item = Result<BlockDevice, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|result| result.map(BlockDevice))
+    }
+}
+
+impl<'a> FusedIterator for BlockDeviceIterator<'a> {}
+
+#[derive(Debug)]
+pub struct BlockDevice(protocol::Interface);
+
+impl BlockDevice {
+    pub fn new(interface: protocol::Interface) -> Result<Self, Error> {
+        if interface.protocol_guid() != block_io::Protocol::GUID {
+            return Err(Error::InvalidArgument("interface"));
+        }
+        Ok(BlockDevice(interface))
+    }
+
+    pub unsafe fn new_unchecked(interface: protocol::Interface) -> Self {
+        BlockDevice(interface)
+    }
+
+    pub fn open(handle: Handle) -> Result<Self, Error> {
+        let interface = protocol::Interface::open(block_io::Protocol::GUID, handle)?;
+        Ok(BlockDevice(interface))
+    }
+
+    // The partition the image was loaded from.
+
+    pub fn containing_image(image_handle: Handle) -> Result<Self, Error> {
+        unsafe {
+            let loaded_image_interface =
+                protocol::Interface::open(loaded_image::Protocol::GUID, image_handle)?;
+            let loaded_image_protocol = &*loaded_image_interface.get::<loaded_image::Protocol>();
+            Self::open(loaded_image_protocol.device_handle)
+        }
+    }
+
+    pub fn containing_current_image() -> Result<Self, Error> {
+        Self::containing_image(crate::system::handle()?)
+    }
+
+    pub fn handle(&self) -> Handle {
+        self.0.handle()
+    }
+
+    pub fn media(&self) -> MediaInfo {
+        unsafe {
+            let block_io = &*self.0.get::<block_io::Protocol>();
+            let media = block_io.media;
+
+            // Fields added by later revisions are not read from older firmware since the
+            // structure ends before them.
+
+            let blocks_per_physical_block = if block_io.revision >= block_io::Protocol::REVISION_2 {
+                (*media).logical_blocks_per_physical_block.max(1)
+            } else {
+                1
+            };
+
+            MediaInfo {
+                media_id: (*media).media_id,
+                is_removable: (*media).removable_media,
+                is_present: (*media).media_present,
+                is_partition: (*media).logical_partition,
+                is_read_only: (*media).read_only,
+                has_write_caching: (*media).write_caching,
+                block_size: (*media).block_size,
+                io_align: (*media).io_align,
+                last_block: (*media).last_block,
+                blocks_per_physical_block,
+            }
+        }
+    }
+
+    pub fn reset(&mut self, extended_verification: bool) -> Result<(), Error> {
+        unsafe {
+            let interface = self.0.get::<block_io::Protocol>();
+            let status = ((*interface).reset)(interface, extended_verification);
+
+            match status {
+                Status::SUCCESS => Ok(()),
+                Status::DEVICE_ERROR => Err(Error::DeviceError),
+                _ => Err(Error::UnexpectedStatus(status)),
+            }
+        }
+    }
+
+    // Reads whole blocks starting at the block address. The buffer must be a multiple of the
+    // block size and aligned to the media's IO alignment.
+
+    pub fn read_blocks(&mut self, lba: Lba, buffer: &mut [u8]) -> Result<(), Error> {
+        let media = self.media();
+
+        self.check_buffer(&media, buffer.as_ptr(), buffer.len())?;
+
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        unsafe {
+            let interface = self.0.get::<block_io::Protocol>();
+
+            let status = ((*interface).read_blocks)(
+                interface,
+                media.media_id,
+                lba,
+                buffer.len(),
+                buffer.as_mut_ptr() as *mut c_void,
+            );
+
+            transfer_result(status)
+        }
+    }
+
+    pub fn write_blocks(&mut self, lba: Lba, buffer: &[u8]) -> Result<(), Error> {
+        let media = self.media();
+
+        if media.is_read_only {
+            return Err(Error::ReadOnlyViolation);
+        }
+
+        self.check_buffer(&media, buffer.as_ptr(), buffer.len())?;
+
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        unsafe {
+            let interface = self.0.get::<block_io::Protocol>();
+
+            let status = ((*interface).write_blocks)(
+                interface,
+                media.media_id,
+                lba,
+                buffer.len(),
+                buffer.as_ptr() as *const c_void,
+            );
+
+            transfer_result(status)
+        }
+    }
+
+    // Writes cached data to the device.
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        unsafe {
+            let interface = self.0.get::<block_io::Protocol>();
+            let status = ((*interface).flush_blocks)(interface);
+
+            transfer_result(status)
+        }
+    }
+
+    fn check_buffer(&self, media: &MediaInfo, ptr: *const u8, len: usize) -> Result<(), Error> {
+        if !media.is_present {
+            return Err(Error::NoMedia);
+        }
+
+        if media.block_size == 0 || len % media.block_size as usize != 0 {
+            return Err(Error::InvalidArgument("buffer"));
+        }
+
+        // An alignment of 0 or 1 means the buffer can be anywhere.
+
+        if media.io_align > 1 && (ptr as usize) % media.io_align as usize != 0 {
+            return Err(Error::InvalidArgument("buffer"));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MediaInfo {
+    media_id: u32,
+    is_removable: bool,
+    is_present: bool,
+    is_partition: bool,
+    is_read_only: bool,
+    has_write_caching: bool,
+    block_size: u32,
+    io_align: u32,
+    last_block: Lba,
+    blocks_per_physical_block: u32,
+}
+
+impl MediaInfo {
+    // Changes every time the media in the device is changed.
+
+    pub fn media_id(self) -> u32 {
+        self.media_id
+    }
+
+    pub fn is_removable(self) -> bool {
+        self.is_removable
+    }
+
+    pub fn is_present(self) -> bool {
+        self.is_present
+    }
+
+    pub fn is_partition(self) -> bool {
+        self.is_partition
+    }
+
+    pub fn is_read_only(self) -> bool {
+        self.is_read_only
+    }
+
+    pub fn has_write_caching(self) -> bool {
+        self.has_write_caching
+    }
+
+    pub fn block_size(self) -> u32 {
+        self.block_size
+    }
+
+    pub fn io_align(self) -> u32 {
+        self.io_align
+    }
+
+    pub fn last_block(self) -> Lba {
+        self.last_block
+    }
+
+    pub fn block_count(self) -> u64 {
+        if self.is_present {
+            self.last_block + 1
+        } else {
+            0
+        }
+    }
+
+    pub fn physical_block_size(self) -> u32 {
+        self.block_size * self.blocks_per_physical_block
+    }
+
+    pub fn len(self) -> u64 {
+        self.block_count() * self.block_size as u64
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Byte addressed access to a block device. Firmware reads the blocks around unaligned offsets so
+// buffers can have any size and alignment.
+
+#[derive(Debug)]
+pub struct Disk {
+    interface: protocol::Interface,
+    block_device: BlockDevice,
+}
+
+impl Disk {
+    pub fn new(block_device: BlockDevice) -> Result<Self, Error> {
+        let interface = protocol::Interface::open(disk_io::Protocol::GUID, block_device.handle())?;
+
+        Ok(Self {
+            interface,
+            block_device,
+        })
+    }
+
+    pub fn open(handle: Handle) -> Result<Self, Error> {
+        Self::new(BlockDevice::open(handle)?)
+    }
+
+    pub fn block_device(&self) -> &BlockDevice {
+        &self.block_device
+    }
+
+    pub fn block_device_mut(&mut self) -> &mut BlockDevice {
+        &mut self.block_device
+    }
+
+    pub fn into_block_device(self) -> BlockDevice {
+        self.block_device
+    }
+
+    pub fn media(&self) -> MediaInfo {
+        self.block_device.media()
+    }
+
+    pub fn read(&mut self, offset: u64, buffer: &mut [u8]) -> Result<(), Error> {
+        let media = self.media();
+
+        if !media.is_present {
+            return Err(Error::NoMedia);
+        }
+
+        unsafe {
+            let interface = self.interface.get::<disk_io::Protocol>();
+
+            let status = ((*interface).read_disk)(
+                interface,
+                media.media_id,
+                offset,
+                buffer.len(),
+                buffer.as_mut_ptr() as *mut c_void,
+            );
+
+            transfer_result(status)
+        }
+    }
+
+    pub fn write(&mut self, offset: u64, buffer: &[u8]) -> Result<(), Error> {
+        let media = self.media();
+
+        if !media.is_present {
+            return Err(Error::NoMedia);
+        }
+
+        if media.is_read_only {
+            return Err(Error::ReadOnlyViolation);
+        }
+
+        unsafe {
+            let interface = self.interface.get::<disk_io::Protocol>();
+
+            let status = ((*interface).write_disk)(
+                interface,
+                media.media_id,
+                offset,
+                buffer.len(),
+                buffer.as_ptr() as *const c_void,
+            );
+
+            transfer_result(status)
+        }
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.block_device.flush()
+    }
+}
+
+fn transfer_result(status: Status) -> Result<(), Error> {
+    match status {
+        Status::SUCCESS => Ok(()),
+        Status::DEVICE_ERROR => Err(Error::DeviceError),
+        Status::NO_MEDIA => Err(Error::NoMedia),
+        Status::MEDIA_CHANGED => Err(Error::MediaInvalidated),
+        Status::WRITE_PROTECTED => Err(Error::ReadOnlyViolation),
+        Status::BAD_BUFFER_SIZE => Err(Error::InvalidArgument("buffer")),
+        Status::INVALID_PARAMETER => Err(Error::InvalidArgument("offset")),
+        _ => Err(Error::UnexpectedStatus(status)),
+    }
+}
@@ -4,9 +4,11 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+pub use crate::ffi::simple_text_input::ScanCode;
 pub use crate::ffi::simple_text_output::{BackColor, FrontColor};
 
 use crate::error::Error;
+use crate::ffi::simple_text_input;
 use crate::ffi::simple_text_output;
 use crate::ffi::simple_text_output::ColorAttribute;
 use crate::ffi::Status;
@@ -112,6 +114,86 @@ impl OutputDevice {
     }
 }
 
+#[repr(transparent)]
+#[derive(Clone)]
+pub struct InputDevice(*mut simple_text_input::Protocol);
+
+impl InputDevice {
+    pub unsafe fn new(protocol: *mut simple_text_input::Protocol) -> Self {
+        Self(protocol)
+    }
+
+    pub fn con_in() -> Result<Self, Error> {
+        unsafe {
+            let system_table = &*system::table()?;
+
+            if system_table.con_in.is_null() {
+                return Err(Error::BootServicesUnavailable);
+            }
+
+            Ok(Self::new(system_table.con_in))
+        }
+    }
+
+    // Discards keys that were pressed before this point.
+
+    pub fn reset(&mut self) -> Result<(), Error> {
+        unsafe {
+            let input = &*self.0;
+            let status = (input.reset)(self.0, false);
+
+            match status {
+                Status::SUCCESS => Ok(()),
+                Status::DEVICE_ERROR => Err(Error::DeviceError),
+                _ => Err(Error::UnexpectedStatus(status)),
+            }
+        }
+    }
+
+    // Returns the next key without waiting or None if no key was pressed.
+
+    pub fn read_key(&mut self) -> Result<Option<Key>, Error> {
+        unsafe {
+            let input = &*self.0;
+
+            let mut input_key = simple_text_input::InputKey {
+                scan_code: ScanCode::NULL,
+                unicode_char: 0,
+            };
+
+            let status = (input.read_key_stroke)(self.0, &mut input_key);
+
+            match status {
+                Status::SUCCESS => Ok(Some(Key::from(input_key))),
+                Status::NOT_READY => Ok(None),
+                Status::DEVICE_ERROR => Err(Error::DeviceError),
+                Status::UNSUPPORTED => Err(Error::NotSupported),
+                _ => Err(Error::UnexpectedStatus(status)),
+            }
+        }
+    }
+}
+
+// Keys with a printable character are reported as characters. Everything else uses its scan code.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Special(ScanCode),
+}
+
+impl From<simple_text_input::InputKey> for Key {
+    fn from(input_key: simple_text_input::InputKey) -> Self {
+        if input_key.scan_code == ScanCode::NULL {
+            if let Some(character) = core::char::from_u32(input_key.unicode_char as u32) {
+                return Key::Char(character);
+            }
+        }
+
+        Key::Special(input_key.scan_code)
+    }
+}
+
 impl fmt::Write for OutputDevice {
     fn write_str(&mut self, s: &str) -> Result<(), fmt::Error> {
         if s.is_empty() {
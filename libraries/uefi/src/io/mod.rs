@@ -4,6 +4,7 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
+pub mod block;
 pub mod console;
 pub mod storage;
 
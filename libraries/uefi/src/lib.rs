@@ -15,11 +15,16 @@ pub mod ffi;
 #[macro_use]
 pub mod memory;
 pub mod graphics;
+pub mod image;
 #[macro_use]
 pub mod io;
 pub mod configuration;
+pub mod device_path;
 pub mod protocol;
+pub mod random;
+pub mod runtime;
 pub mod system;
+pub mod variable;
 
 pub use self::error::*;
 pub use self::ffi::system::Table as SystemTable;
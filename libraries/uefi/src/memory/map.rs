@@ -4,9 +4,8 @@
 // This code is made available under the MIT License.                                              *
 //**************************************************************************************************
 
-pub use crate::ffi::boot::MemoryType;
+pub use crate::ffi::boot::{MemoryAttributes, MemoryDescriptor, MemoryType};
 
-use crate::ffi::boot::MemoryDescriptor;
 use crate::ffi::Status;
 use crate::memory::PAGE_SIZE;
 use crate::{system, Error};
@@ -58,6 +57,21 @@ impl<'a> MemoryMapEntry<'a> {
     pub fn region_type(&self) -> MemoryType {
         self.0.region_type
     }
+
+    pub fn attributes(&self) -> MemoryAttributes {
+        self.0.attribute
+    }
+
+    // Regions with the runtime attribute must be kept and mapped after boot services are exited
+    // so the runtime services can still be called.
+
+    pub fn is_runtime(&self) -> bool {
+        self.0.attribute.contains(MemoryAttributes::RUNTIME)
+    }
+
+    pub fn descriptor(&self) -> MemoryDescriptor {
+        *self.0
+    }
 }
 
 pub struct MemoryMap {
@@ -171,6 +171,7 @@ impl Interface {
                     value,
                 }),
                 Status::INVALID_PARAMETER => Err(Error::InvalidArgument("handle")),
+                Status::UNSUPPORTED => Err(Error::NotSupported),
                 _ => Err(Error::UnexpectedStatus(status)),
             }
         }
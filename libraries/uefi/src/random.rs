@@ -0,0 +1,32 @@
+//**************************************************************************************************
+// random.rs                                                                                       *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+use crate::error::Error;
+use crate::ffi::{rng, Status};
+use crate::protocol;
+use core::ptr;
+
+// Fills the buffer with random bytes from the first RNG protocol using its default algorithm.
+// Firmware often does not provide the protocol so callers should have another source.
+
+pub fn fill(buffer: &mut [u8]) -> Result<(), Error> {
+    let handle_buffer = protocol::HandleBuffer::locate(rng::Protocol::GUID)?;
+    let interface = handle_buffer.open(0)?;
+
+    unsafe {
+        let protocol = interface.get::<rng::Protocol>();
+
+        let status =
+            ((*protocol).get_rng)(protocol, ptr::null_mut(), buffer.len(), buffer.as_mut_ptr());
+
+        match status {
+            Status::SUCCESS => Ok(()),
+            Status::UNSUPPORTED => Err(Error::NotSupported),
+            Status::DEVICE_ERROR | Status::NOT_READY => Err(Error::DeviceError),
+            _ => Err(Error::UnexpectedStatus(status)),
+        }
+    }
+}
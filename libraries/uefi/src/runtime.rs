@@ -0,0 +1,148 @@
+//**************************************************************************************************
+// runtime.rs                                                                                      *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+pub use crate::ffi::runtime::ResetType;
+pub use crate::ffi::Time;
+
+use crate::error::Error;
+use crate::ffi::boot::MemoryDescriptor;
+use crate::ffi::runtime::Services;
+use crate::ffi::Status;
+use crate::memory::PAGE_SIZE;
+use crate::system;
+use core::mem;
+use core::ptr;
+
+// The runtime services stay available after boot services are exited. The table is held
+// directly instead of being found through the system table so a kernel can use them once the
+// system table is gone. The services are not reentrant so callers must serialize calls.
+
+#[derive(Debug)]
+pub struct RuntimeServices {
+    services: *mut Services,
+}
+
+impl RuntimeServices {
+    pub unsafe fn new(services: *mut Services) -> Result<Self, Error> {
+        if services.is_null() || (*services).hdr.signature != Services::SIGNATURE {
+            return Err(Error::InvalidArgument("services"));
+        }
+
+        Ok(Self { services })
+    }
+
+    // The runtime services of the system table the crate was initialized with.
+
+    pub fn current() -> Result<Self, Error> {
+        unsafe { Self::new((*system::table()?).runtime_services) }
+    }
+
+    pub fn as_ptr(&self) -> *mut Services {
+        self.services
+    }
+
+    pub fn time(&self) -> Result<Time, Error> {
+        unsafe {
+            let mut time = mem::zeroed();
+
+            let status = ((*self.services).get_time)(&mut time, ptr::null_mut());
+
+            match status {
+                Status::SUCCESS => Ok(time),
+                Status::DEVICE_ERROR => Err(Error::DeviceError),
+                Status::UNSUPPORTED => Err(Error::NotSupported),
+                _ => Err(Error::UnexpectedStatus(status)),
+            }
+        }
+    }
+
+    pub fn set_time(&self, time: &Time) -> Result<(), Error> {
+        unsafe {
+            let mut time = *time;
+
+            let status = ((*self.services).set_time)(&mut time);
+
+            match status {
+                Status::SUCCESS => Ok(()),
+                Status::INVALID_PARAMETER => Err(Error::InvalidArgument("time")),
+                Status::DEVICE_ERROR => Err(Error::DeviceError),
+                Status::UNSUPPORTED => Err(Error::NotSupported),
+                _ => Err(Error::UnexpectedStatus(status)),
+            }
+        }
+    }
+
+    // The high 32 bits of the platform's monotonic counter. It is increased every time it is read
+    // and kept across resets.
+
+    pub fn next_high_monotonic_count(&self) -> Result<u32, Error> {
+        unsafe {
+            let mut high_count = 0;
+
+            let status = ((*self.services).get_next_high_monotonic_count)(&mut high_count);
+
+            match status {
+                Status::SUCCESS => Ok(high_count),
+                Status::DEVICE_ERROR => Err(Error::DeviceError),
+                Status::UNSUPPORTED => Err(Error::NotSupported),
+                _ => Err(Error::UnexpectedStatus(status)),
+            }
+        }
+    }
+
+    // Only returns if the firmware failed to reset the system. The caller decides what to do
+    // instead since it may still hold locks around the services.
+
+    pub fn reset(&self, reset_type: ResetType) {
+        unsafe {
+            ((*self.services).reset_system)(reset_type, Status::SUCCESS, 0, ptr::null_mut());
+        }
+    }
+
+    // Moves the runtime services to the virtual addresses given in the map. The map must contain
+    // every runtime region. This can only be done once after boot services are exited and while
+    // the firmware is still identity mapped. The services must be used at their new address
+    // afterwards so they are returned again with the table at its virtual address.
+
+    pub unsafe fn set_virtual_address_map(
+        self,
+        map: &mut [MemoryDescriptor],
+    ) -> Result<Self, Error> {
+        let physical_address = self.services as u64;
+
+        let virtual_address = map
+            .iter()
+            .find(|descriptor| {
+                let len = descriptor.number_of_pages * PAGE_SIZE as u64;
+                physical_address >= descriptor.physical_start
+                    && physical_address - descriptor.physical_start < len
+            })
+            .map(|descriptor| {
+                descriptor.virtual_start + (physical_address - descriptor.physical_start)
+            })
+            .ok_or(Error::InvalidArgument("map"))?;
+
+        let status = ((*self.services).set_virtual_address_map)(
+            map.len() * mem::size_of::<MemoryDescriptor>(),
+            mem::size_of::<MemoryDescriptor>(),
+            MemoryDescriptor::VERSION as u32,
+            map.as_mut_ptr(),
+        );
+
+        match status {
+            Status::SUCCESS => Ok(Self {
+                services: virtual_address as *mut Services,
+            }),
+            Status::UNSUPPORTED => Err(Error::NotSupported),
+            Status::INVALID_PARAMETER | Status::NO_MAPPING | Status::NOT_FOUND => {
+                Err(Error::InvalidArgument("map"))
+            }
+            _ => Err(Error::UnexpectedStatus(status)),
+        }
+    }
+}
+
+unsafe impl Send for RuntimeServices {}
@@ -8,6 +8,7 @@ use super::error::Error;
 use super::ffi::system;
 use super::ffi::{Handle, Status};
 use super::memory::MemoryMapKey;
+use core::ptr;
 
 //TODO Take another look at the use of static mut variables here. UEFI is single threaded but
 // can this be made safer?
@@ -75,3 +76,41 @@ pub fn exit(key: MemoryMapKey) -> Result<(), Error> {
         }
     }
 }
+
+pub fn stall(microseconds: usize) -> Result<(), Error> {
+    unsafe {
+        let system_table = &*table()?;
+
+        if system_table.boot_services.is_null() {
+            return Err(Error::BootServicesUnavailable);
+        }
+
+        let boot_services = &*system_table.boot_services;
+
+        match (boot_services.stall)(microseconds) {
+            Status::SUCCESS => Ok(()),
+            status => Err(Error::UnexpectedStatus(status)),
+        }
+    }
+}
+
+// Sets the watchdog timer to reset the system after the timeout. A timeout of 0 disables it.
+// Firmware arms a five minute watchdog before starting a boot option.
+
+pub fn set_watchdog_timer(seconds: usize) -> Result<(), Error> {
+    unsafe {
+        let system_table = &*table()?;
+
+        if system_table.boot_services.is_null() {
+            return Err(Error::BootServicesUnavailable);
+        }
+
+        let boot_services = &*system_table.boot_services;
+
+        match (boot_services.set_watchdog_timer)(seconds, 0, 0, ptr::null_mut()) {
+            Status::SUCCESS => Ok(()),
+            Status::UNSUPPORTED => Err(Error::NotSupported),
+            status => Err(Error::UnexpectedStatus(status)),
+        }
+    }
+}
@@ -0,0 +1,297 @@
+//**************************************************************************************************
+// variable.rs                                                                                     *
+// Copyright (c) 2021 The Verdure Project                                                          *
+// This code is made available under the MIT License.                                              *
+//**************************************************************************************************
+
+pub use crate::ffi::runtime::VariableAttributes;
+
+use crate::error::Error;
+use crate::ffi::runtime::Services;
+use crate::ffi::{Guid, Status};
+use crate::runtime::RuntimeServices;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::ffi::c_void;
+use core::mem;
+use ucs2::FromUcs2Buffer;
+
+pub const GLOBAL_VARIABLE: Guid = Services::GLOBAL_VARIABLE_GUID;
+
+// Bit of OsIndications that asks the firmware to stop at its setup UI on the next boot.
+
+const OS_INDICATIONS_BOOT_TO_FW_UI: u64 = 0x1;
+
+// Variables are found by their name together with the GUID of their vendor. Only variables with
+// the runtime access attribute can be used after boot services are exited.
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct VariableName {
+    pub name: String,
+    pub vendor: Guid,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Variable {
+    pub attributes: VariableAttributes,
+    pub data: Vec<u8>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct VariableStorageInfo {
+    pub maximum_storage_size: u64,
+    pub remaining_storage_size: u64,
+    pub maximum_variable_size: u64,
+}
+
+impl RuntimeServices {
+    pub fn variable(&self, name: &str, vendor: &Guid) -> Result<Variable, Error> {
+        let encoded_name = encode_name(name)?;
+
+        let mut data = Vec::new();
+        let mut attributes = VariableAttributes::empty();
+
+        unsafe {
+            loop {
+                let mut data_size = data.len();
+
+                let status = ((*self.as_ptr()).get_variable)(
+                    encoded_name.as_ptr(),
+                    vendor,
+                    &mut attributes,
+                    &mut data_size,
+                    data.as_mut_ptr() as *mut c_void,
+                );
+
+                match status {
+                    Status::SUCCESS => {
+                        data.truncate(data_size);
+                        return Ok(Variable { attributes, data });
+                    }
+                    Status::BUFFER_TOO_SMALL => data.resize(data_size, 0),
+                    _ => return Err(variable_error(status, name)),
+                }
+            }
+        }
+    }
+
+    // Writing empty data deletes the variable.
+
+    pub fn set_variable(
+        &self,
+        name: &str,
+        vendor: &Guid,
+        attributes: VariableAttributes,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        let encoded_name = encode_name(name)?;
+
+        unsafe {
+            let status = ((*self.as_ptr()).set_variable)(
+                encoded_name.as_ptr(),
+                vendor,
+                attributes,
+                data.len(),
+                data.as_ptr() as *const c_void,
+            );
+
+            match status {
+                Status::SUCCESS => Ok(()),
+                _ => Err(variable_error(status, name)),
+            }
+        }
+    }
+
+    pub fn delete_variable(&self, name: &str, vendor: &Guid) -> Result<(), Error> {
+        self.set_variable(name, vendor, VariableAttributes::empty(), &[])
+    }
+
+    pub fn variable_names(&self) -> VariableNameIterator {
+        VariableNameIterator {
+            services: self,
+            name: vec![0; 64],
+            vendor: Guid {
+                data_1: 0,
+                data_2: 0,
+                data_3: 0,
+                data_4: [0; 8],
+            },
+            is_finished: false,
+        }
+    }
+
+    pub fn query_variable_info(
+        &self,
+        attributes: VariableAttributes,
+    ) -> Result<VariableStorageInfo, Error> {
+        let mut info = VariableStorageInfo {
+            maximum_storage_size: 0,
+            remaining_storage_size: 0,
+            maximum_variable_size: 0,
+        };
+
+        unsafe {
+            let status = ((*self.as_ptr()).query_variable_info)(
+                attributes,
+                &mut info.maximum_storage_size,
+                &mut info.remaining_storage_size,
+                &mut info.maximum_variable_size,
+            );
+
+            match status {
+                Status::SUCCESS => Ok(info),
+                Status::INVALID_PARAMETER => Err(Error::InvalidArgument("attributes")),
+                Status::UNSUPPORTED => Err(Error::NotSupported),
+                _ => Err(Error::UnexpectedStatus(status)),
+            }
+        }
+    }
+
+    // The numbers of the Boot#### load options in the order the boot manager tries them.
+
+    pub fn boot_order(&self) -> Result<Vec<u16>, Error> {
+        let variable = self.variable("BootOrder", &GLOBAL_VARIABLE)?;
+
+        Ok(variable
+            .data
+            .chunks_exact(mem::size_of::<u16>())
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+            .collect())
+    }
+
+    // The number of the Boot#### load option the system was booted with.
+
+    pub fn boot_current(&self) -> Result<u16, Error> {
+        let variable = self.variable("BootCurrent", &GLOBAL_VARIABLE)?;
+
+        let bytes = variable
+            .data
+            .get(..mem::size_of::<u16>())
+            .ok_or(Error::UnexpectedEnd)?;
+
+        Ok(u16::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    // Makes the next boot stop at the firmware's setup UI. The system still has to be reset
+    // afterwards.
+
+    pub fn boot_to_firmware_setup(&self) -> Result<(), Error> {
+        if self.os_indications("OsIndicationsSupported")? & OS_INDICATIONS_BOOT_TO_FW_UI == 0 {
+            return Err(Error::NotSupported);
+        }
+
+        let indications = match self.os_indications("OsIndications") {
+            Ok(indications) => indications,
+            Err(Error::VariableNonExistent(_)) => 0,
+            Err(error) => return Err(error),
+        };
+
+        self.set_variable(
+            "OsIndications",
+            &GLOBAL_VARIABLE,
+            VariableAttributes::NON_VOLATILE
+                | VariableAttributes::BOOTSERVICE_ACCESS
+                | VariableAttributes::RUNTIME_ACCESS,
+            &(indications | OS_INDICATIONS_BOOT_TO_FW_UI).to_le_bytes(),
+        )
+    }
+
+    fn os_indications(&self, name: &str) -> Result<u64, Error> {
+        let variable = self.variable(name, &GLOBAL_VARIABLE)?;
+
+        let bytes = variable
+            .data
+            .get(..mem::size_of::<u64>())
+            .ok_or(Error::UnexpectedEnd)?;
+
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+// The firmware keeps its position in the list of variables through the last name it returned so
+// the name buffer is reused for every call.
+
+pub struct VariableNameIterator<'a> {
+    services: &'a RuntimeServices,
+    name: Vec<u16>,
+    vendor: Guid,
+    is_finished: bool,
+}
+
+impl<'a> Iterator for VariableNameIterator<'a> {
+    type Item = Result<VariableName, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_finished {
+            return None;
+        }
+
+        unsafe {
+            loop {
+                let mut name_size = self.name.len() * mem::size_of::<u16>();
+
+                let status = ((*self.services.as_ptr()).get_next_variable_name)(
+                    &mut name_size,
+                    self.name.as_mut_ptr(),
+                    &mut self.vendor,
+                );
+
+                match status {
+                    Status::SUCCESS => break,
+                    Status::BUFFER_TOO_SMALL => {
+                        let len = (name_size + 1) / mem::size_of::<u16>();
+                        self.name.resize(len, 0);
+                    }
+                    Status::NOT_FOUND => {
+                        self.is_finished = true;
+                        return None;
+                    }
+                    _ => {
+                        self.is_finished = true;
+
+                        return Some(Err(match status {
+                            Status::DEVICE_ERROR => Error::DeviceError,
+                            Status::UNSUPPORTED => Error::NotSupported,
+                            _ => Error::UnexpectedStatus(status),
+                        }));
+                    }
+                }
+            }
+        }
+
+        let len = self
+            .name
+            .iter()
+            .position(|&unit| unit == 0)
+            .unwrap_or_else(|| self.name.len());
+
+        Some(
+            String::from_usc2(&self.name[..len])
+                .map(|name| VariableName {
+                    name,
+                    vendor: self.vendor,
+                })
+                .map_err(|_| Error::InvalidArgument("name")),
+        )
+    }
+}
+
+fn encode_name(name: &str) -> Result<Box<[u16]>, Error> {
+    ucs2::encode_string_with_null(name).map_err(|_| Error::InvalidArgument("name"))
+}
+
+fn variable_error(status: Status, name: &str) -> Error {
+    match status {
+        Status::NOT_FOUND => Error::VariableNonExistent(String::from(name)),
+        Status::INVALID_PARAMETER => Error::InvalidArgument("name"),
+        Status::OUT_OF_RESOURCES => Error::VariableStorageFull,
+        Status::DEVICE_ERROR => Error::DeviceError,
+        Status::WRITE_PROTECTED => Error::ReadOnlyViolation,
+        Status::SECURITY_VIOLATION => Error::OperationDenied,
+        Status::UNSUPPORTED => Error::NotSupported,
+        _ => Error::UnexpectedStatus(status),
+    }
+}